@@ -0,0 +1,230 @@
+use std::env;
+use std::fmt::{Display, Formatter};
+
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use url::{form_urlencoded, Url};
+
+/// Sonarr manages series and Radarr movies, both through the same v3 API.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ArrKind {
+    Sonarr,
+    Radarr,
+}
+
+impl ArrKind {
+    fn resource(self) -> &'static str {
+        match self {
+            ArrKind::Sonarr => "series",
+            ArrKind::Radarr => "movie",
+        }
+    }
+
+    fn env_prefix(self) -> &'static str {
+        match self {
+            ArrKind::Sonarr => "SONARR",
+            ArrKind::Radarr => "RADARR",
+        }
+    }
+
+    /// Asks the app to search for the added item right away, so it doesn't wait for new releases only.
+    fn add_options(self) -> Value {
+        match self {
+            ArrKind::Sonarr => json!({ "monitor": "all", "searchForMissingEpisodes": true }),
+            ArrKind::Radarr => json!({ "searchForMovie": true }),
+        }
+    }
+}
+
+impl Display for ArrKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ArrKind::Sonarr => write!(f, "Sonarr"),
+            ArrKind::Radarr => write!(f, "Radarr"),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct QualityProfile {
+    pub id: u32,
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+struct RootFolder {
+    path: String,
+}
+
+pub struct ArrClient {
+    kind: ArrKind,
+    api_key: String,
+    base_url: Url,
+    client: Client,
+}
+
+impl ArrClient {
+    /// `None` if the app's base url isn't set, as adding series or movies is optional.
+    pub fn from_env(kind: ArrKind) -> Option<ArrClient> {
+        let base_url_env = format!("{}_BASE_URL", kind.env_prefix());
+        let api_key_env = format!("{}_API_KEY", kind.env_prefix());
+        let url_string = env::var(&base_url_env).ok()?;
+        Some(ArrClient {
+            kind,
+            api_key: env::var(&api_key_env)
+                .unwrap_or_else(|_| panic!("{api_key_env} env variable must be set when {base_url_env} is set")),
+            base_url: Url::parse(&url_string)
+                .unwrap_or_else(|err| panic!("Could not parse {}: {}: \"{}\"", base_url_env, err, url_string)),
+            client: Client::new(),
+        })
+    }
+
+    /// Series or movies matching the term, as the app describes them. Items already in the library have an `id`.
+    pub async fn lookup(&self, term: &str) -> reqwest::Result<Vec<Value>> {
+        let term: String = form_urlencoded::byte_serialize(term.as_bytes()).collect();
+        self.client.get(format!("{}api/v3/{}/lookup?apikey={}&term={}", self.base_url,
+                                self.kind.resource(), self.api_key, term))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Vec<Value>>()
+            .await
+    }
+
+    pub async fn quality_profiles(&self) -> reqwest::Result<Vec<QualityProfile>> {
+        self.client.get(format!("{}api/v3/qualityprofile?apikey={}", self.base_url, self.api_key))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Vec<QualityProfile>>()
+            .await
+    }
+
+    async fn root_folders(&self) -> reqwest::Result<Vec<RootFolder>> {
+        self.client.get(format!("{}api/v3/rootfolder?apikey={}", self.base_url, self.api_key))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Vec<RootFolder>>()
+            .await
+    }
+
+    /// Adds a looked up item to the app's first root folder, monitored and searched for right away.
+    pub async fn add(&self, item: &Value, quality_profile_id: u32) -> Result<(), String> {
+        let root_folder = self.root_folders()
+            .await
+            .map_err(|err| err.to_string())?
+            .into_iter()
+            .next()
+            .ok_or_else(|| format!("{} has no root folders", self.kind))?;
+        let mut item = item.clone();
+        item["qualityProfileId"] = quality_profile_id.into();
+        item["rootFolderPath"] = root_folder.path.into();
+        item["monitored"] = true.into();
+        item["addOptions"] = self.kind.add_options();
+        let response = self.client.post(format!("{}api/v3/{}?apikey={}", self.base_url, self.kind.resource(), self.api_key))
+            .header(CONTENT_TYPE, "application/json")
+            .json(&item)
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+        if response.status().is_success() {
+            Ok(())
+        } else {
+            Err(format!("{} {}", response.status(), response.text().await.unwrap_or_default()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::core::arr::{ArrClient, ArrKind};
+
+    fn client(mock_server: &MockServer, kind: ArrKind) -> ArrClient {
+        temp_env::with_vars(
+            [("SONARR_BASE_URL", Some(mock_server.uri())), ("SONARR_API_KEY", Some("key".to_string())),
+                ("RADARR_BASE_URL", Some(mock_server.uri())), ("RADARR_API_KEY", Some("key".to_string()))],
+            || ArrClient::from_env(kind).unwrap())
+    }
+
+    #[test]
+    fn not_configured() {
+        temp_env::with_var_unset("RADARR_BASE_URL", || assert!(ArrClient::from_env(ArrKind::Radarr).is_none()));
+    }
+
+    #[test]
+    #[should_panic(expected = "SONARR_API_KEY env variable must be set when SONARR_BASE_URL is set")]
+    fn panic_if_no_api_key() {
+        temp_env::with_vars([("SONARR_BASE_URL", Some("http://localhost:8989")), ("SONARR_API_KEY", None)],
+                            || ArrClient::from_env(ArrKind::Sonarr));
+    }
+
+    #[tokio::test]
+    async fn lookup() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/series/lookup"))
+            .and(query_param("apikey", "key"))
+            .and(query_param("term", "the office & co"))
+            .respond_with(ResponseTemplate::new(200)
+                .set_body_json(json!([{ "title": "The Office", "year": 2005, "tvdbId": 73244 }])))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let items = client(&mock_server, ArrKind::Sonarr).lookup("the office & co").await.unwrap();
+
+        assert_eq!(items, vec![json!({ "title": "The Office", "year": 2005, "tvdbId": 73244 })]);
+    }
+
+    #[tokio::test]
+    async fn add() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/rootfolder"))
+            .respond_with(ResponseTemplate::new(200)
+                .set_body_json(json!([{ "id": 1, "path": "/movies" }, { "id": 2, "path": "/other" }])))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v3/movie"))
+            .and(body_partial_json(json!({
+                "title": "Alien", "tmdbId": 348, "qualityProfileId": 4, "rootFolderPath": "/movies",
+                "monitored": true, "addOptions": { "searchForMovie": true }
+            })))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        client(&mock_server, ArrKind::Radarr)
+            .add(&json!({ "title": "Alien", "tmdbId": 348 }), 4)
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn add_rejected() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/rootfolder"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([{ "path": "/tv" }])))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v3/series"))
+            .respond_with(ResponseTemplate::new(400).set_body_string("already added"))
+            .mount(&mock_server)
+            .await;
+
+        let result = client(&mock_server, ArrKind::Sonarr).add(&json!({ "title": "The Office" }), 1).await;
+
+        assert_eq!(result, Err("400 Bad Request already added".to_string()));
+    }
+}
@@ -0,0 +1,145 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Stops calling a service after `threshold` failures in a row, so callers get an error right away
+/// instead of waiting for timeouts. Once `open_for` passes, the circuit is half-open: a single call is let through
+/// to check whether the service is back, a success closes the circuit and a failure opens it for another `open_for`.
+/// Other calls keep failing right away until the probe's result is known.
+pub struct CircuitBreaker {
+    threshold: u32,
+    open_for: Duration,
+    state: Mutex<State>,
+}
+
+struct State {
+    failures: u32,
+    opened_at: Option<Instant>,
+    probing: bool,
+}
+
+/// A call let through by the circuit breaker, whose result is passed to `record`.
+/// A probe dropped without a result, e.g. because its future was cancelled, lets another call probe.
+pub struct Call<'a> {
+    circuit_breaker: &'a CircuitBreaker,
+    probe: bool,
+}
+
+impl CircuitBreaker {
+    /// A `threshold` of 0 never opens the circuit.
+    pub fn new(threshold: u32, open_for: Duration) -> CircuitBreaker {
+        CircuitBreaker { threshold, open_for, state: Mutex::new(State { failures: 0, opened_at: None, probing: false }) }
+    }
+
+    /// A call if one may be made, which in the half-open state is the probe.
+    pub fn call(&self) -> Option<Call<'_>> {
+        let mut state = self.state.lock().unwrap();
+        let Some(opened_at) = state.opened_at else {
+            return Some(Call { circuit_breaker: self, probe: false });
+        };
+        if opened_at.elapsed() < self.open_for || state.probing {
+            return None;
+        }
+        state.probing = true;
+        Some(Call { circuit_breaker: self, probe: true })
+    }
+
+    fn record(&self, success: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.probing = false;
+        if success {
+            state.failures = 0;
+            state.opened_at = None;
+        } else {
+            state.failures += 1;
+            if self.threshold > 0 && state.failures >= self.threshold {
+                state.opened_at = Some(Instant::now());
+            }
+        }
+    }
+}
+
+impl Call<'_> {
+    pub fn record(mut self, success: bool) {
+        self.probe = false;
+        self.circuit_breaker.record(success);
+    }
+}
+
+impl Drop for Call<'_> {
+    fn drop(&mut self) {
+        if self.probe {
+            self.circuit_breaker.state.lock().unwrap().probing = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::core::circuit_breaker::CircuitBreaker;
+
+    fn record(circuit_breaker: &CircuitBreaker, success: bool) {
+        circuit_breaker.call().unwrap().record(success);
+    }
+
+    #[test]
+    fn opens_after_failures_in_a_row() {
+        let circuit_breaker = CircuitBreaker::new(2, Duration::from_secs(60));
+
+        record(&circuit_breaker, false);
+        record(&circuit_breaker, true);
+        record(&circuit_breaker, false);
+        assert!(circuit_breaker.call().is_some());
+        record(&circuit_breaker, false);
+        assert!(circuit_breaker.call().is_none());
+    }
+
+    #[test]
+    fn lets_calls_through_after_a_while() {
+        let circuit_breaker = CircuitBreaker::new(1, Duration::ZERO);
+
+        record(&circuit_breaker, false);
+        record(&circuit_breaker, true);
+        assert!(circuit_breaker.call().is_some());
+        assert!(circuit_breaker.call().is_some());
+    }
+
+    #[test]
+    fn lets_one_probe_through_when_half_open() {
+        let circuit_breaker = CircuitBreaker::new(1, Duration::from_millis(50));
+
+        record(&circuit_breaker, false);
+        assert!(circuit_breaker.call().is_none());
+        std::thread::sleep(Duration::from_millis(50));
+        let probe = circuit_breaker.call().unwrap();
+        assert!(circuit_breaker.call().is_none());
+        probe.record(false);
+        assert!(circuit_breaker.call().is_none());
+        std::thread::sleep(Duration::from_millis(50));
+        let probe = circuit_breaker.call().unwrap();
+        assert!(circuit_breaker.call().is_none());
+        probe.record(true);
+        assert!(circuit_breaker.call().is_some());
+        assert!(circuit_breaker.call().is_some());
+    }
+
+    #[test]
+    fn cancelled_probe_lets_another_one_through() {
+        let circuit_breaker = CircuitBreaker::new(1, Duration::ZERO);
+
+        record(&circuit_breaker, false);
+        let probe = circuit_breaker.call().unwrap();
+        assert!(circuit_breaker.call().is_none());
+        drop(probe);
+        assert!(circuit_breaker.call().is_some());
+    }
+
+    #[test]
+    fn disabled() {
+        let circuit_breaker = CircuitBreaker::new(0, Duration::from_secs(60));
+
+        (0..10).for_each(|_| record(&circuit_breaker, false));
+        assert!(circuit_breaker.call().is_some());
+    }
+}
@@ -1,10 +1,13 @@
+use std::collections::HashSet;
 use std::sync::Arc;
 
 use derive_more::Display;
 use serde::Deserialize;
-use tokio::task;
+use tokio::task::JoinSet;
 
 use crate::core::downloads_tracker::DownloadsTracker;
+use crate::core::response::Response;
+use crate::core::traits::notifier::NotificationChannels;
 use crate::core::traits::sender::Sender;
 
 #[derive(Deserialize, Display)]
@@ -14,17 +17,45 @@ pub struct CompletionRequest {
     name: String,
 }
 
+/// Notifies everyone who requested the download: in the chat it was requested from,
+/// and through every notification channel configured for the user.
 pub async fn notify(request: CompletionRequest,
                     downloads_tracker: Arc<DownloadsTracker>,
-                    sender: Arc<dyn Sender>) {
+                    sender: Arc<dyn Sender>,
+                    notification_channels: Arc<NotificationChannels>) {
     log::info!("Received download completion notification for {}", request);
+    let mut notifications = JoinSet::new();
+    let mut notified_chats = HashSet::new();
     for user in downloads_tracker.remove(request.hash).iter() {
-        let sender = sender.clone();
         let download_name = request.name.clone();
         let chat_id = user.destination;
         let locale = user.locale.clone();
-        task::spawn(async move {
-            match sender.send_plain_message(chat_id, &t!("download_complete", locale = &locale, name = download_name)).await {
+        let message = t!("download_complete", locale = &locale, name = download_name).to_string();
+        for notifier in notification_channels.get(&user.source).into_iter().flatten() {
+            let notifier = notifier.clone();
+            let title = t!("download_complete_title", locale = &locale).to_string();
+            let message = message.clone();
+            let user_id = user.source;
+            let download_name = download_name.clone();
+            notifications.spawn(async move {
+                match notifier.notify(&title, &message).await {
+                    Ok(_) => {
+                        log::info!("userId {} | Sent download complete notification for \"{}\" to a notification channel",
+                            user_id, download_name);
+                    }
+                    Err(err) => {
+                        log::error!("userId {} | Could not send download complete notification for \"{}\" to a notification channel: {:?}",
+                            user_id, download_name, err);
+                    }
+                }
+            });
+        }
+        if !notified_chats.insert(chat_id) {
+            continue;
+        }
+        let sender = sender.clone();
+        notifications.spawn(async move {
+            match sender.send(chat_id, &Response::Notice(message)).await {
                 Ok(_) => {
                     log::info!("userId {} | Sent download complete notification for \"{}\"", chat_id, download_name);
                 }
@@ -35,4 +66,43 @@ pub async fn notify(request: CompletionRequest,
             };
         });
     }
+    notifications.join_all().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use mockall::predicate::eq;
+
+    use crate::core::completion::{notify, CompletionRequest};
+    use crate::core::downloads_tracker::DownloadsTracker;
+    use crate::core::response::Response;
+    use crate::core::traits::notifier::{MockNotifier, Notifier};
+    use crate::core::traits::sender::MockSender;
+
+    #[tokio::test]
+    async fn notifies_chat_once_and_user_channels() {
+        let downloads_tracker = Arc::new(DownloadsTracker::new());
+        downloads_tracker.add("hash".to_string(), 1, 10, "en".into());
+        downloads_tracker.add("hash".to_string(), 2, 10, "en".into());
+        let mut sender = MockSender::new();
+        sender.expect_send()
+            .withf(|destination, response|
+                *destination == 10 && matches!(response, Response::Notice(message) if message == "Downloaded \"Ubuntu\""))
+            .times(1)
+            .returning(|_, _| Box::pin(async { Ok(()) }));
+        let mut notifier = MockNotifier::new();
+        notifier.expect_notify()
+            .with(eq("Download complete"), eq("Downloaded \"Ubuntu\""))
+            .times(1)
+            .returning(|_, _| Box::pin(async { Ok(()) }));
+        let notifier: Arc<dyn Notifier> = Arc::new(notifier);
+
+        notify(CompletionRequest { hash: "hash".to_string(), name: "Ubuntu".to_string() },
+               downloads_tracker,
+               Arc::new(sender),
+               Arc::new(HashMap::from([(2, vec![notifier])]))).await;
+    }
 }
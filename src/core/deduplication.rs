@@ -0,0 +1,150 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use hightorrent::MagnetLink;
+
+use crate::core::prowlarr::SearchResult;
+
+#[derive(PartialEq, Eq, Hash)]
+enum ReleaseKey {
+    InfoHash(String),
+    TitleAndSize(String, u128),
+}
+
+/// Merges copies of a release carried by several indexers into one result, keeping the order releases first appear in.
+/// Copies are recognized by their info hashes or, when a hash is unknown, by their normalized titles and sizes.
+pub fn deduplicate(results: Vec<SearchResult>) -> Vec<SearchResult> {
+    let mut positions: HashMap<ReleaseKey, usize> = HashMap::new();
+    let mut releases: Vec<Vec<SearchResult>> = Vec::new();
+    for result in results {
+        let position = *positions.entry(release_key(&result))
+            .or_insert_with(|| {
+                releases.push(Vec::new());
+                releases.len() - 1
+            });
+        releases[position].push(result);
+    }
+    releases.into_iter()
+        .filter_map(merge)
+        .collect()
+}
+
+/// Keeps the best seeded copy, as its link is the one to download, with the names of the other indexers in
+/// `other_indexers`. Peers are the maximum of the copies, since swarms of a release overlap, while grabs are summed.
+fn merge(mut copies: Vec<SearchResult>) -> Option<SearchResult> {
+    let best = copies.iter()
+        .enumerate()
+        .min_by_key(|(_, copy)| Reverse(copy.seeders))
+        .map(|(position, _)| position)?;
+    let mut merged = copies.remove(best);
+    for copy in copies {
+        merged.leechers = merged.leechers.max(copy.leechers);
+        merged.grabs = match (merged.grabs, copy.grabs) {
+            (Some(grabs), Some(other)) => Some(grabs + other),
+            (grabs, other) => grabs.or(other),
+        };
+        for indexer in copy.indexer.into_iter().chain(copy.other_indexers) {
+            if merged.indexer.as_ref() != Some(&indexer) && !merged.other_indexers.contains(&indexer) {
+                merged.other_indexers.push(indexer);
+            }
+        }
+    }
+    Some(merged)
+}
+
+fn release_key(result: &SearchResult) -> ReleaseKey {
+    result.info_hash.clone()
+        .or_else(|| result.magnet_url.as_ref()
+            .and_then(|url| MagnetLink::new(url).ok())
+            .map(|magnet| magnet.hash().to_string()))
+        .map(|hash| ReleaseKey::InfoHash(hash.to_lowercase()))
+        .unwrap_or_else(|| ReleaseKey::TitleAndSize(normalize(&result.title), result.size))
+}
+
+/// Lower-cases a title and drops punctuation, so `Ubuntu.22.04 [x64]` and `ubuntu 22 04 x64` are the same.
+fn normalize(title: &str) -> String {
+    title.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use crate::core::deduplication::deduplicate;
+    use crate::core::prowlarr::{Protocol, SearchResult};
+
+    fn search_result(indexer: &str, title: &str, seeders: u32) -> SearchResult {
+        SearchResult {
+            guid: format!("{indexer}/{title}"),
+            indexer_id: 1,
+            indexer: Some(indexer.to_string()),
+            title: title.to_string(),
+            size: 1234567,
+            publish_date: DateTime::from_timestamp(1431648000, 0).unwrap(),
+            download_url: None,
+            magnet_url: None,
+            info_url: "http://localhost/ubuntu".to_string(),
+            seeders,
+            leechers: seeders / 2,
+            grabs: Some(100),
+            categories: vec![],
+            indexer_flags: vec![],
+            imdb_id: 0,
+            info_hash: None,
+            protocol: Protocol::Torrent,
+            other_indexers: vec![],
+        }
+    }
+
+    #[test]
+    fn same_title_and_size() {
+        let results = vec![
+            search_result("A", "Ubuntu.22.04 [x64]", 5),
+            search_result("B", "Debian 12", 3),
+            search_result("B", "ubuntu 22 04 x64", 20),
+            search_result("C", "Ubuntu 22.04 x64", 10),
+        ];
+
+        let results = deduplicate(results);
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].guid, "B/ubuntu 22 04 x64");
+        assert_eq!(results[0].seeders, 20);
+        assert_eq!(results[0].leechers, 10);
+        assert_eq!(results[0].grabs, Some(300));
+        assert_eq!(results[0].other_indexers, vec!["A", "C"]);
+        assert_eq!(results[1].title, "Debian 12");
+    }
+
+    #[test]
+    fn same_info_hash() {
+        let results = vec![
+            SearchResult { info_hash: Some("C811B41641A09D192B8ED81B14064FFF55D85CE3".to_string()),
+                ..search_result("A", "Ubuntu 22.04", 5) },
+            SearchResult { magnet_url: Some("magnet:?xt=urn:btih:c811b41641a09d192b8ed81b14064fff55d85ce3".to_string()),
+                ..search_result("B", "Ubuntu Jammy", 3) },
+        ];
+
+        let results = deduplicate(results);
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].indexer, Some("A".to_string()));
+        assert_eq!(results[0].other_indexers, vec!["B"]);
+    }
+
+    #[test]
+    fn different_sizes_or_hashes() {
+        let results = vec![
+            search_result("A", "Ubuntu 22.04", 5),
+            SearchResult { size: 1, ..search_result("B", "Ubuntu 22.04", 5) },
+            SearchResult { info_hash: Some("1".to_string()), ..search_result("C", "Ubuntu 22.04", 5) },
+            SearchResult { info_hash: Some("2".to_string()), ..search_result("D", "Ubuntu 22.04", 5) },
+        ];
+
+        assert_eq!(deduplicate(results).len(), 4);
+    }
+}
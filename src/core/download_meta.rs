@@ -1,15 +1,39 @@
 use async_trait::async_trait;
 use bytes::Bytes;
+use hightorrent::{MagnetLink, TorrentFile};
 #[cfg(test)]
 use mockall::automock;
 
+use crate::core::prowlarr::ProwlarrError;
+
+#[derive(Clone, PartialEq, Debug)]
 pub enum DownloadMeta {
     MagnetLink(String),
     TorrentFile(Bytes),
+    NzbFile(Bytes),
+}
+
+impl DownloadMeta {
+    /// Info hash of a torrent, which download clients report completion by.
+    pub fn torrent_hash(&self) -> Result<String, String> {
+        match self {
+            DownloadMeta::MagnetLink(link) =>
+                Ok(MagnetLink::new(link)
+                    .map_err(|err| err.to_string())?
+                    .hash()
+                    .to_string()),
+            DownloadMeta::TorrentFile(torrent_file) =>
+                Ok(TorrentFile::from_slice(torrent_file.as_ref())
+                    .map_err(|err| err.to_string())?
+                    .hash()
+                    .to_string()),
+            DownloadMeta::NzbFile(_) => Err("An .nzb file has no torrent hash".to_string()),
+        }
+    }
 }
 
 #[async_trait]
 #[cfg_attr(test, automock)]
 pub trait DownloadMetaProvider {
-    async fn get_download_meta(&self, download_url: &str) -> Result<DownloadMeta, String>;
+    async fn get_download_meta(&self, download_url: &str) -> Result<DownloadMeta, ProwlarrError>;
 }
@@ -0,0 +1,175 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use byte_unit::Byte;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::core::prowlarr::SearchResult;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum RuleError {
+    #[error("unknown rule \"{0}\", expected seeders>=N, size>=SIZE, size<=SIZE, size=MIN-MAX, quality=TAG[,TAG], +keyword or -keyword")]
+    Unknown(String),
+    #[error("\"{0}\" isn't a number")]
+    Number(String),
+    #[error("\"{0}\" isn't a size, e.g. 700MB or 4GB")]
+    Size(String),
+}
+
+/// Which new results of a saved search are downloaded automatically, e.g.
+/// `seeders>=5 size=1GB-20GB quality=1080p,2160p +remux -cam`.
+/// A result has to satisfy every rule, and have at least one of the quality tags if any are given.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DownloadRules {
+    min_seeders: Option<u32>,
+    min_size: Option<u128>,
+    max_size: Option<u128>,
+    qualities: Vec<String>,
+    required: Vec<String>,
+    forbidden: Vec<String>,
+}
+
+impl DownloadRules {
+    pub fn matches(&self, search_result: &SearchResult) -> bool {
+        let words = title_words(&search_result.title);
+        self.min_seeders.is_none_or(|min_seeders| search_result.seeders >= min_seeders)
+            && self.min_size.is_none_or(|min_size| search_result.size >= min_size)
+            && self.max_size.is_none_or(|max_size| search_result.size <= max_size)
+            && (self.qualities.is_empty() || self.qualities.iter().any(|quality| words.contains(quality)))
+            && self.required.iter().all(|keyword| words.contains(keyword))
+            && !self.forbidden.iter().any(|keyword| words.contains(keyword))
+    }
+}
+
+impl FromStr for DownloadRules {
+    type Err = RuleError;
+
+    fn from_str(rules: &str) -> Result<Self, Self::Err> {
+        let mut parsed = DownloadRules::default();
+        for rule in rules.split_whitespace() {
+            let lowercase = rule.to_lowercase();
+            if let Some(seeders) = lowercase.strip_prefix("seeders>=") {
+                parsed.min_seeders = Some(seeders.parse().map_err(|_| RuleError::Number(seeders.to_string()))?);
+            } else if let Some(size) = lowercase.strip_prefix("size>=") {
+                parsed.min_size = Some(parse_size(size)?);
+            } else if let Some(size) = lowercase.strip_prefix("size<=") {
+                parsed.max_size = Some(parse_size(size)?);
+            } else if let Some((min, max)) = lowercase.strip_prefix("size=").and_then(|sizes| sizes.split_once('-')) {
+                parsed.min_size = Some(parse_size(min)?);
+                parsed.max_size = Some(parse_size(max)?);
+            } else if let Some(qualities) = lowercase.strip_prefix("quality=") {
+                parsed.qualities.extend(qualities.split(',').filter(|quality| !quality.is_empty()).map(str::to_string));
+            } else if let Some(keyword) = lowercase.strip_prefix('+').filter(|keyword| !keyword.is_empty()) {
+                parsed.required.push(keyword.to_string());
+            } else if let Some(keyword) = lowercase.strip_prefix('-').filter(|keyword| !keyword.is_empty()) {
+                parsed.forbidden.push(keyword.to_string());
+            } else {
+                return Err(RuleError::Unknown(rule.to_string()));
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+impl Display for DownloadRules {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let mut rules: Vec<String> = Vec::new();
+        rules.extend(self.min_seeders.map(|seeders| format!("seeders>={seeders}")));
+        rules.extend(self.min_size.map(|size| format!("size>={}", format_size(size))));
+        rules.extend(self.max_size.map(|size| format!("size<={}", format_size(size))));
+        if !self.qualities.is_empty() {
+            rules.push(format!("quality={}", self.qualities.join(",")));
+        }
+        rules.extend(self.required.iter().map(|keyword| format!("+{keyword}")));
+        rules.extend(self.forbidden.iter().map(|keyword| format!("-{keyword}")));
+        write!(f, "{}", rules.join(" "))
+    }
+}
+
+fn parse_size(size: &str) -> Result<u128, RuleError> {
+    Byte::parse_str(size, true)
+        .map(Byte::as_u128)
+        .map_err(|_| RuleError::Size(size.to_string()))
+}
+
+fn format_size(size: u128) -> String {
+    Byte::from_u128(size)
+        .map(|size| size.get_appropriate_unit(byte_unit::UnitType::Decimal))
+        .map(|size| format!("{size:#}").replace(' ', ""))
+        .unwrap_or_else(|| size.to_string())
+}
+
+/// Lower-cased words of a title, so `+x264` matches both `Movie.x264` and `Movie [X264]`.
+fn title_words(title: &str) -> Vec<String> {
+    title.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use crate::core::download_rules::{DownloadRules, RuleError};
+    use crate::core::prowlarr::{Protocol, SearchResult};
+
+    fn search_result(title: &str, seeders: u32, size: u128) -> SearchResult {
+        SearchResult {
+            guid: "guid".to_string(),
+            indexer_id: 1,
+            indexer: None,
+            title: title.to_string(),
+            size,
+            publish_date: DateTime::from_timestamp(1431648000, 0).unwrap(),
+            download_url: None,
+            magnet_url: None,
+            info_url: "http://localhost/movie".to_string(),
+            seeders,
+            leechers: 0,
+            grabs: None,
+            categories: vec![],
+            indexer_flags: vec![],
+            imdb_id: 0,
+            info_hash: None,
+            protocol: Protocol::Torrent,
+            other_indexers: vec![],
+        }
+    }
+
+    #[test]
+    fn every_rule_has_to_match() {
+        let rules: DownloadRules = "seeders>=5 size=1GB-20GB quality=1080p,2160p +remux -cam".parse().unwrap();
+
+        assert!(rules.matches(&search_result("Movie.2023.1080p.REMUX", 5, 10_000_000_000)));
+        assert!(rules.matches(&search_result("Movie (2023) [2160p] Remux", 50, 1_000_000_000)));
+        assert!(!rules.matches(&search_result("Movie.2023.1080p.REMUX", 4, 10_000_000_000)));
+        assert!(!rules.matches(&search_result("Movie.2023.1080p.REMUX", 5, 30_000_000_000)));
+        assert!(!rules.matches(&search_result("Movie.2023.1080p.REMUX", 5, 900_000_000)));
+        assert!(!rules.matches(&search_result("Movie.2023.720p.REMUX", 5, 10_000_000_000)));
+        assert!(!rules.matches(&search_result("Movie.2023.1080p.WEB", 5, 10_000_000_000)));
+        assert!(!rules.matches(&search_result("Movie.2023.1080p.REMUX.CAM", 5, 10_000_000_000)));
+    }
+
+    #[test]
+    fn no_rules_match_anything() {
+        let rules: DownloadRules = "".parse().unwrap();
+
+        assert!(rules.matches(&search_result("Movie", 0, 0)));
+    }
+
+    #[test]
+    fn display() {
+        let rules: DownloadRules = "-cam size<=4GB SEEDERS>=3 +Remux".parse().unwrap();
+
+        assert_eq!(rules.to_string(), "seeders>=3 size<=4GB +remux -cam");
+    }
+
+    #[test]
+    fn invalid_rules() {
+        assert_eq!("seeders>=many".parse::<DownloadRules>(), Err(RuleError::Number("many".to_string())));
+        assert_eq!("size>=big".parse::<DownloadRules>(), Err(RuleError::Size("big".to_string())));
+        assert_eq!("year=2023".parse::<DownloadRules>(), Err(RuleError::Unknown("year=2023".to_string())));
+    }
+}
@@ -0,0 +1,194 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use tokio::time::interval;
+
+use crate::core::input_handler::InputHandler;
+use crate::core::traits::input::{Destination, Locale};
+
+const HEALTH_ALERT_CHATS_ENV: &str = "HEALTH_ALERT_CHATS";
+const HEALTH_INTERVAL_MINUTES_ENV: &str = "HEALTH_INTERVAL_MINUTES";
+const DEFAULT_HEALTH_INTERVAL_MINUTES: u64 = 15;
+
+/// State of Prowlarr and of the indexers searches are sent to,
+/// to tell whether searches come back empty because indexers are failing.
+pub struct Health {
+    pub version: String,
+    /// Problems found by Prowlarr's own health checks.
+    pub issues: Vec<HealthIssue>,
+    pub indexers: Vec<IndexerHealth>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct HealthIssue {
+    /// `warning` or `error`.
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub message: String,
+}
+
+pub struct IndexerHealth {
+    pub name: String,
+    /// Whether it's enabled in Prowlarr, indexers disabled there on purpose aren't a problem.
+    pub enabled: bool,
+    /// Prowlarr stops querying an indexer for a while after it fails, with growing pauses.
+    pub disabled_till: Option<DateTime<Utc>>,
+}
+
+impl Health {
+    /// Enabled indexers that Prowlarr doesn't query at `now` after their failures.
+    pub fn failing(&self, now: DateTime<Utc>) -> impl Iterator<Item = &IndexerHealth> {
+        self.indexers.iter()
+            .filter(move |indexer| indexer.enabled && indexer.disabled_till.is_some_and(|till| till > now))
+    }
+
+    /// Enabled indexers, including failing ones, which are the indexers a search is sent to.
+    pub fn enabled_count(&self) -> usize {
+        self.indexers.iter().filter(|indexer| indexer.enabled).count()
+    }
+
+    /// Health check messages and failing indexers, sorted so that the same problems compare equal.
+    pub fn problems(&self, now: DateTime<Utc>) -> Vec<String> {
+        let mut problems: Vec<String> = self.issues.iter()
+            .map(|issue| issue.message.clone())
+            .chain(self.failing(now).map(|indexer| indexer.name.clone()))
+            .collect();
+        problems.sort_unstable();
+        problems
+    }
+
+    /// Localized version, indexer counts, failing and disabled indexers and health check messages, one per line.
+    pub fn summary(&self, locale: &str, now: DateTime<Utc>) -> String {
+        let failing: Vec<String> = self.failing(now)
+            .map(|indexer| match indexer.disabled_till {
+                Some(till) => format!("{} ({})", indexer.name, t!("health_paused_until", locale = locale,
+                    time = till.format("%Y-%m-%d %H:%M UTC"))),
+                None => indexer.name.clone(),
+            })
+            .collect();
+        let disabled: Vec<&str> = self.indexers.iter()
+            .filter(|indexer| !indexer.enabled)
+            .map(|indexer| indexer.name.as_str())
+            .collect();
+        let mut lines = vec![
+            t!("health_version", locale = locale, version = self.version).to_string(),
+            t!("health_indexers", locale = locale,
+                enabled = self.enabled_count(), failing = failing.len(), disabled = disabled.len()).to_string(),
+        ];
+        if !failing.is_empty() {
+            lines.push(t!("health_failing", locale = locale, indexers = failing.join(", ")).to_string());
+        }
+        if !disabled.is_empty() {
+            lines.push(t!("health_disabled", locale = locale, indexers = disabled.join(", ")).to_string());
+        }
+        lines.extend(self.issues.iter().map(|issue| format!("{}: {}", issue.kind, issue.message)));
+        if failing.is_empty() && self.issues.is_empty() {
+            lines.push(t!("health_ok", locale = locale).to_string());
+        }
+        lines.join("\n")
+    }
+}
+
+/// Chats to alert when Prowlarr's health changes, read from `HEALTH_ALERT_CHATS`
+/// with ids converted by the front-end's `chat_id`.
+pub fn alert_chats_from_env(chat_id: impl Fn(&str) -> Option<Destination>) -> Vec<Destination> {
+    std::env::var(HEALTH_ALERT_CHATS_ENV)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|chat| !chat.is_empty())
+        .map(|chat| chat_id(chat)
+            .unwrap_or_else(|| panic!("Could not parse {HEALTH_ALERT_CHATS_ENV}: unexpected chat id \"{chat}\"")))
+        .collect()
+}
+
+/// Checks Prowlarr's health every `HEALTH_INTERVAL_MINUTES` and alerts `chats` in `locale` when its problems change.
+/// Runs without chats to alert too, keeping the number of indexers shown while searching up to date.
+pub async fn run(input_handler: Arc<InputHandler>, chats: Vec<Destination>, locale: Locale) {
+    let mut ticks = interval(Duration::from_secs(60 * get_interval_minutes()));
+    let mut problems = Vec::new();
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            _ = ticks.tick() => problems = input_handler.alert_on_health_change(&chats, &locale, problems).await,
+        }
+    }
+}
+
+fn get_interval_minutes() -> u64 {
+    match std::env::var(HEALTH_INTERVAL_MINUTES_ENV) {
+        Ok(val) => val.parse()
+            .ok()
+            .filter(|minutes| *minutes > 0)
+            .unwrap_or_else(|| panic!("{HEALTH_INTERVAL_MINUTES_ENV} must be a positive number, got \"{val}\"")),
+        Err(_) => DEFAULT_HEALTH_INTERVAL_MINUTES,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeDelta};
+
+    use crate::core::health::{alert_chats_from_env, get_interval_minutes, Health, HealthIssue, IndexerHealth};
+
+    fn health() -> Health {
+        let now = DateTime::from_timestamp(1431648000, 0).unwrap();
+        Health {
+            version: "1.24.3".to_string(),
+            issues: vec![HealthIssue {
+                kind: "warning".to_string(),
+                message: "Indexers unavailable due to failures: RuTracker".to_string(),
+            }],
+            indexers: vec![
+                IndexerHealth { name: "RuTracker".to_string(), enabled: true, disabled_till: Some(now + TimeDelta::minutes(30)) },
+                IndexerHealth { name: "Nyaa".to_string(), enabled: false, disabled_till: None },
+                IndexerHealth { name: "1337x".to_string(), enabled: true, disabled_till: Some(now - TimeDelta::minutes(30)) },
+            ],
+        }
+    }
+
+    #[test]
+    fn summary_of_failing_indexers() {
+        let now = DateTime::from_timestamp(1431648000, 0).unwrap();
+
+        assert_eq!(health().summary("en", now), "Prowlarr 1.24.3\n\
+            Indexers: 2 enabled, 1 failing, 1 disabled\n\
+            Failing: RuTracker (paused until 2015-05-15 00:30 UTC)\n\
+            Disabled: Nyaa\n\
+            warning: Indexers unavailable due to failures: RuTracker");
+        assert_eq!(health().problems(now), vec!["Indexers unavailable due to failures: RuTracker", "RuTracker"]);
+    }
+
+    #[test]
+    fn summary_of_recovered_indexers() {
+        let later = DateTime::from_timestamp(1431648000, 0).unwrap() + TimeDelta::hours(1);
+        let health = Health { issues: vec![], ..health() };
+
+        assert_eq!(health.summary("en", later), "Prowlarr 1.24.3\n\
+            Indexers: 2 enabled, 0 failing, 1 disabled\n\
+            Disabled: Nyaa\n\
+            All indexers are working.");
+        assert!(health.problems(later).is_empty());
+    }
+
+    #[test]
+    fn alert_chats() {
+        temp_env::with_var_unset("HEALTH_ALERT_CHATS", || assert!(alert_chats_from_env(|chat| chat.parse().ok()).is_empty()));
+        temp_env::with_var("HEALTH_ALERT_CHATS", Some("1000, -1002000"),
+                           || assert_eq!(alert_chats_from_env(|chat| chat.parse().ok()), vec![1000, -1002000]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Could not parse HEALTH_ALERT_CHATS: unexpected chat id \"admin\"")]
+    fn bad_alert_chat() {
+        temp_env::with_var("HEALTH_ALERT_CHATS", Some("admin"), || alert_chats_from_env(|chat| chat.parse().ok()));
+    }
+
+    #[test]
+    fn interval() {
+        temp_env::with_var_unset("HEALTH_INTERVAL_MINUTES", || assert_eq!(get_interval_minutes(), 15));
+        temp_env::with_var("HEALTH_INTERVAL_MINUTES", Some("5"), || assert_eq!(get_interval_minutes(), 5));
+    }
+}
@@ -0,0 +1,197 @@
+use url::{form_urlencoded, Url};
+
+#[derive(Debug, PartialEq)]
+enum MediaId {
+    /// `tt` followed by digits, e.g. tt0111161.
+    Imdb(String),
+    Tmdb(u32),
+    Tvdb(u32),
+}
+
+/// Whether an id is known to be of a movie or of a series, which IMDb ids and links don't tell.
+#[derive(Debug, PartialEq)]
+enum Kind {
+    Unknown,
+    Movie,
+    Series,
+}
+
+/// A search for a movie or an episode by its IMDb, TMDb or TVDb id, which finds releases of exactly it,
+/// unlike a search by a title, which also finds its namesakes.
+#[derive(Debug, PartialEq)]
+pub struct IdSearch {
+    id: MediaId,
+    kind: Kind,
+    season: Option<u32>,
+    episode: Option<u32>,
+}
+
+impl IdSearch {
+    /// Recognizes an id (`tt0111161`, `tmdb:278`, `tvdb:81189`) or a link to an IMDb, TMDb or TVDb page,
+    /// optionally followed by a season and an episode such as `S02E03`. Anything else is a search by a title.
+    pub fn parse(query: &str) -> Option<IdSearch> {
+        let mut words = query.split_whitespace();
+        let mut search = parse_id(words.next()?)?;
+        if let Some(word) = words.next() {
+            let (season, episode) = parse_episode(word)?;
+            search.kind = Kind::Series;
+            search.season = Some(season);
+            search.episode = episode.or(search.episode);
+        }
+        if words.next().is_some() {
+            return None;
+        }
+        Some(search)
+    }
+
+    /// Prowlarr's search type, which picks the categories to search in,
+    /// or the generic search in all of them if the kind of the id is unknown.
+    pub fn search_type(&self) -> &'static str {
+        match self.kind {
+            Kind::Unknown => "search",
+            Kind::Movie => "movie",
+            Kind::Series => "tvsearch",
+        }
+    }
+
+    /// Parameters of Prowlarr's search, which takes ids as `{Name:value}` tokens of the query.
+    pub fn prowlarr_params(&self) -> String {
+        let id = match &self.id {
+            MediaId::Imdb(id) => format!("{{ImdbId:{id}}}"),
+            MediaId::Tmdb(id) => format!("{{TmdbId:{id}}}"),
+            MediaId::Tvdb(id) => format!("{{TvdbId:{id}}}"),
+        };
+        let query = [Some(id),
+            self.season.map(|season| format!("{{Season:{season}}}")),
+            self.episode.map(|episode| format!("{{Episode:{episode}}}"))]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("&type={}&query={}", self.search_type(), form_urlencoded::byte_serialize(query.as_bytes()).collect::<String>())
+    }
+}
+
+fn parse_id(word: &str) -> Option<IdSearch> {
+    let word = word.to_lowercase();
+    if let Some(id) = imdb_id(&word) {
+        return Some(IdSearch { id: MediaId::Imdb(id), kind: Kind::Unknown, season: None, episode: None });
+    }
+    if let Some(id) = word.strip_prefix("tmdb:") {
+        return Some(IdSearch { id: MediaId::Tmdb(id.parse().ok()?), kind: Kind::Unknown, season: None, episode: None });
+    }
+    if let Some(id) = word.strip_prefix("tvdb:") {
+        return Some(IdSearch { id: MediaId::Tvdb(id.parse().ok()?), kind: Kind::Series, season: None, episode: None });
+    }
+    parse_link(&Url::parse(&word).ok()?)
+}
+
+fn imdb_id(word: &str) -> Option<String> {
+    word.strip_prefix("tt")
+        .filter(|digits| digits.len() >= 7 && digits.chars().all(|c| c.is_ascii_digit()))
+        .map(|_| word.to_string())
+}
+
+fn parse_link(url: &Url) -> Option<IdSearch> {
+    let host = url.host_str()?;
+    let segments: Vec<&str> = url.path_segments()?.filter(|segment| !segment.is_empty()).collect();
+    if host == "imdb.com" || host.ends_with(".imdb.com") {
+        let id = segments.iter().find_map(|segment| imdb_id(segment))?;
+        Some(IdSearch { id: MediaId::Imdb(id), kind: Kind::Unknown, season: None, episode: None })
+    } else if host == "themoviedb.org" || host.ends_with(".themoviedb.org") {
+        // e.g. /movie/278-the-shawshank-redemption or /tv/1396-breaking-bad/season/2/episode/3
+        let kind = match segments.first() {
+            Some(&"movie") => Kind::Movie,
+            Some(&"tv") => Kind::Series,
+            _ => return None,
+        };
+        let id = leading_number(segments.get(1)?)?;
+        Some(IdSearch {
+            id: MediaId::Tmdb(id),
+            kind,
+            season: segment_after(&segments, "season"),
+            episode: segment_after(&segments, "episode"),
+        })
+    } else if host == "thetvdb.com" || host.ends_with(".thetvdb.com") {
+        // e.g. /?tab=series&id=81189 or /dereferrer/series/81189
+        let id = url.query_pairs()
+            .find(|(name, _)| name == "id")
+            .and_then(|(_, id)| id.parse().ok())
+            .or_else(|| segment_after(&segments, "series"))?;
+        Some(IdSearch { id: MediaId::Tvdb(id), kind: Kind::Series, season: None, episode: None })
+    } else {
+        None
+    }
+}
+
+fn leading_number(segment: &str) -> Option<u32> {
+    segment.split(|c: char| !c.is_ascii_digit()).next()?.parse().ok()
+}
+
+fn segment_after(segments: &[&str], name: &str) -> Option<u32> {
+    segments.iter()
+        .position(|segment| *segment == name)
+        .and_then(|position| segments.get(position + 1))
+        .and_then(|segment| segment.parse().ok())
+}
+
+/// `S02` or `S02E03`, case-insensitive.
+fn parse_episode(word: &str) -> Option<(u32, Option<u32>)> {
+    let word = word.to_lowercase();
+    let rest = word.strip_prefix('s')?;
+    match rest.split_once('e') {
+        Some((season, episode)) => Some((season.parse().ok()?, Some(episode.parse().ok()?))),
+        None => Some((rest.parse().ok()?, None)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::id_search::{IdSearch, Kind, MediaId};
+
+    fn id_search(id: MediaId, kind: Kind, season: Option<u32>, episode: Option<u32>) -> Option<IdSearch> {
+        Some(IdSearch { id, kind, season, episode })
+    }
+
+    #[test]
+    fn ids() {
+        assert_eq!(IdSearch::parse("tt0111161"), id_search(MediaId::Imdb("tt0111161".to_string()), Kind::Unknown, None, None));
+        assert_eq!(IdSearch::parse(" TMDB:278 "), id_search(MediaId::Tmdb(278), Kind::Unknown, None, None));
+        assert_eq!(IdSearch::parse("tvdb:81189 S02E03"), id_search(MediaId::Tvdb(81189), Kind::Series, Some(2), Some(3)));
+        assert_eq!(IdSearch::parse("tt0903747 s2"), id_search(MediaId::Imdb("tt0903747".to_string()), Kind::Series, Some(2), None));
+    }
+
+    #[test]
+    fn links() {
+        assert_eq!(IdSearch::parse("https://www.imdb.com/title/tt0111161/?ref_=nv_sr_srsg_0"),
+                   id_search(MediaId::Imdb("tt0111161".to_string()), Kind::Unknown, None, None));
+        assert_eq!(IdSearch::parse("https://m.imdb.com/title/tt0903747/"),
+                   id_search(MediaId::Imdb("tt0903747".to_string()), Kind::Unknown, None, None));
+        assert_eq!(IdSearch::parse("https://www.themoviedb.org/movie/278-the-shawshank-redemption"),
+                   id_search(MediaId::Tmdb(278), Kind::Movie, None, None));
+        assert_eq!(IdSearch::parse("https://www.themoviedb.org/tv/1396-breaking-bad/season/2/episode/3"),
+                   id_search(MediaId::Tmdb(1396), Kind::Series, Some(2), Some(3)));
+        assert_eq!(IdSearch::parse("https://thetvdb.com/?tab=series&id=81189"),
+                   id_search(MediaId::Tvdb(81189), Kind::Series, None, None));
+        assert_eq!(IdSearch::parse("https://thetvdb.com/dereferrer/series/81189"),
+                   id_search(MediaId::Tvdb(81189), Kind::Series, None, None));
+    }
+
+    #[test]
+    fn titles() {
+        for query in ["The Shawshank Redemption", "tt0111161 extended edition", "tt123", "tmdb:abc",
+            "https://example.org/title/tt0111161", "https://www.themoviedb.org/person/500-tom-cruise"] {
+            assert_eq!(IdSearch::parse(query), None, "unexpected id search for \"{query}\"");
+        }
+    }
+
+    #[test]
+    fn prowlarr_params() {
+        assert_eq!(IdSearch::parse("tt0111161").unwrap().prowlarr_params(),
+                   "&type=search&query=%7BImdbId%3Att0111161%7D");
+        assert_eq!(IdSearch::parse("https://www.themoviedb.org/movie/278").unwrap().prowlarr_params(),
+                   "&type=movie&query=%7BTmdbId%3A278%7D");
+        assert_eq!(IdSearch::parse("tvdb:81189 S02E03").unwrap().prowlarr_params(),
+                   "&type=tvsearch&query=%7BTvdbId%3A81189%7D+%7BSeason%3A2%7D+%7BEpisode%3A3%7D");
+    }
+}
@@ -1,26 +1,44 @@
-use std::fmt::Display;
-use std::sync::Arc;
+use std::cmp::Reverse;
+use std::sync::{Arc, Mutex};
 
+use chrono::Utc;
+
+use crate::core::arr::ArrKind;
+use crate::core::deduplication::deduplicate;
 use crate::core::download_meta::{DownloadMeta, DownloadMetaProvider};
-use crate::core::downloads_tracker::DownloadsTracker;
-use crate::core::HandlingResult;
-use crate::core::prowlarr::{ProwlarrClient, SearchResult};
+use crate::core::download_rules::DownloadRules;
+use crate::core::downloads_tracker::{DownloadsTracker, User};
+use crate::core::library::{Library, LibraryError};
+use crate::core::{HandlingResult, OperationError};
+use crate::core::health::Health;
+use crate::core::prowlarr::{Protocol, ProwlarrClient, ProwlarrError, SearchResult};
+use crate::core::response::{Choice, FoundResult, Response};
+use crate::core::search_cache::CacheStats;
+use crate::core::suspicious::{check_files, check_result, warning, Suspicion, SuspiciousPolicy};
+use crate::core::torrent_files::TorrentFiles;
 use crate::core::torrent_meta::TorrentMeta;
-use crate::core::traits::input::{Command, Destination, Input, ItemUuid, Locale, ReplyToMessage, SearchQuery, Source};
-use crate::core::traits::search_result_serializer::SearchResultSerializer;
-use crate::core::traits::sender::Sender;
-use crate::core::traits::uuid_mapper::{MapperError, UuidMapper};
+use crate::core::traits::input::{Command, Destination, Input, ItemUuid, Locale, ReplyToMessage, SearchQuery, Source, WatchNumber};
+use crate::core::traits::download_client::DownloadClient;
+use crate::core::traits::sender::{MessageHandle, Sender};
+use crate::core::traits::uuid_mapper::UuidMapper;
+use crate::core::util::human_readable_size;
+use crate::core::watches::{WatchId, Watches};
 
 pub struct InputHandler {
     prowlarr: ProwlarrClient,
     uuid_mapper: Box<dyn UuidMapper<TorrentMeta>>,
     downloads_tracker: Arc<DownloadsTracker>,
     allowed_users: Vec<u64>,
-    sender: Box<dyn Sender>,
-    search_result_serializer: Box<dyn SearchResultSerializer>
+    sender: Arc<dyn Sender>,
+    watches: Watches,
+    library: Option<Library>,
+    download_client: Option<Box<dyn DownloadClient>>,
+    suspicious_policy: SuspiciousPolicy,
+    indexer_count: Mutex<Option<usize>>,
 }
 
 const RESULTS_COUNT: usize = 10;
+const TORRENT_FILES_COUNT: usize = 50;
 
 impl InputHandler {
 
@@ -28,180 +46,1382 @@ impl InputHandler {
                uuid_mapper: Box<dyn UuidMapper<TorrentMeta>>,
                downloads_tracker: Arc<DownloadsTracker>,
                allowed_users: Vec<u64>,
-               sender: Box<dyn Sender>,
-               search_result_serializer: Box<dyn SearchResultSerializer>) -> InputHandler {
+               sender: Arc<dyn Sender>) -> InputHandler {
         InputHandler {
             prowlarr,
             uuid_mapper,
             downloads_tracker,
             allowed_users,
             sender,
-            search_result_serializer,
+            watches: Watches::new(),
+            library: None,
+            download_client: None,
+            suspicious_policy: SuspiciousPolicy::default(),
+            indexer_count: Mutex::new(None),
         }
     }
 
+    /// Enables downloading magnet links and .torrent files sent by users with the `Submit` command.
+    pub fn with_download_client(mut self, download_client: Box<dyn DownloadClient>) -> InputHandler {
+        self.download_client = Some(download_client);
+        self
+    }
+
+    /// Enables adding series and movies with the `Series` and `Movie` commands.
+    pub fn with_library(mut self, library: Library) -> InputHandler {
+        self.library = Some(library);
+        self
+    }
+
+    /// Replaces the saved searches, e.g. with ones restored from a storage.
+    pub fn with_watches(mut self, watches: Watches) -> InputHandler {
+        self.watches = watches;
+        self
+    }
+
+    /// Sets whether suspicious releases are only marked or also refused to download.
+    pub fn with_suspicious_policy(mut self, suspicious_policy: SuspiciousPolicy) -> InputHandler {
+        self.suspicious_policy = suspicious_policy;
+        self
+    }
+
     pub async fn handle(&self, input: Box<dyn Input>) -> HandlingResult {
         let source = input.get_source();
         let destination = input.get_destination();
         let locale = input.get_locale();
         let reply_to_message = input.get_reply_to_message();
-        if self.allowed_users.is_empty() || self.allowed_users.contains(&source) {
-            self.sender.send_progress_indication(destination).await?;
+        if self.is_allowed(source) {
             match input.get_command() {
-                Command::Search(query) => self.search(source, destination, reply_to_message, &locale, &query).await?,
-                Command::Download(uuid) => self.download(source, destination, &locale, &uuid).await?,
-                Command::GetLink(uuid) => self.link(source, destination, &locale, &uuid).await?,
-                Command::Help => self.sender.send_plain_message(destination, &t!("help", locale = &locale)).await?,
+                Command::Search(query) => self.search(source, destination, reply_to_message, &locale, &query, false).await?,
+                Command::Refresh(query) => self.search(source, destination, reply_to_message, &locale, &query, true).await?,
+                Command::Download(uuid) => {
+                    self.sender.send_progress_indication(destination).await?;
+                    self.download(source, destination, &locale, &uuid).await?
+                }
+                Command::GetLink(uuid) => {
+                    self.sender.send_progress_indication(destination).await?;
+                    self.link(source, destination, &locale, &uuid).await?
+                }
+                Command::ShowResult(uuid) => self.show_result(source, destination, &locale, &uuid).await?,
+                Command::Files(uuid) => {
+                    self.sender.send_progress_indication(destination).await?;
+                    self.files(source, destination, &locale, &uuid).await?
+                }
+                Command::Watch(query) => self.watch(source, destination, &locale, &query).await?,
+                Command::Watches => self.list_watches(source, destination, &locale).await?,
+                Command::Unwatch(number) => self.unwatch(source, destination, &locale, &number).await?,
+                Command::Series(query) => self.lookup(source, destination, &locale, ArrKind::Sonarr, &query).await?,
+                Command::Movie(query) => self.lookup(source, destination, &locale, ArrKind::Radarr, &query).await?,
+                Command::Add(argument) => {
+                    self.sender.send_progress_indication(destination).await?;
+                    self.add_to_library(source, destination, &locale, &argument).await?
+                }
+                Command::Submit(torrent) => {
+                    self.sender.send_progress_indication(destination).await?;
+                    self.submit(source, destination, &locale, &torrent).await?
+                }
+                Command::UploadFailed => self.sender.send(destination, &Response::Error(t!("upload_failed", locale = &locale).to_string())).await?,
+                Command::Health => {
+                    self.sender.send_progress_indication(destination).await?;
+                    self.health(source, destination, &locale).await?
+                }
+                Command::Start => self.sender.send(destination, &Response::Notice(t!("start", locale = &locale).to_string())).await?,
+                Command::Help => self.sender.send(destination, &Response::Notice(t!("help", locale = &locale).to_string())).await?,
             }
         }
         Ok(())
     }
 
+    /// Whether the user may use the bot, front-ends check it before doing anything costly on a user's behalf.
+    pub fn is_allowed(&self, source: Source) -> bool {
+        self.allowed_users.is_empty() || self.allowed_users.contains(&source)
+    }
+
+    /// Searches Prowlarr and returns the most popular releases, each saved under a new uuid.
+    /// Copies of a release found on several indexers are merged into one result.
+    /// Recent results of the same search are reused unless `refresh` is set.
+    pub async fn find(&self, query: &str, refresh: bool) -> Result<Vec<FoundResult>, OperationError> {
+        let results = self.search_prowlarr(query, refresh).await?;
+        self.save(best(results)).await
+    }
+
+    /// Re-runs every saved search and sends the releases that weren't found before to whoever saved it.
+    pub async fn check_watches(&self) {
+        for (id, query) in self.watches.queries() {
+            if let Err(err) = self.check_watch(id, &query).await {
+                log::error!("Could not send new results of saved search {}: {}", id, err);
+            }
+        }
+    }
+
+    /// Sends the health summary to `chats` if Prowlarr's problems differ from the `previous` ones,
+    /// returning the current problems. Prowlarr being unreachable is a problem too.
+    pub async fn alert_on_health_change(&self, chats: &[Destination], locale: &Locale, previous: Vec<String>) -> Vec<String> {
+        let now = Utc::now();
+        let (problems, summary) = match self.prowlarr.health().await {
+            Ok(health) => {
+                self.remember_indexer_count(&health);
+                (health.problems(now), health.summary(locale, now))
+            }
+            Err(err) => {
+                log::warn!("Could not check Prowlarr health: {}", err);
+                let unreachable = t!("health_unreachable", locale = &locale).to_string();
+                (vec![unreachable.clone()], unreachable)
+            }
+        };
+        if problems != previous {
+            let alert = Response::Notice(format!("{}\n{}", t!("health_changed", locale = &locale), summary));
+            for chat in chats {
+                match self.sender.send(*chat, &alert).await {
+                    Ok(_) => log::info!("  to {} | Sent Prowlarr health alert", chat),
+                    Err(err) => log::error!("  to {} | Could not send Prowlarr health alert: {}", chat, err),
+                }
+            }
+        }
+        problems
+    }
+
+    /// Hits and misses of the search cache, `None` if searches aren't cached.
+    pub fn search_cache_stats(&self) -> Option<CacheStats> {
+        self.prowlarr.cache_stats()
+    }
+
+    async fn search_prowlarr(&self, query: &str, refresh: bool) -> Result<Vec<SearchResult>, OperationError> {
+        Ok(self.prowlarr.search(query, refresh).await?)
+    }
+
+    /// Saves results under new uuids, which commands refer to them by.
+    async fn save(&self, results: Vec<SearchResult>) -> Result<Vec<FoundResult>, OperationError> {
+        let bot_uuids = self.uuid_mapper.put_all(results
+            .iter()
+            .map(|a| a.into())
+            .collect()).await?;
+        Ok(results.into_iter()
+            .zip(bot_uuids)
+            .map(|(search_result, uuid)| FoundResult { uuid, search_result })
+            .collect())
+    }
+
+    /// Asks Prowlarr to send the result with the given uuid to the download client of its protocol.
+    /// Suspicious releases aren't downloaded if the policy blocks them.
+    pub async fn start_download(&self, uuid: &str) -> Result<TorrentMeta, OperationError> {
+        let meta = self.get_torrent_meta(uuid).await?;
+        if self.suspicious_policy == SuspiciousPolicy::Block {
+            let suspicions = self.suspicions(&meta).await;
+            if !suspicions.is_empty() {
+                return Err(OperationError::Suspicious(meta.to_string(), suspicions));
+            }
+        }
+        let response = self.prowlarr.download(&meta.indexer_id, &meta.guid).await?;
+        if response.status().is_success() {
+            Ok(meta)
+        } else {
+            Err(OperationError::DownloadRejected(format!("{} {}",
+                response.status(), response.text().await.unwrap_or_default())))
+        }
+    }
+
+    /// Gets a magnet link, a .torrent or an .nzb file of the result with the given uuid.
+    pub async fn get_download_link(&self, uuid: &str) -> Result<DownloadMeta, OperationError> {
+        let meta = self.get_torrent_meta(uuid).await?;
+        self.download_link(&meta).await
+    }
+
+    async fn download_link(&self, meta: &TorrentMeta) -> Result<DownloadMeta, OperationError> {
+        if let Some(magnet_url) = &meta.magnet_url { // todo this code resembles TorrentMeta.get_torrent_hash()
+            Ok(DownloadMeta::MagnetLink(magnet_url.clone()))
+        } else if let Some(download_url) = &meta.download_url {
+            self.prowlarr.get_download_meta(download_url)
+                .await
+                .map_err(OperationError::from)
+        } else {
+            Err(OperationError::NoLink(meta.to_string()))
+        }
+    }
+
+    /// Signs of a fake release in its search result and, if it comes as a .torrent file, in the torrent's files.
+    async fn suspicions(&self, meta: &TorrentMeta) -> Vec<Suspicion> {
+        let Some(search_result) = &meta.search_result else {
+            return vec![];
+        };
+        let mut suspicions = check_result(search_result, Utc::now());
+        if let Ok(DownloadMeta::TorrentFile(file)) = self.download_link(meta).await {
+            if let Ok(files) = TorrentFiles::parse(&file) {
+                suspicions.extend(check_files(&files, &search_result.categories));
+            }
+        }
+        suspicions
+    }
+
+    async fn get_torrent_meta(&self, uuid: &str) -> Result<TorrentMeta, OperationError> {
+        self.uuid_mapper.get(uuid)
+            .await?
+            .ok_or_else(|| OperationError::NotFound(uuid.into()))
+    }
+
     async fn search(&self,
                     source: Source,
                     destination: Destination,
                     reply_to_message: ReplyToMessage,
                     locale: &Locale,
-                    query: &SearchQuery
+                    query: &SearchQuery,
+                    refresh: bool,
     ) -> HandlingResult {
-        log::info!("from {} | Received search request \"{}\"", source, query);
-        match self.prowlarr.search(query).await {
-            Ok(results) => {
-                let first_n_sorted_results: Vec<SearchResult> = sorted_by_seeders(results)
-                    .into_iter()
-                    .take(RESULTS_COUNT)
-                    .collect();
-                let bot_uuids = self.uuid_mapper.put_all(first_n_sorted_results
-                    .iter()
-                    .map(|a| a.into())
-                    .collect()).await;
-                match bot_uuids {
-                    Ok(bot_uuids) => {
-                        let response = first_n_sorted_results
-                            .iter()
-                            .enumerate()
-                            .map(|(index, search_result)|
-                                self.search_result_serializer.serialize(search_result, &bot_uuids[index], locale))
-                            .reduce(|acc, e| acc + &e);
-                        match response {
-                            None => {
-                                self.sender.send_plain_reply(destination, reply_to_message, &t!("no_results", locale = &locale)).await?;
-                                log::info!("  to {} | Sent \"No results\" response", destination);
-                            }
-                            Some(response) => {
-                                self.sender.send_reply(destination, reply_to_message, &response).await?;
-                                log::info!("  to {} | Sent search response \"{}\"", destination, to_digest(&response));
-                            }
-                        }
-                    }
-                    Err(err) => self.handle_mapper_error(destination, locale, err).await?,
-                }
+        log::info!("from {} | Received search request \"{}\"{}", source, query, if refresh { ", bypassing the cache" } else { "" });
+        let searching_message = Response::Notice(self.searching_message(destination, locale).await);
+        let progress_message = self.sender.send_editable_reply(destination, reply_to_message, &searching_message).await?;
+        match self.find(query, refresh).await {
+            Ok(found_results) if found_results.is_empty() => {
+                self.sender.edit(destination, &progress_message, &Response::Notice(t!("no_results", locale = &locale).to_string())).await?;
+                log::info!("  to {} | Sent \"No results\" response", destination);
+            }
+            Ok(found_results) => {
+                let count = found_results.len();
+                self.sender.edit(destination, &progress_message, &Response::Results { results: found_results, locale: locale.clone() }).await?;
+                log::info!("  to {} | Sent {} search results", destination, count);
             }
-            Err(err) => self.handle_prowlarr_error(destination, locale, err).await?,
+            Err(err) => self.handle_error(destination, Some(&progress_message), locale, err).await?,
         }
         Ok(())
     }
 
-    async fn handle_prowlarr_error(&self,
-                                   destination: Destination,
-                                   locale: &Locale,
-                                   err: impl Display) -> HandlingResult {
-        log::error!("  to {} | Error when interacting with Prowlarr: {}", destination, err);
-        self.sender.send_plain_message(destination, &t!("prowlarr_error", locale = &locale)).await
+    async fn searching_message(&self, destination: Destination, locale: &Locale) -> String {
+        match self.indexer_count().await {
+            Ok(count) => t!("searching_indexers", locale = &locale, count = count).to_string(),
+            Err(err) => {
+                log::warn!("  to {} | Could not count Prowlarr indexers: {}", destination, err);
+                t!("searching", locale = &locale).to_string()
+            }
+        }
+    }
+
+    /// Number of enabled indexers as of the last health check, so that searches don't have to wait for another request.
+    /// Asks Prowlarr if it hasn't been checked yet.
+    async fn indexer_count(&self) -> Result<usize, ProwlarrError> {
+        let known = *self.indexer_count.lock().unwrap();
+        match known {
+            Some(count) => Ok(count),
+            None => {
+                let count = self.prowlarr.count_indexers().await?;
+                *self.indexer_count.lock().unwrap() = Some(count);
+                Ok(count)
+            }
+        }
+    }
+
+    fn remember_indexer_count(&self, health: &Health) {
+        *self.indexer_count.lock().unwrap() = Some(health.enabled_count());
     }
 
-    async fn handle_mapper_error(&self,
-                                 destination: Destination,
-                                 locale: &Locale,
-                                 err: MapperError) -> HandlingResult {
-        log::error!("  to {} | {}", destination, err);
-        self.sender.send_plain_message(destination, &t!("mapper_error", locale = locale)).await
+    async fn handle_error(&self,
+                          destination: Destination,
+                          progress_message: Option<&MessageHandle>,
+                          locale: &Locale,
+                          err: OperationError) -> HandlingResult {
+        let message_key = match err {
+            OperationError::Prowlarr(_) => {
+                log::error!("  to {} | {}", destination, err);
+                "prowlarr_error"
+            }
+            OperationError::ProwlarrUnavailable => {
+                log::warn!("  to {} | {}", destination, err);
+                "prowlarr_unavailable"
+            }
+            OperationError::Mapper(_) => {
+                log::error!("  to {} | {}", destination, err);
+                "mapper_error"
+            }
+            OperationError::DownloadRejected(_) => {
+                log::error!("  to {} | {}", destination, err);
+                "could_not_send_to_download"
+            }
+            OperationError::NotFound(_) | OperationError::NoLink(_) => {
+                log::warn!("  to {} | {}", destination, err);
+                "link_not_found"
+            }
+            OperationError::Suspicious(_, _) => {
+                log::warn!("  to {} | {}", destination, err);
+                "suspicious_release_blocked"
+            }
+        };
+        let response = Response::Error(t!(message_key, locale = locale).to_string());
+        match progress_message {
+            Some(progress_message) => self.sender.edit(destination, progress_message, &response).await,
+            None => self.sender.send(destination, &response).await,
+        }
     }
 
     async fn download(&self, source: Source, destination: Destination, locale: &Locale, uuid: &ItemUuid) -> HandlingResult {
         log::info!("from {} | Received download request for {}", source, uuid);
-        match self.uuid_mapper.get(uuid).await {
-            Ok(torrent_data) => match torrent_data {
-                None => self.link_not_found(destination, locale, uuid).await?,
-                Some(meta) => {
-                    match self.prowlarr.download(&meta.indexer_id, &meta.guid).await {
-                        Ok(response) => {
-                            if response.status().is_success() {
-                                self.sender.send_plain_message(destination, &t!("sent_to_download", locale = &locale)).await?;
-                                log::info!("  to {} | Sent {} for downloading", destination, meta);
-                                match meta.get_torrent_hash(&self.prowlarr).await {
-                                    Ok(hash) => self.downloads_tracker.add(hash, destination, locale.clone()),
-                                    Err(err) => {
-                                        log::error!("  to {} | {}", destination, err);
-                                    }
-                                };
-                            } else {
-                                log::error!("  to {} | Download response from Prowlarr wasn't successful: {} {}",
-                                    destination, response.status(), response.text().await.unwrap_or_default());
-                                self.sender.send_plain_message(destination, &t!("could_not_send_to_download", locale = &locale)).await?;
-                            }
-                        }
-                        Err(err) => self.handle_prowlarr_error(destination, locale, err).await?
-                    }
-                }
+        match self.start_download(uuid).await {
+            Ok(meta) => {
+                self.sender.send(destination, &Response::Notice(t!("sent_to_download", locale = &locale).to_string())).await?;
+                log::info!("  to {} | Sent {} for downloading", destination, meta);
+                self.track(&meta, User { source, destination, locale: locale.clone() }).await;
+            }
+            Err(err) => self.handle_error(destination, None, locale, err).await?,
+        }
+        Ok(())
+    }
+
+    /// Registers a download to notify the user when it completes.
+    async fn track(&self, meta: &TorrentMeta, user: User) {
+        if meta.protocol == Protocol::Usenet {
+            log::info!("  to {} | Completion of Usenet download {} isn't tracked", user.destination, meta);
+            return;
+        }
+        match meta.get_torrent_hash(&self.prowlarr).await {
+            Ok(hash) => self.downloads_tracker.add(hash, user.source, user.destination, user.locale),
+            Err(err) => log::error!("  to {} | {}", user.destination, err),
+        }
+    }
+
+    /// Adds a torrent sent by the user to the download client and tracks it like a found one.
+    async fn submit(&self, source: Source, destination: Destination, locale: &Locale, torrent: &DownloadMeta) -> HandlingResult {
+        log::info!("from {} | Received a torrent to download", source);
+        let hash = match torrent.torrent_hash() {
+            Ok(hash) => hash,
+            Err(err) => {
+                log::warn!("  to {} | Could not read the torrent: {}", destination, err);
+                return self.sender.send(destination, &Response::Error(t!("bad_torrent", locale = &locale).to_string())).await;
+            }
+        };
+        let Some(download_client) = &self.download_client else {
+            log::warn!("  to {} | No download client to add torrent {} to", destination, hash);
+            return self.sender.send(destination, &Response::Error(t!("download_client_not_configured", locale = &locale).to_string())).await;
+        };
+        match download_client.add(torrent).await {
+            Ok(()) => {
+                self.sender.send(destination, &Response::Notice(t!("sent_to_download", locale = &locale).to_string())).await?;
+                log::info!("  to {} | Sent torrent {} for downloading", destination, hash);
+                self.downloads_tracker.add(hash, source, destination, locale.clone());
+            }
+            Err(err) => {
+                log::error!("  to {} | Download client rejected torrent {}: {}", destination, hash, err);
+                self.sender.send(destination, &Response::Error(t!("could_not_send_to_download", locale = &locale).to_string())).await?;
             }
-            Err(err) => self.handle_mapper_error(destination, locale, err).await?,
         }
         Ok(())
     }
 
     async fn link(&self, source: Source, destination: Destination, locale: &Locale, uuid: &ItemUuid) -> HandlingResult {
         log::info!("from {} | Received get link request for {}", source, uuid);
-        match self.uuid_mapper.get(uuid).await {
-            Ok(torrent_data) => match torrent_data {
-                None => self.link_not_found(destination, locale, uuid).await?,
-                Some(torrent_data) => {
-                    if torrent_data.magnet_url.is_some() { // todo this code resembles TorrentMeta.get_torrent_hash()
-                        self.sender.send_magnet(destination, torrent_data.magnet_url.as_ref().unwrap()).await?;
-                        log::info!("  to {} | Sent magnet link for {} ", destination, &torrent_data);
-                    } else if torrent_data.download_url.is_some() {
-                        match self.prowlarr.get_download_meta(torrent_data.download_url.as_ref().unwrap()).await {
-                            Ok(content) => {
-                                match content {
-                                    DownloadMeta::MagnetLink(link) => {
-                                        self.sender.send_magnet(destination, &link).await?;
-                                        log::info!("  to {} | Sent magnet link for {} ", destination, &torrent_data);
-                                    }
-                                    DownloadMeta::TorrentFile(file) => {
-                                        self.sender.send_torrent_file(destination, &format!("{}.torrent", uuid), file).await?;
-                                        log::info!("  to {} | Sent .torrent file for {} ", destination, &torrent_data);
-                                    }
-                                }
-                            }
-                            Err(err) => self.handle_prowlarr_error(destination, locale, err).await?,
-                        }
+        match self.get_download_link(uuid).await {
+            Ok(DownloadMeta::MagnetLink(link)) => {
+                self.sender.send(destination, &Response::Magnet(link)).await?;
+                log::info!("  to {} | Sent magnet link for {}", destination, uuid);
+            }
+            Ok(DownloadMeta::TorrentFile(file)) => {
+                self.sender.send(destination, &Response::File { filename: format!("{}.torrent", uuid), content: file }).await?;
+                log::info!("  to {} | Sent .torrent file for {}", destination, uuid);
+            }
+            Ok(DownloadMeta::NzbFile(file)) => {
+                self.sender.send(destination, &Response::File { filename: format!("{}.nzb", uuid), content: file }).await?;
+                log::info!("  to {} | Sent .nzb file for {}", destination, uuid);
+            }
+            Err(err) => self.handle_error(destination, None, locale, err).await?,
+        }
+        Ok(())
+    }
+
+    async fn files(&self, source: Source, destination: Destination, locale: &Locale, uuid: &ItemUuid) -> HandlingResult {
+        log::info!("from {} | Received files request for {}", source, uuid);
+        let meta = match self.get_torrent_meta(uuid).await {
+            Ok(meta) => meta,
+            Err(err) => return self.handle_error(destination, None, locale, err).await,
+        };
+        let file = match self.download_link(&meta).await {
+            Ok(DownloadMeta::TorrentFile(file)) => file,
+            Ok(_) => {
+                log::info!("  to {} | No .torrent file to list files of {}", destination, uuid);
+                return self.sender.send(destination, &Response::Error(t!("files_unavailable", locale = &locale).to_string())).await;
+            }
+            Err(err) => return self.handle_error(destination, None, locale, err).await,
+        };
+        match TorrentFiles::parse(&file) {
+            Ok(files) => {
+                let heading = t!("torrent_files", locale = &locale, name = files.name, count = files.count(),
+                    size = human_readable_size(files.total_size()));
+                let categories = meta.search_result.map(|search_result| search_result.categories).unwrap_or_default();
+                let message = match warning(&check_files(&files, &categories), locale) {
+                    Some(warning) => format!("{}\n{}\n{}", heading, files.tree(TORRENT_FILES_COUNT), warning),
+                    None => format!("{}\n{}", heading, files.tree(TORRENT_FILES_COUNT)),
+                };
+                self.sender.send(destination, &Response::Notice(message)).await?;
+                log::info!("  to {} | Sent {} files of {}", destination, files.count(), uuid);
+            }
+            Err(err) => {
+                log::warn!("  to {} | Could not read the torrent of {}: {}", destination, uuid, err);
+                self.sender.send(destination, &Response::Error(t!("files_unavailable", locale = &locale).to_string())).await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn watch(&self, source: Source, destination: Destination, locale: &Locale, query: &SearchQuery) -> HandlingResult {
+        log::info!("from {} | Received watch request \"{}\"", source, query);
+        let (query, rules) = match query.split_once('|') {
+            Some((query, rules)) => match rules.parse::<DownloadRules>() {
+                Ok(rules) => (query.trim(), Some(rules)),
+                Err(err) => {
+                    let message = t!("watch_bad_rules", locale = &locale, error = err.to_string()).to_string();
+                    return self.sender.send(destination, &Response::Error(message)).await;
+                }
+            },
+            None => (query.as_ref(), None),
+        };
+        if query.is_empty() {
+            return self.sender.send(destination, &Response::Error(t!("watch_no_query", locale = &locale).to_string())).await;
+        }
+        self.sender.send_progress_indication(destination).await?;
+        match self.search_prowlarr(query, false).await {
+            Ok(results) => {
+                let message_key = if rules.is_some() { "watch_added_with_rules" } else { "watch_added" };
+                let user = User { source, destination, locale: locale.clone() };
+                let id = self.watches.add(query.into(), rules, user, results.into_iter().map(|result| result.guid)).await;
+                self.sender.send(destination, &Response::Notice(t!(message_key, locale = &locale, query = query).to_string())).await?;
+                log::info!("  to {} | Saved search {}", destination, id);
+            }
+            Err(err) => self.handle_error(destination, None, locale, err).await?,
+        }
+        Ok(())
+    }
+
+    async fn health(&self, source: Source, destination: Destination, locale: &Locale) -> HandlingResult {
+        log::info!("from {} | Received health request", source);
+        match self.prowlarr.health().await {
+            Ok(health) => {
+                self.remember_indexer_count(&health);
+                self.sender.send(destination, &Response::Notice(health.summary(locale, Utc::now()))).await
+            }
+            Err(err) => self.handle_error(destination, None, locale, err.into()).await,
+        }
+    }
+
+    async fn list_watches(&self, source: Source, destination: Destination, locale: &Locale) -> HandlingResult {
+        log::info!("from {} | Received saved searches request", source);
+        let watches = self.watches.list(source, destination);
+        let message = if watches.is_empty() {
+            t!("no_watches", locale = &locale).to_string()
+        } else {
+            watches.iter()
+                .fold(t!("watches", locale = &locale).to_string(), |message, (id, query)| format!("{message}\n{id}. {query}"))
+        };
+        self.sender.send(destination, &Response::Notice(message)).await
+    }
+
+    async fn unwatch(&self, source: Source, destination: Destination, locale: &Locale, number: &WatchNumber) -> HandlingResult {
+        log::info!("from {} | Received unwatch request for {}", source, number);
+        let removed = match number.parse() {
+            Ok(id) => self.watches.remove(id, source).await,
+            Err(_) => None,
+        };
+        let response = match removed {
+            Some(query) => Response::Notice(t!("watch_removed", locale = &locale, query = query).to_string()),
+            None => Response::Error(t!("watch_not_found", locale = &locale).to_string()),
+        };
+        self.sender.send(destination, &response).await
+    }
+
+    async fn check_watch(&self, id: WatchId, query: &str) -> HandlingResult {
+        // saved searches look for new releases, which cached results can't have
+        let results = match self.search_prowlarr(query, true).await {
+            Ok(results) => results,
+            Err(err) => {
+                log::warn!("Could not re-run saved search {}: {}", id, err);
+                return Ok(());
+            }
+        };
+        let Some((user, rules, unseen)) = self.watches.take_unseen(id, results).await else {
+            return Ok(());
+        };
+        if unseen.is_empty() {
+            return Ok(());
+        }
+        let releases = sorted_by_popularity(deduplicate(unseen));
+        let to_download = rules.and_then(|rules| releases.iter()
+            .filter(|release| self.suspicious_policy == SuspiciousPolicy::Warn || check_result(release, Utc::now()).is_empty())
+            .find(|release| rules.matches(release))
+            .cloned());
+        match self.save(releases.into_iter().take(RESULTS_COUNT).collect()).await {
+            Ok(found_results) => {
+                let count = found_results.len();
+                self.sender.send(user.destination, &Response::Notice(
+                    t!("watch_new_results", locale = &user.locale, query = query).to_string())).await?;
+                self.sender.send(user.destination, &Response::Results { results: found_results, locale: user.locale.clone() }).await?;
+                log::info!("  to {} | Sent {} new results of saved search {}", user.destination, count, id);
+            }
+            Err(err) => log::error!("  to {} | {}", user.destination, err),
+        }
+        if let Some(release) = to_download {
+            self.auto_download(id, release, user).await?;
+        }
+        Ok(())
+    }
+
+    async fn auto_download(&self, id: WatchId, release: SearchResult, user: User) -> HandlingResult {
+        let title = release.title.clone();
+        let downloaded = match self.save(vec![release]).await {
+            Ok(found_results) => self.start_download(&found_results[0].uuid).await,
+            Err(err) => Err(err),
+        };
+        match downloaded {
+            Ok(meta) => {
+                self.sender.send(user.destination, &Response::Notice(
+                    t!("watch_auto_download", locale = &user.locale, title = title).to_string())).await?;
+                log::info!("  to {} | Sent {} of saved search {} for downloading", user.destination, meta, id);
+                self.track(&meta, user).await;
+            }
+            Err(err) => self.handle_error(user.destination, None, &user.locale, err).await?,
+        }
+        Ok(())
+    }
+
+    fn library(&self, kind: ArrKind) -> Result<&Library, LibraryError> {
+        self.library.as_ref().ok_or(LibraryError::NotConfigured(kind))
+    }
+
+    async fn lookup(&self, source: Source, destination: Destination, locale: &Locale, kind: ArrKind, query: &SearchQuery) -> HandlingResult {
+        log::info!("from {} | Received {} lookup request \"{}\"", source, kind, query);
+        if query.is_empty() {
+            return self.sender.send(destination, &Response::Error(t!("library_no_query", locale = &locale).to_string())).await;
+        }
+        self.sender.send_progress_indication(destination).await?;
+        let found = match self.library(kind) {
+            Ok(library) => library.lookup(kind, query).await,
+            Err(err) => Err(err),
+        };
+        match found {
+            Ok(items) if items.is_empty() => {
+                self.sender.send(destination, &Response::Notice(t!("no_results", locale = &locale).to_string())).await?;
+                log::info!("  to {} | Sent \"No results\" response", destination);
+            }
+            Ok(items) => {
+                let count = items.len();
+                let choices = items.into_iter()
+                    .map(|(uuid, item)| if item.in_library() {
+                        Choice { label: t!("library_item_in_library", locale = &locale, title = item.title()).to_string(), command: None }
                     } else {
-                        log::warn!("  to {} | Neither magnet nor download link exist for torrent {}", destination, torrent_data);
-                        self.sender.send_plain_message(destination, &t!("link_not_found", locale = &locale)).await?;
-                    }
+                        Choice { label: item.title(), command: Some(Command::Add(uuid.into())) }
+                    })
+                    .collect();
+                let heading = t!("library_pick_item", locale = &locale).to_string();
+                self.sender.send(destination, &Response::Choices { heading, choices }).await?;
+                log::info!("  to {} | Sent {} {} lookup results", destination, count, kind);
+            }
+            Err(err) => self.handle_library_error(destination, locale, err).await?,
+        }
+        Ok(())
+    }
+
+    /// Offers quality profiles for an item picked by `uuid`, or adds it if `uuid_profileId` is given.
+    async fn add_to_library(&self, source: Source, destination: Destination, locale: &Locale, argument: &ItemUuid) -> HandlingResult {
+        log::info!("from {} | Received add request for {}", source, argument);
+        let (uuid, quality_profile) = match argument.split_once('_') {
+            Some((uuid, quality_profile)) => (uuid, Some(quality_profile)),
+            None => (argument.as_ref(), None),
+        };
+        let Some(library) = &self.library else {
+            return self.handle_library_error(destination, locale, LibraryError::NotFound(uuid.into())).await;
+        };
+        let item = match library.get(uuid).await {
+            Ok(item) => item,
+            Err(err) => return self.handle_library_error(destination, locale, err).await,
+        };
+        let Some(quality_profile) = quality_profile else {
+            return match library.quality_profiles(item.kind).await {
+                Ok(profiles) => {
+                    let choices = profiles.into_iter()
+                        .map(|profile| Choice { label: profile.name, command: Some(Command::Add(format!("{}_{}", uuid, profile.id).into())) })
+                        .collect();
+                    let heading = t!("library_pick_quality_profile", locale = &locale, title = item.title()).to_string();
+                    self.sender.send(destination, &Response::Choices { heading, choices }).await
                 }
+                Err(err) => self.handle_library_error(destination, locale, err).await,
+            };
+        };
+        let Ok(quality_profile_id) = quality_profile.parse() else {
+            return self.handle_library_error(destination, locale, LibraryError::NotFound(argument.clone())).await;
+        };
+        match library.add(&item, quality_profile_id).await {
+            Ok(()) => {
+                self.sender.send(destination, &Response::Notice(
+                    t!("library_added", locale = &locale, title = item.title(), app = item.kind.to_string()).to_string())).await?;
+                log::info!("  to {} | Added \"{}\" to {}", destination, item.title(), item.kind);
             }
-            Err(err) => self.handle_mapper_error(destination, locale, err).await?,
+            Err(err) => self.handle_library_error(destination, locale, err).await?,
         }
         Ok(())
     }
 
-    async fn link_not_found(&self, destination: Destination, locale: &Locale, uuid: &ItemUuid) -> HandlingResult {
-        log::warn!("  to {} | Link for uuid {} not found", destination, &uuid);
-        self.sender.send_plain_message(destination, &t!("link_not_found", locale = locale)).await?;
+    async fn handle_library_error(&self, destination: Destination, locale: &Locale, err: LibraryError) -> HandlingResult {
+        let message = match &err {
+            LibraryError::NotConfigured(kind) => {
+                log::warn!("  to {} | {}", destination, err);
+                t!("library_not_configured", locale = &locale, app = kind.to_string())
+            }
+            LibraryError::Arr(kind, _) => {
+                log::error!("  to {} | {}", destination, err);
+                t!("library_error", locale = &locale, app = kind.to_string())
+            }
+            LibraryError::Mapper(_) => {
+                log::error!("  to {} | {}", destination, err);
+                t!("mapper_error", locale = &locale)
+            }
+            LibraryError::NotFound(_) => {
+                log::warn!("  to {} | {}", destination, err);
+                t!("link_not_found", locale = &locale)
+            }
+        };
+        self.sender.send(destination, &Response::Error(message.to_string())).await
+    }
+
+    async fn show_result(&self, source: Source, destination: Destination, locale: &Locale, uuid: &ItemUuid) -> HandlingResult {
+        log::info!("from {} | Received show result request for {}", source, uuid);
+        match self.get_torrent_meta(uuid).await {
+            Ok(TorrentMeta { search_result: Some(search_result), .. }) => {
+                let results = vec![FoundResult { uuid: uuid.to_string(), search_result }];
+                self.sender.send(destination, &Response::Results { results, locale: locale.clone() }).await?;
+                log::info!("  to {} | Sent search result {}", destination, uuid);
+            }
+            Ok(_) => self.handle_error(destination, None, locale, OperationError::NotFound(uuid.clone())).await?,
+            Err(err) => self.handle_error(destination, None, locale, err).await?,
+        }
         Ok(())
     }
 }
 
-fn sorted_by_seeders(mut results: Vec<SearchResult>) -> Vec<SearchResult> {
-    results.sort_unstable_by(|a, b| b.seeders.cmp(&a.seeders));
+/// The most popular releases, with copies from several indexers merged.
+fn best(results: Vec<SearchResult>) -> Vec<SearchResult> {
+    sorted_by_popularity(deduplicate(results))
+        .into_iter()
+        .take(RESULTS_COUNT)
+        .collect()
+}
+
+fn sorted_by_popularity(mut results: Vec<SearchResult>) -> Vec<SearchResult> {
+    results.sort_unstable_by_key(|result| Reverse(result.popularity()));
     results
 }
 
-fn to_digest(str: &str) -> String {
-    str.char_indices()
-        .map(|(i, _)| i)
-        .nth(100)
-        .map(|end| str[0..end].to_string())
-        .unwrap_or(str.to_string())
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use bytes::Bytes;
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::core::arr::{ArrClient, ArrKind};
+    use crate::core::download_meta::DownloadMeta;
+    use crate::core::downloads_tracker::DownloadsTracker;
+    use crate::core::input_handler::InputHandler;
+    use crate::core::library::{Library, LibraryItem};
+    use crate::core::prowlarr::{Protocol, ProwlarrClient, SearchResult};
+    use crate::core::response::Response;
+    use crate::core::suspicious::SuspiciousPolicy;
+    use crate::core::torrent_meta::TorrentMeta;
+    use crate::core::traits::input::{Command, Destination, Input, Locale, ReplyToMessage, Source};
+    use crate::core::traits::download_client::MockDownloadClient;
+    use crate::core::traits::sender::MockSender;
+    use crate::ext::uuid_mapper;
+
+    struct TestInput(Command);
+
+    impl Input for TestInput {
+        fn get_command(&self) -> Command {
+            self.0.clone()
+        }
+
+        fn get_source(&self) -> Source {
+            1
+        }
+
+        fn get_destination(&self) -> Destination {
+            2
+        }
+
+        fn get_reply_to_message(&self) -> ReplyToMessage {
+            3
+        }
+
+        fn get_locale(&self) -> Locale {
+            "en".into()
+        }
+    }
+
+    async fn prowlarr_mock(search_response: ResponseTemplate) -> MockServer {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/indexer"))
+            .respond_with(ResponseTemplate::new(200)
+                .set_body_string("[{\"id\":1,\"enable\":true},{\"id\":2,\"enable\":true}]"))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/search"))
+            .respond_with(search_response)
+            .mount(&mock_server)
+            .await;
+        mock_server
+    }
+
+    fn input_handler(mock_server: &MockServer, sender: MockSender) -> InputHandler {
+        let prowlarr = temp_env::with_vars(
+            [("PROWLARR_API_KEY", Some("key123")),
+                ("PROWLARR_BASE_URL", Some(&mock_server.uri()))],
+            ProwlarrClient::from_env);
+        InputHandler::new(
+            prowlarr,
+            uuid_mapper::create::<TorrentMeta>(),
+            Arc::new(DownloadsTracker::new()),
+            vec![],
+            Arc::new(sender))
+    }
+
+    fn sender_with_progress_message() -> MockSender {
+        let mut sender = MockSender::new();
+        sender.expect_send_editable_reply()
+            .withf(|destination, reply_to_message, response|
+                *destination == 2 && *reply_to_message == 3
+                    && matches!(response, Response::Notice(message) if message == "Searching 2 indexer(s)…"))
+            .times(1)
+            .returning(|_, _, _| Box::pin(async { Ok("42".into()) }));
+        sender
+    }
+
+    #[tokio::test]
+    async fn search_edits_progress_message_into_results() {
+        let mock_server = prowlarr_mock(ResponseTemplate::new(200)
+            .set_body_string(
+                "[{\"guid\":\"1\",\"indexerId\":1,\"title\":\"Less seeded\",\
+                \"size\":20000,\"publishDate\":\"2015-05-15T00:00:00Z\",\
+                \"infoUrl\":\"info url\",\"seeders\":1,\"leechers\":0},\
+                {\"guid\":\"2\",\"indexerId\":1,\"title\":\"More seeded\",\
+                \"size\":20000,\"publishDate\":\"2015-05-15T00:00:00Z\",\
+                \"infoUrl\":\"info url\",\"seeders\":10,\"leechers\":0}]")).await;
+        let mut sender = sender_with_progress_message();
+        sender.expect_edit()
+            .withf(|destination, message_handle, response|
+                *destination == 2 && message_handle.as_ref() == "42"
+                    && matches!(response, Response::Results { results, .. }
+                        if results.len() == 2
+                            && results[0].search_result.title == "More seeded"
+                            && results[1].search_result.title == "Less seeded"))
+            .times(1)
+            .returning(|_, _, _| Box::pin(async { Ok(()) }));
+
+        input_handler(&mock_server, sender)
+            .handle(Box::new(TestInput(Command::Search("ubuntu".into()))))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn search_merges_copies_of_a_release() {
+        let mock_server = prowlarr_mock(ResponseTemplate::new(200)
+            .set_body_string(
+                "[{\"guid\":\"1\",\"indexerId\":1,\"indexer\":\"A\",\"title\":\"Ubuntu.22.04\",\
+                \"size\":20000,\"publishDate\":\"2015-05-15T00:00:00Z\",\
+                \"infoUrl\":\"info url\",\"seeders\":1,\"leechers\":0},\
+                {\"guid\":\"2\",\"indexerId\":2,\"indexer\":\"B\",\"title\":\"Ubuntu 22.04\",\
+                \"size\":20000,\"publishDate\":\"2015-05-15T00:00:00Z\",\
+                \"infoUrl\":\"info url\",\"seeders\":10,\"leechers\":0}]")).await;
+        let mut sender = sender_with_progress_message();
+        sender.expect_edit()
+            .withf(|_, _, response|
+                matches!(response, Response::Results { results, .. }
+                    if results.len() == 1
+                        && results[0].search_result.guid == "2"
+                        && results[0].search_result.other_indexers == vec!["A"]))
+            .times(1)
+            .returning(|_, _, _| Box::pin(async { Ok(()) }));
+
+        input_handler(&mock_server, sender)
+            .handle(Box::new(TestInput(Command::Search("ubuntu".into()))))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn search_edits_progress_message_if_no_results() {
+        let mock_server = prowlarr_mock(ResponseTemplate::new(200).set_body_string("[]")).await;
+        let mut sender = sender_with_progress_message();
+        sender.expect_edit()
+            .withf(|destination, message_handle, response|
+                *destination == 2 && message_handle.as_ref() == "42"
+                    && matches!(response, Response::Notice(message) if message == "No results"))
+            .times(1)
+            .returning(|_, _, _| Box::pin(async { Ok(()) }));
+
+        input_handler(&mock_server, sender)
+            .handle(Box::new(TestInput(Command::Search("ubuntu".into()))))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn search_edits_progress_message_into_error() {
+        let mock_server = prowlarr_mock(ResponseTemplate::new(500)).await;
+        let mut sender = sender_with_progress_message();
+        sender.expect_edit()
+            .withf(|destination, message_handle, response|
+                *destination == 2 && message_handle.as_ref() == "42"
+                    && matches!(response, Response::Error(message)
+                        if message == "Search/downloads aren't available. Please contact support."))
+            .times(1)
+            .returning(|_, _, _| Box::pin(async { Ok(()) }));
+
+        input_handler(&mock_server, sender)
+            .handle(Box::new(TestInput(Command::Search("ubuntu".into()))))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn search_fails_fast_while_prowlarr_is_unavailable() {
+        let mock_server = prowlarr_mock(ResponseTemplate::new(500)).await;
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let mut sender = MockSender::new();
+        sender.expect_send_editable_reply()
+            .times(2)
+            .returning(|_, _, _| Box::pin(async { Ok("42".into()) }));
+        let recorded = errors.clone();
+        sender.expect_edit()
+            .returning(move |_, _, response| {
+                if let Response::Error(message) = response {
+                    recorded.lock().unwrap().push(message.clone());
+                }
+                Box::pin(async { Ok(()) })
+            });
+        let input_handler = temp_env::with_vars(
+            [("PROWLARR_RETRIES", Some("0")), ("PROWLARR_BREAKER_FAILURES", Some("1"))],
+            || input_handler(&mock_server, sender));
+
+        for _ in 0..2 {
+            input_handler.handle(Box::new(TestInput(Command::Search("ubuntu".into())))).await.unwrap();
+        }
+
+        assert_eq!(*errors.lock().unwrap(), vec![
+            "Search/downloads aren't available. Please contact support.",
+            "Prowlarr is unavailable at the moment. Please try again in a few minutes.",
+        ]);
+    }
+
+    async fn healthy_prowlarr_mock() -> MockServer {
+        let mock_server = prowlarr_mock(ResponseTemplate::new(200).set_body_string("[]")).await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/system/status"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("{\"version\":\"1.24.3\"}"))
+            .mount(&mock_server)
+            .await;
+        for endpoint in ["/api/v1/health", "/api/v1/indexerstatus"] {
+            Mock::given(method("GET"))
+                .and(path(endpoint))
+                .respond_with(ResponseTemplate::new(200).set_body_string("[]"))
+                .mount(&mock_server)
+                .await;
+        }
+        mock_server
+    }
+
+    #[tokio::test]
+    async fn health_summary() {
+        let mock_server = healthy_prowlarr_mock().await;
+        let mut sender = MockSender::new();
+        sender.expect_send_progress_indication()
+            .returning(|_| Box::pin(async { Ok(()) }));
+        sender.expect_send()
+            .withf(|destination, response|
+                *destination == 2 && matches!(response, Response::Notice(message)
+                    if message == "Prowlarr 1.24.3\nIndexers: 2 enabled, 0 failing, 0 disabled\nAll indexers are working."))
+            .times(1)
+            .returning(|_, _| Box::pin(async { Ok(()) }));
+
+        input_handler(&mock_server, sender)
+            .handle(Box::new(TestInput(Command::Health)))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn health_alert_is_sent_on_change_only() {
+        let mock_server = healthy_prowlarr_mock().await;
+        let mut sender = MockSender::new();
+        sender.expect_send()
+            .withf(|destination, response|
+                [7, 8].contains(destination) && matches!(response, Response::Notice(message)
+                    if message.starts_with("Prowlarr health has changed:\nProwlarr 1.24.3\n")))
+            .times(2)
+            .returning(|_, _| Box::pin(async { Ok(()) }));
+        let input_handler = input_handler(&mock_server, sender);
+
+        let problems = input_handler.alert_on_health_change(&[7, 8], &"en".into(), vec![]).await;
+        assert!(problems.is_empty());
+        let problems = input_handler.alert_on_health_change(&[7, 8], &"en".into(), vec!["B".to_string()]).await;
+        assert!(problems.is_empty());
+    }
+
+    async fn indexer_requests(mock_server: &MockServer) -> usize {
+        mock_server.received_requests().await.unwrap().iter()
+            .filter(|request| request.url.path() == "/api/v1/indexer")
+            .count()
+    }
+
+    fn sender_with_two_searches() -> MockSender {
+        let mut sender = MockSender::new();
+        sender.expect_send_editable_reply()
+            .withf(|_, _, response| matches!(response, Response::Notice(message) if message == "Searching 2 indexer(s)…"))
+            .times(2)
+            .returning(|_, _, _| Box::pin(async { Ok("42".into()) }));
+        sender.expect_edit()
+            .returning(|_, _, _| Box::pin(async { Ok(()) }));
+        sender
+    }
+
+    #[tokio::test]
+    async fn searches_reuse_indexer_count_of_health_check() {
+        let mock_server = healthy_prowlarr_mock().await;
+        let input_handler = input_handler(&mock_server, sender_with_two_searches());
+
+        input_handler.alert_on_health_change(&[], &"en".into(), vec![]).await;
+        for _ in 0..2 {
+            input_handler.handle(Box::new(TestInput(Command::Search("ubuntu".into())))).await.unwrap();
+        }
+
+        assert_eq!(indexer_requests(&mock_server).await, 1);
+    }
+
+    #[tokio::test]
+    async fn indexers_are_counted_once_without_health_checks() {
+        let mock_server = prowlarr_mock(ResponseTemplate::new(200).set_body_string("[]")).await;
+        let input_handler = input_handler(&mock_server, sender_with_two_searches());
+
+        for _ in 0..2 {
+            input_handler.handle(Box::new(TestInput(Command::Search("ubuntu".into())))).await.unwrap();
+        }
+
+        assert_eq!(indexer_requests(&mock_server).await, 1);
+    }
+
+    #[tokio::test]
+    async fn link_to_nzb_file() {
+        let mock_server = prowlarr_mock(ResponseTemplate::new(200).set_body_string("[]")).await;
+        Mock::given(method("GET"))
+            .and(path("/content"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw("nzb contents", "application/x-nzb"))
+            .mount(&mock_server)
+            .await;
+        let mut sender = MockSender::new();
+        sender.expect_send_progress_indication()
+            .returning(|_| Box::pin(async { Ok(()) }));
+        sender.expect_send()
+            .withf(|destination, response|
+                *destination == 2
+                    && matches!(response, Response::File { filename, content }
+                        if filename.ends_with(".nzb") && content.as_ref() == b"nzb contents"))
+            .times(1)
+            .returning(|_, _| Box::pin(async { Ok(()) }));
+        let input_handler = input_handler(&mock_server, sender);
+        let uuids = input_handler.uuid_mapper.put_all(vec![TorrentMeta {
+            guid: "1".to_string(),
+            indexer_id: 1,
+            download_url: Some(format!("{}/content", mock_server.uri())),
+            magnet_url: None,
+            protocol: Protocol::Usenet,
+            search_result: None,
+        }]).await.unwrap();
+
+        input_handler
+            .handle(Box::new(TestInput(Command::GetLink(uuids[0].as_str().into()))))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn files_of_torrent() {
+        let mock_server = prowlarr_mock(ResponseTemplate::new(200).set_body_string("[]")).await;
+        Mock::given(method("GET"))
+            .and(path("/content"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                "d4:infod5:filesld6:lengthi1000e4:pathl6:Sample10:sample.mkveed6:lengthi5000000e4:pathl9:movie.mkveee\
+                4:name5:Movie12:piece lengthi262144e6:pieces0:ee", "application/x-bittorrent"))
+            .mount(&mock_server)
+            .await;
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let input_handler = input_handler(&mock_server, recording_sender(sent.clone()));
+        let uuids = input_handler.uuid_mapper.put_all(vec![
+            TorrentMeta {
+                guid: "1".to_string(),
+                indexer_id: 1,
+                download_url: Some(format!("{}/content", mock_server.uri())),
+                magnet_url: None,
+                protocol: Protocol::Torrent,
+                search_result: None,
+            },
+            TorrentMeta {
+                guid: "2".to_string(),
+                indexer_id: 1,
+                download_url: None,
+                magnet_url: Some("magnet:?xt=urn:btih:c811b41641a09d192b8ed81b14064fff55d85ce3".to_string()),
+                protocol: Protocol::Torrent,
+                search_result: None,
+            },
+        ]).await.unwrap();
+
+        input_handler.handle(Box::new(TestInput(Command::Files(uuids[0].as_str().into())))).await.unwrap();
+        input_handler.handle(Box::new(TestInput(Command::Files(uuids[1].as_str().into())))).await.unwrap();
+
+        assert_eq!(*sent.lock().unwrap(), vec![
+            "Movie: 2 file(s), 5 MB\nSample/\n  sample.mkv (1 KB)\nmovie.mkv (5 MB)".to_string(),
+            "Files can only be listed for releases with a torrent-file.".to_string(),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn suspicious_release_is_blocked() {
+        let mock_server = prowlarr_mock(ResponseTemplate::new(200).set_body_string("[]")).await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/search"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(0)
+            .mount(&mock_server)
+            .await;
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let input_handler = input_handler(&mock_server, recording_sender(sent.clone()))
+            .with_suspicious_policy(SuspiciousPolicy::Block);
+        let search_result: SearchResult = serde_json::from_value(json!({
+            "guid": "1", "indexerId": 1, "title": "The Movie 2023 2160p", "size": 5000000,
+            "publishDate": "2015-05-15T00:00:00Z", "infoUrl": "info url", "seeders": 10, "leechers": 0,
+            "magnetUrl": "magnet:?xt=urn:btih:c811b41641a09d192b8ed81b14064fff55d85ce3",
+            "categories": [{ "id": 2040, "name": "Movies/HD" }]
+        })).unwrap();
+        let uuids = input_handler.uuid_mapper.put_all(vec![(&search_result).into()]).await.unwrap();
+
+        input_handler.handle(Box::new(TestInput(Command::Download(uuids[0].as_str().into())))).await.unwrap();
+
+        assert_eq!(*sent.lock().unwrap(), vec!["The release looks fake, so it wasn't sent for downloading.".to_string()]);
+    }
+
+    fn search_response(guids: &[&str]) -> ResponseTemplate {
+        let results: Vec<String> = guids.iter()
+            .map(|guid| format!("{{\"guid\":\"{guid}\",\"indexerId\":1,\"title\":\"Ubuntu {guid}\",\"size\":20000,\
+                \"publishDate\":\"2015-05-15T00:00:00Z\",\"infoUrl\":\"info url\",\"seeders\":1,\"leechers\":0}}"))
+            .collect();
+        ResponseTemplate::new(200).set_body_string(format!("[{}]", results.join(",")))
+    }
+
+    #[tokio::test]
+    async fn saved_search_sends_only_new_results() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/search"))
+            .respond_with(search_response(&["1"]))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/search"))
+            .respond_with(search_response(&["1", "2"]))
+            .mount(&mock_server)
+            .await;
+        let mut sender = MockSender::new();
+        sender.expect_send_progress_indication()
+            .returning(|_| Box::pin(async { Ok(()) }));
+        sender.expect_send()
+            .withf(|destination, response|
+                *destination == 2 && matches!(response, Response::Notice(message) if message.starts_with("Saved the search \"ubuntu\"")))
+            .times(1)
+            .returning(|_, _| Box::pin(async { Ok(()) }));
+        sender.expect_send()
+            .withf(|destination, response|
+                *destination == 2 && matches!(response, Response::Notice(message) if message == "New results for \"ubuntu\":"))
+            .times(1)
+            .returning(|_, _| Box::pin(async { Ok(()) }));
+        sender.expect_send()
+            .withf(|destination, response|
+                *destination == 2 && matches!(response, Response::Results { results, .. }
+                    if results.len() == 1 && results[0].search_result.guid == "2"))
+            .times(1)
+            .returning(|_, _| Box::pin(async { Ok(()) }));
+        let input_handler = input_handler(&mock_server, sender);
+
+        input_handler.handle(Box::new(TestInput(Command::Watch("ubuntu".into())))).await.unwrap();
+        input_handler.check_watches().await;
+        input_handler.check_watches().await;
+    }
+
+    #[tokio::test]
+    async fn saved_search_downloads_result_matching_rules() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/search"))
+            .respond_with(search_response(&[]))
+            .up_to_n_times(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/search"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                "[{\"guid\":\"1\",\"indexerId\":1,\"title\":\"Ubuntu 720p\",\"size\":20000,\
+                \"publishDate\":\"2015-05-15T00:00:00Z\",\"infoUrl\":\"info url\",\"seeders\":10,\"leechers\":0},\
+                {\"guid\":\"2\",\"indexerId\":1,\"title\":\"Ubuntu 1080p\",\"size\":20000,\
+                \"publishDate\":\"2015-05-15T00:00:00Z\",\"infoUrl\":\"info url\",\"seeders\":5,\"leechers\":0,\
+                \"magnetUrl\":\"magnet:?xt=urn:btih:c811b41641a09d192b8ed81b14064fff55d85ce3\"}]"))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/search"))
+            .and(body_partial_json(json!({ "guid": "2" })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let mut sender = MockSender::new();
+        sender.expect_send_progress_indication()
+            .returning(|_| Box::pin(async { Ok(()) }));
+        sender.expect_send()
+            .withf(|_, response| matches!(response, Response::Notice(_) | Response::Results { .. }))
+            .times(3)
+            .returning(|_, _| Box::pin(async { Ok(()) }));
+        sender.expect_send()
+            .withf(|destination, response| *destination == 2 && matches!(response, Response::Notice(message)
+                if message == "Sent \"Ubuntu 1080p\" for downloading as it matches the rules of the saved search."))
+            .times(1)
+            .returning(|_, _| Box::pin(async { Ok(()) }));
+        let input_handler = input_handler(&mock_server, sender);
+
+        input_handler.handle(Box::new(TestInput(Command::Watch("ubuntu | quality=1080p".into())))).await.unwrap();
+        input_handler.check_watches().await;
+
+        let users = input_handler.downloads_tracker.remove("c811b41641a09d192b8ed81b14064fff55d85ce3".to_string());
+        assert_eq!(users.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn invalid_download_rules() {
+        let mock_server = prowlarr_mock(search_response(&[])).await;
+        let mut sender = MockSender::new();
+        sender.expect_send()
+            .withf(|_, response| matches!(response, Response::Error(message)
+                if message.starts_with("Could not understand the download rules: unknown rule \"year=2023\"")))
+            .times(1)
+            .returning(|_, _| Box::pin(async { Ok(()) }));
+
+        input_handler(&mock_server, sender)
+            .handle(Box::new(TestInput(Command::Watch("ubuntu | year=2023".into()))))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn list_and_remove_saved_searches() {
+        let mock_server = prowlarr_mock(search_response(&[])).await;
+        let mut sender = MockSender::new();
+        sender.expect_send_progress_indication()
+            .returning(|_| Box::pin(async { Ok(()) }));
+        sender.expect_send()
+            .withf(|_, response| matches!(response, Response::Notice(message) if message.starts_with("Saved the search")))
+            .times(1)
+            .returning(|_, _| Box::pin(async { Ok(()) }));
+        sender.expect_send()
+            .withf(|_, response| matches!(response, Response::Notice(message) if message.ends_with(":\n1. ubuntu")))
+            .times(1)
+            .returning(|_, _| Box::pin(async { Ok(()) }));
+        sender.expect_send()
+            .withf(|_, response| matches!(response, Response::Notice(message) if message == "Removed the saved search \"ubuntu\"."))
+            .times(1)
+            .returning(|_, _| Box::pin(async { Ok(()) }));
+        sender.expect_send()
+            .withf(|_, response| matches!(response, Response::Error(message) if message == "There is no saved search with this number."))
+            .times(1)
+            .returning(|_, _| Box::pin(async { Ok(()) }));
+        let input_handler = input_handler(&mock_server, sender);
+
+        input_handler.handle(Box::new(TestInput(Command::Watch("ubuntu".into())))).await.unwrap();
+        input_handler.handle(Box::new(TestInput(Command::Watches))).await.unwrap();
+        input_handler.handle(Box::new(TestInput(Command::Unwatch("1".into())))).await.unwrap();
+        input_handler.handle(Box::new(TestInput(Command::Unwatch("1".into())))).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn show_unknown_result() {
+        let mock_server = prowlarr_mock(ResponseTemplate::new(200).set_body_string("[]")).await;
+        let mut sender = MockSender::new();
+        sender.expect_send()
+            .withf(|destination, response|
+                *destination == 2
+                    && matches!(response, Response::Error(message) if message.starts_with("Could not find the link")))
+            .times(1)
+            .returning(|_, _| Box::pin(async { Ok(()) }));
+
+        input_handler(&mock_server, sender)
+            .handle(Box::new(TestInput(Command::ShowResult("unknown".into()))))
+            .await
+            .unwrap();
+    }
+
+    fn library_with_sonarr(mock_server: &MockServer) -> Library {
+        let sonarr = temp_env::with_vars(
+            [("SONARR_BASE_URL", Some(mock_server.uri())), ("SONARR_API_KEY", Some("key".to_string()))],
+            || ArrClient::from_env(ArrKind::Sonarr));
+        Library::new(sonarr, None, uuid_mapper::create::<LibraryItem>())
+    }
+
+    /// Records sent responses as text, choices rendered as "label: command" lines.
+    fn recording_sender(sent: Arc<Mutex<Vec<String>>>) -> MockSender {
+        let mut sender = MockSender::new();
+        sender.expect_send_progress_indication()
+            .returning(|_| Box::pin(async { Ok(()) }));
+        sender.expect_send()
+            .returning(move |_, response| {
+                let text = match response {
+                    Response::Choices { heading, choices } => choices.iter()
+                        .fold(heading.clone(), |text, choice| format!("{text}\n{}: {:?}", choice.label, choice.command)),
+                    Response::Notice(message) | Response::Error(message) => message.clone(),
+                    _ => "unexpected response".to_string(),
+                };
+                sent.lock().unwrap().push(text);
+                Box::pin(async { Ok(()) })
+            });
+        sender
+    }
+
+    #[tokio::test]
+    async fn add_series_to_library() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/series/lookup"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([
+                { "title": "The Office", "year": 2005, "tvdbId": 73244 },
+                { "title": "The Office", "year": 2001, "tvdbId": 78107, "id": 7 }
+            ])))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/qualityprofile"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([{ "id": 1, "name": "Any" }, { "id": 4, "name": "HD-1080p" }])))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/v3/rootfolder"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!([{ "path": "/tv" }])))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v3/series"))
+            .and(body_partial_json(json!({ "tvdbId": 73244, "qualityProfileId": 4, "rootFolderPath": "/tv" })))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let input_handler = input_handler(&mock_server, recording_sender(sent.clone()))
+            .with_library(library_with_sonarr(&mock_server));
+
+        input_handler.handle(Box::new(TestInput(Command::Series("the office".into())))).await.unwrap();
+        let uuid = sent.lock().unwrap()[0].split("Some(Add(\"").nth(1).unwrap().split('"').next().unwrap().to_string();
+        input_handler.handle(Box::new(TestInput(Command::Add(uuid.clone().into())))).await.unwrap();
+        input_handler.handle(Box::new(TestInput(Command::Add(format!("{uuid}_4").into())))).await.unwrap();
+
+        assert_eq!(*sent.lock().unwrap(), vec![
+            format!("Which one should I add?\nThe Office (2005): Some(Add(\"{uuid}\"))\nThe Office (2001), already in the library: None"),
+            format!("Pick a quality for \"The Office (2005)\":\nAny: Some(Add(\"{uuid}_1\"))\nHD-1080p: Some(Add(\"{uuid}_4\"))"),
+            "Added \"The Office (2005)\" to Sonarr. It'll be downloaded once found.".to_string(),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn library_not_configured() {
+        let mock_server = prowlarr_mock(search_response(&[])).await;
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let input_handler = input_handler(&mock_server, recording_sender(sent.clone()))
+            .with_library(library_with_sonarr(&mock_server));
+
+        input_handler.handle(Box::new(TestInput(Command::Movie("alien".into())))).await.unwrap();
+        input_handler.handle(Box::new(TestInput(Command::Add("unknown_4".into())))).await.unwrap();
+
+        assert_eq!(*sent.lock().unwrap(), vec![
+            "Radarr isn't set up. Please contact support.".to_string(),
+            "Could not find the link, probably it's out of date. Please repeat your search to get new links.".to_string(),
+        ]);
+    }
+
+    #[tokio::test]
+    async fn submitted_magnet_link_is_downloaded_and_tracked() {
+        let mock_server = prowlarr_mock(search_response(&[])).await;
+        let magnet = DownloadMeta::MagnetLink("magnet:?xt=urn:btih:c811b41641a09d192b8ed81b14064fff55d85ce3".to_string());
+        let mut download_client = MockDownloadClient::new();
+        let expected = magnet.clone();
+        download_client.expect_add()
+            .withf(move |torrent| *torrent == expected)
+            .times(1)
+            .returning(|_| Box::pin(async { Ok(()) }));
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let downloads_tracker = Arc::new(DownloadsTracker::new());
+        let input_handler = temp_env::with_vars(
+            [("PROWLARR_API_KEY", Some("key123")), ("PROWLARR_BASE_URL", Some(&mock_server.uri()))],
+            || InputHandler::new(
+                ProwlarrClient::from_env(),
+                uuid_mapper::create::<TorrentMeta>(),
+                downloads_tracker.clone(),
+                vec![],
+                Arc::new(recording_sender(sent.clone()))))
+            .with_download_client(Box::new(download_client));
+
+        input_handler.handle(Box::new(TestInput(Command::Submit(magnet)))).await.unwrap();
+        input_handler.handle(Box::new(TestInput(Command::Submit(DownloadMeta::TorrentFile(Bytes::new()))))).await.unwrap();
+
+        assert_eq!(*sent.lock().unwrap(), vec![
+            "Sent for downloading".to_string(),
+            "Could not read the torrent. Please send a magnet link or a .torrent file.".to_string(),
+        ]);
+        assert_eq!(downloads_tracker.remove("c811b41641a09d192b8ed81b14064fff55d85ce3".to_string()).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_upload_is_reported() {
+        let mock_server = prowlarr_mock(search_response(&[])).await;
+        let sent = Arc::new(Mutex::new(Vec::new()));
+
+        input_handler(&mock_server, recording_sender(sent.clone()))
+            .handle(Box::new(TestInput(Command::UploadFailed)))
+            .await
+            .unwrap();
+
+        assert_eq!(*sent.lock().unwrap(), vec!["Could not receive the file. Please try sending it again.".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn only_allowed_users() {
+        let mock_server = prowlarr_mock(search_response(&[])).await;
+        let input_handler = temp_env::with_vars(
+            [("PROWLARR_API_KEY", Some("key123")), ("PROWLARR_BASE_URL", Some(&mock_server.uri()))],
+            || InputHandler::new(
+                ProwlarrClient::from_env(),
+                uuid_mapper::create::<TorrentMeta>(),
+                Arc::new(DownloadsTracker::new()),
+                vec![1],
+                Arc::new(MockSender::new())));
+
+        assert!(input_handler.is_allowed(1));
+        assert!(!input_handler.is_allowed(2));
+    }
+
+    #[tokio::test]
+    async fn submitted_torrent_without_download_client() {
+        let mock_server = prowlarr_mock(search_response(&[])).await;
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let magnet = DownloadMeta::MagnetLink("magnet:?xt=urn:btih:c811b41641a09d192b8ed81b14064fff55d85ce3".to_string());
+
+        input_handler(&mock_server, recording_sender(sent.clone()))
+            .handle(Box::new(TestInput(Command::Submit(magnet))))
+            .await
+            .unwrap();
+
+        assert_eq!(*sent.lock().unwrap(), vec![
+            "Downloading torrents sent to the bot isn't set up. Please contact support.".to_string(),
+        ]);
+    }
 }
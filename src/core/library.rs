@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::core::arr::{ArrClient, ArrKind, QualityProfile};
+use crate::core::traits::input::ItemUuid;
+use crate::core::traits::uuid_mapper::{MapperError, UuidMapper};
+
+/// A series or a movie found by a lookup, as Sonarr or Radarr describes it, so it can be added as it is.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct LibraryItem {
+    pub kind: ArrKind,
+    pub item: Value,
+}
+
+impl LibraryItem {
+    /// Title along with the year, as remakes share titles.
+    pub fn title(&self) -> String {
+        let title = self.item["title"].as_str().unwrap_or_default();
+        match self.item["year"].as_u64() {
+            Some(year) if year > 0 => format!("{title} ({year})"),
+            _ => title.to_string(),
+        }
+    }
+
+    /// Looked up items that are already in the library have an id there.
+    pub fn in_library(&self) -> bool {
+        self.item["id"].as_u64().is_some_and(|id| id > 0)
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum LibraryError {
+    #[error("{0} isn't configured")]
+    NotConfigured(ArrKind),
+    #[error("Error when interacting with {0}: {1}")]
+    Arr(ArrKind, String),
+    #[error(transparent)]
+    Mapper(#[from] MapperError),
+    #[error("Library item for uuid {0} not found")]
+    NotFound(ItemUuid),
+}
+
+/// Series and movies managed by Sonarr and Radarr, either of which may be left unconfigured.
+pub struct Library {
+    sonarr: Option<ArrClient>,
+    radarr: Option<ArrClient>,
+    items: Box<dyn UuidMapper<LibraryItem>>,
+}
+
+const LOOKUP_RESULTS_COUNT: usize = 10;
+
+impl Library {
+    pub fn new(sonarr: Option<ArrClient>, radarr: Option<ArrClient>, items: Box<dyn UuidMapper<LibraryItem>>) -> Library {
+        Library { sonarr, radarr, items }
+    }
+
+    fn client(&self, kind: ArrKind) -> Result<&ArrClient, LibraryError> {
+        match kind {
+            ArrKind::Sonarr => self.sonarr.as_ref(),
+            ArrKind::Radarr => self.radarr.as_ref(),
+        }.ok_or(LibraryError::NotConfigured(kind))
+    }
+
+    /// Looks up series or movies by a name, each saved under a new uuid to be added by.
+    pub async fn lookup(&self, kind: ArrKind, term: &str) -> Result<Vec<(String, LibraryItem)>, LibraryError> {
+        let items: Vec<LibraryItem> = self.client(kind)?
+            .lookup(term)
+            .await
+            .map_err(|err| LibraryError::Arr(kind, err.to_string()))?
+            .into_iter()
+            .take(LOOKUP_RESULTS_COUNT)
+            .map(|item| LibraryItem { kind, item })
+            .collect();
+        let uuids = self.items.put_all(items.clone()).await?;
+        Ok(uuids.into_iter().zip(items).collect())
+    }
+
+    pub async fn get(&self, uuid: &str) -> Result<LibraryItem, LibraryError> {
+        self.items.get(uuid)
+            .await?
+            .ok_or_else(|| LibraryError::NotFound(uuid.into()))
+    }
+
+    pub async fn quality_profiles(&self, kind: ArrKind) -> Result<Vec<QualityProfile>, LibraryError> {
+        self.client(kind)?
+            .quality_profiles()
+            .await
+            .map_err(|err| LibraryError::Arr(kind, err.to_string()))
+    }
+
+    pub async fn add(&self, item: &LibraryItem, quality_profile_id: u32) -> Result<(), LibraryError> {
+        self.client(item.kind)?
+            .add(&item.item, quality_profile_id)
+            .await
+            .map_err(|err| LibraryError::Arr(item.kind, err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::core::arr::ArrKind;
+    use crate::core::library::LibraryItem;
+
+    #[test]
+    fn title_with_year() {
+        let item = LibraryItem { kind: ArrKind::Radarr, item: json!({ "title": "Alien", "year": 1979 }) };
+        let unreleased = LibraryItem { kind: ArrKind::Radarr, item: json!({ "title": "Alien 9", "year": 0 }) };
+
+        assert_eq!(item.title(), "Alien (1979)");
+        assert_eq!(unreleased.title(), "Alien 9");
+    }
+
+    #[test]
+    fn in_library() {
+        assert!(LibraryItem { kind: ArrKind::Sonarr, item: json!({ "title": "The Office", "id": 3 }) }.in_library());
+        assert!(!LibraryItem { kind: ArrKind::Sonarr, item: json!({ "title": "The Office", "id": 0 }) }.in_library());
+        assert!(!LibraryItem { kind: ArrKind::Sonarr, item: json!({ "title": "The Office" }) }.in_library());
+    }
+}
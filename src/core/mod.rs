@@ -1,5 +1,10 @@
 use thiserror::Error;
 
+use crate::core::prowlarr::ProwlarrError;
+use crate::core::suspicious::Suspicion;
+use crate::core::traits::input::ItemUuid;
+use crate::core::traits::uuid_mapper::MapperError;
+
 pub mod input_handler;
 pub mod traits;
 pub mod downloads_tracker;
@@ -8,6 +13,18 @@ pub mod util;
 pub mod completion;
 pub mod torrent_meta;
 pub mod download_meta;
+pub mod response;
+pub mod deduplication;
+pub mod watches;
+pub mod download_rules;
+pub mod arr;
+pub mod library;
+pub mod id_search;
+pub mod torrent_files;
+pub mod suspicious;
+pub mod search_cache;
+pub mod circuit_breaker;
+pub mod health;
 
 #[derive(Error, Debug)]
 pub enum HandlingError {
@@ -16,3 +33,30 @@ pub enum HandlingError {
 }
 
 pub type HandlingResult = Result<(), HandlingError>;
+
+#[derive(Error, Debug)]
+pub enum OperationError {
+    #[error("Error when interacting with Prowlarr: {0}")]
+    Prowlarr(String),
+    #[error("Prowlarr is unavailable")]
+    ProwlarrUnavailable,
+    #[error(transparent)]
+    Mapper(#[from] MapperError),
+    #[error("Download response from Prowlarr wasn't successful: {0}")]
+    DownloadRejected(String),
+    #[error("Link for uuid {0} not found")]
+    NotFound(ItemUuid),
+    #[error("Neither magnet nor download link exist for torrent {0}")]
+    NoLink(String),
+    #[error("Not downloading suspicious release {0}: {1:?}")]
+    Suspicious(String, Vec<Suspicion>),
+}
+
+impl From<ProwlarrError> for OperationError {
+    fn from(err: ProwlarrError) -> Self {
+        match err {
+            ProwlarrError::Unavailable => OperationError::ProwlarrUnavailable,
+            err => OperationError::Prowlarr(err.to_string()),
+        }
+    }
+}
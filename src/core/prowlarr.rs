@@ -1,36 +1,145 @@
+use std::time::Duration;
 use std::{env, fs};
 
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
 use reqwest::header::{CONTENT_TYPE, LOCATION};
-use reqwest::{Client, Response};
-use serde::{Deserialize, Serialize};
+use reqwest::{Client, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+use thiserror::Error;
 use url::Url;
 
+use crate::core::circuit_breaker::CircuitBreaker;
 use crate::core::download_meta::{DownloadMeta, DownloadMetaProvider};
+use crate::core::health::{Health, HealthIssue, IndexerHealth};
+use crate::core::id_search::IdSearch;
+use crate::core::search_cache::{CacheStats, SearchResultsCache};
 
 pub struct ProwlarrClient {
     api_key: String,
     base_url: Url,
     limit_param: String,
-    indexer_id_params: String,
+    indexer_ids: Vec<u32>,
     client: Client,
+    retries: u32,
+    circuit_breaker: CircuitBreaker,
+    cache: Option<SearchResultsCache>,
 }
 
-#[derive(Deserialize)]
+#[derive(Error, Debug)]
+pub enum ProwlarrError {
+    #[error("Prowlarr is considered unavailable after repeated failures")]
+    Unavailable,
+    #[error(transparent)]
+    Request(#[from] reqwest::Error),
+    #[error("{0}")]
+    Response(String),
+}
+
+#[derive(Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchResult {
     pub guid: String,
     pub indexer_id: u8,
+    #[serde(default)]
+    pub indexer: Option<String>,
     pub title: String,
     pub size: u128,
     pub publish_date: DateTime<Utc>,
     pub download_url: Option<String>,
     pub magnet_url: Option<String>,
     pub info_url: String,
+    /// Usenet results have no peers, Prowlarr sends null for them.
+    #[serde(default, deserialize_with = "null_as_default")]
     pub seeders: u32,
+    #[serde(default, deserialize_with = "null_as_default")]
     pub leechers: u32,
     pub grabs: Option<u32>,
+    #[serde(default)]
+    pub categories: Vec<Category>,
+    #[serde(default)]
+    pub indexer_flags: Vec<String>,
+    /// Prowlarr sends 0 when the id is unknown.
+    #[serde(default)]
+    pub imdb_id: u32,
+    #[serde(default)]
+    pub info_hash: Option<String>,
+    #[serde(default)]
+    pub protocol: Protocol,
+    /// Names of the other indexers carrying the release, filled in when copies of it are merged.
+    #[serde(default)]
+    pub other_indexers: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Category {
+    pub id: u32,
+    pub name: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    #[default]
+    Torrent,
+    Usenet,
+    #[serde(other)]
+    Unknown,
+}
+
+/// Categories with ids from this one on are indexer-specific, the ones below are Newznab standard categories.
+const CUSTOM_CATEGORIES_START: u32 = 100000;
+
+impl SearchResult {
+    /// Names of the standard categories of the result, indexer-specific ones are skipped.
+    pub fn category_names(&self) -> impl Iterator<Item = &str> {
+        self.categories.iter()
+            .filter(|category| category.id < CUSTOM_CATEGORIES_START)
+            .filter_map(|category| category.name.as_deref())
+    }
+
+    pub fn imdb_url(&self) -> Option<String> {
+        (self.imdb_id != 0).then(|| format!("https://www.imdb.com/title/tt{:07}/", self.imdb_id))
+    }
+
+    /// Seeders of a torrent, or grabs of a Usenet release, which has no peers.
+    pub fn popularity(&self) -> u32 {
+        match self.protocol {
+            Protocol::Usenet => self.grabs.unwrap_or_default(),
+            Protocol::Torrent | Protocol::Unknown => self.seeders,
+        }
+    }
+}
+
+fn null_as_default<'de, D: Deserializer<'de>, T: Default + Deserialize<'de>>(deserializer: D) -> Result<T, D::Error> {
+    Option::<T>::deserialize(deserializer).map(Option::unwrap_or_default)
+}
+
+/// Whether a flag means downloading doesn't count, fully or partially, towards the user's ratio:
+/// `freeleech`, `freeleech25`, `freeleech75` and `halfleech`.
+pub fn is_freeleech_flag(flag: &str) -> bool {
+    flag.starts_with("freeleech") || flag == "halfleech"
+}
+
+#[derive(Deserialize)]
+struct Indexer {
+    id: u32,
+    #[serde(default)]
+    name: String,
+    enable: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexerStatus {
+    indexer_id: u32,
+    disabled_till: Option<DateTime<Utc>>,
+}
+
+#[derive(Deserialize)]
+struct SystemStatus {
+    version: String,
 }
 
 #[derive(Serialize)]
@@ -40,11 +149,27 @@ struct DownloadParams<'a> {
     indexer_id: &'a u8,
 }
 
+const NZB_CONTENT_TYPE: &str = "application/x-nzb";
+
 const PROWLARR_API_KEY_ENV: &str = "PROWLARR_API_KEY";
 const PROWLARR_API_KEY_FILE_ENV: &str = "PROWLARR_API_KEY_FILE";
 const PROWLARR_BASE_URL_ENV: &str = "PROWLARR_BASE_URL";
 const PROWLARR_DEFAULT_LIMIT_PARAM_ENV: &str = "PROWLARR_DEFAULT_LIMIT_PARAM";
 const PROWLARR_INDEXER_IDS_ENV: &str = "PROWLARR_INDEXER_IDS";
+const PROWLARR_CONNECT_TIMEOUT_ENV: &str = "PROWLARR_CONNECT_TIMEOUT";
+const PROWLARR_TIMEOUT_ENV: &str = "PROWLARR_TIMEOUT";
+const PROWLARR_RETRIES_ENV: &str = "PROWLARR_RETRIES";
+const PROWLARR_BREAKER_FAILURES_ENV: &str = "PROWLARR_BREAKER_FAILURES";
+const PROWLARR_BREAKER_SECONDS_ENV: &str = "PROWLARR_BREAKER_SECONDS";
+
+const DEFAULT_CONNECT_TIMEOUT_SECONDS: u64 = 10;
+/// Prowlarr waits for slow indexers for up to 100 seconds by default, so a search may take as long.
+const DEFAULT_TIMEOUT_SECONDS: u64 = 120;
+const DEFAULT_RETRIES: u64 = 2;
+const DEFAULT_BREAKER_FAILURES: u64 = 5;
+const DEFAULT_BREAKER_SECONDS: u64 = 60;
+/// Delay before the first retry, doubled for each next one.
+const RETRY_DELAY: Duration = Duration::from_millis(500);
 
 impl ProwlarrClient {
     pub fn from_env() -> ProwlarrClient {
@@ -52,11 +177,61 @@ impl ProwlarrClient {
             api_key: get_api_key(),
             base_url: ProwlarrClient::parse_base_url(),
             limit_param: ProwlarrClient::get_limit_param(),
-            indexer_id_params: ProwlarrClient::get_indexer_id_params(),
-            client: Client::new(),
+            indexer_ids: ProwlarrClient::get_indexer_ids(),
+            client: Client::builder()
+                .connect_timeout(Duration::from_secs(get_number(PROWLARR_CONNECT_TIMEOUT_ENV, DEFAULT_CONNECT_TIMEOUT_SECONDS)))
+                .timeout(Duration::from_secs(get_number(PROWLARR_TIMEOUT_ENV, DEFAULT_TIMEOUT_SECONDS)))
+                .build()
+                .expect("Could not create an HTTP client"),
+            retries: get_number(PROWLARR_RETRIES_ENV, DEFAULT_RETRIES) as u32,
+            circuit_breaker: CircuitBreaker::new(
+                get_number(PROWLARR_BREAKER_FAILURES_ENV, DEFAULT_BREAKER_FAILURES) as u32,
+                Duration::from_secs(get_number(PROWLARR_BREAKER_SECONDS_ENV, DEFAULT_BREAKER_SECONDS))),
+            cache: None,
         }
     }
 
+    /// Sends a request built by `request`, retrying idempotent ones with a growing delay if they can't connect
+    /// or get a server error. Timeouts aren't retried, since users would wait for several of them in a row.
+    /// All of these failures open the circuit breaker, after which requests fail right away until it lets them through again.
+    async fn send(&self, request: impl Fn() -> RequestBuilder, idempotent: bool) -> Result<Response, ProwlarrError> {
+        let Some(call) = self.circuit_breaker.call() else {
+            return Err(ProwlarrError::Unavailable);
+        };
+        let attempts = if idempotent { self.retries + 1 } else { 1 };
+        let mut delay = RETRY_DELAY;
+        let mut attempt = 1;
+        loop {
+            let result = request().send().await;
+            let (failed, retryable) = match &result {
+                Ok(response) => (response.status().is_server_error(), response.status().is_server_error()),
+                Err(err) => (err.is_timeout() || err.is_connect(), err.is_connect()),
+            };
+            if !retryable || attempt == attempts {
+                call.record(!failed);
+                return Ok(result?);
+            }
+            log::warn!("Prowlarr request failed, retrying in {:?}: {}", delay, match result {
+                Ok(response) => response.status().to_string(),
+                Err(err) => err.to_string(),
+            });
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+            attempt += 1;
+        }
+    }
+
+    /// Serves repeated searches from the cache until their results expire.
+    pub fn with_cache(mut self, cache: SearchResultsCache) -> ProwlarrClient {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Hits and misses of the search cache, `None` if searches aren't cached.
+    pub fn cache_stats(&self) -> Option<CacheStats> {
+        self.cache.as_ref().map(SearchResultsCache::stats)
+    }
+
     fn get_limit_param() -> String {
         match env::var(PROWLARR_DEFAULT_LIMIT_PARAM_ENV) {
             Ok(val) => {
@@ -76,63 +251,126 @@ impl ProwlarrClient {
                 panic!("Could not parse {}: {}: \"{}\"", PROWLARR_BASE_URL_ENV, err, url_string))
     }
 
-    fn get_indexer_id_params() -> String {
-        let params = env::var(PROWLARR_INDEXER_IDS_ENV)
+    fn get_indexer_ids() -> Vec<u32> {
+        env::var(PROWLARR_INDEXER_IDS_ENV)
             .unwrap_or_default()
             .split(',')
             .filter(|indexer_id| !indexer_id.is_empty())
             .map(|user| user.parse::<u32>()
                 .unwrap_or_else(|_| panic!("{} list must be a comma-separated \
                 string of integers. Value \"{}\" is unexpected", PROWLARR_INDEXER_IDS_ENV, user)))
-            .map(|n| format!("indexerIds={}", n))
-            .collect::<Vec<String>>()
-            .join("&");
-        if params.is_empty() {
-            params
-        } else {
-            format!("&{}", params)
-        }
+            .collect()
     }
 
-    pub async fn search(&self, query: &str) -> reqwest::Result<Vec<SearchResult>> {
-        self.client.get(format!("{}api/v1/search?apikey={}{}&query={}{}", self.base_url,
-                                self.api_key, self.limit_param, query, self.indexer_id_params))
-            .send()
+    fn indexer_id_params(&self) -> String {
+        self.indexer_ids.iter()
+            .map(|n| format!("&indexerIds={}", n))
+            .collect()
+    }
+
+    /// Searches by a title, or by an IMDb, TMDb or TVDb id if the query is one or links to one.
+    /// Cached results are returned unless `refresh` is set, in which case they're replaced with new ones.
+    pub async fn search(&self, query: &str, refresh: bool) -> Result<Vec<SearchResult>, ProwlarrError> {
+        let id_search = IdSearch::parse(query);
+        let (search_type, query_params) = match &id_search {
+            Some(id_search) => (id_search.search_type(), id_search.prowlarr_params()),
+            None => ("search", format!("&query={}", query)),
+        };
+        let cache_key = SearchResultsCache::key(query, &self.indexer_ids, search_type);
+        if let Some(cache) = self.cache.as_ref().filter(|_| !refresh) {
+            if let Some(results) = cache.get(&cache_key).await {
+                log::info!("Found {} cached results of \"{}\"", results.len(), query);
+                return Ok(results);
+            }
+        }
+        let url = format!("{}api/v1/search?apikey={}{}{}{}", self.base_url,
+                          self.api_key, self.limit_param, query_params, self.indexer_id_params());
+        let results = self.send(|| self.client.get(&url), true)
             .await?
             .json::<Vec<SearchResult>>()
-            .await
+            .await?;
+        if let Some(cache) = &self.cache {
+            cache.put(&cache_key, &results).await;
+        }
+        Ok(results)
+    }
+
+    /// Number of enabled indexers a search will be sent to.
+    pub async fn count_indexers(&self) -> Result<usize, ProwlarrError> {
+        Ok(self.get::<Vec<Indexer>>("indexer")
+            .await?
+            .iter()
+            .filter(|indexer| indexer.enable)
+            .filter(|indexer| self.is_used(indexer.id))
+            .count())
+    }
+
+    /// Version, health check results and the state of the indexers searches are sent to.
+    /// Requested one after another up to the first failure, so that an unreachable Prowlarr
+    /// counts as a single failure for the circuit breaker.
+    pub async fn health(&self) -> Result<Health, ProwlarrError> {
+        let status = self.get::<SystemStatus>("system/status").await?;
+        let issues = self.get::<Vec<HealthIssue>>("health").await?;
+        let indexers = self.get::<Vec<Indexer>>("indexer").await?;
+        let statuses = self.get::<Vec<IndexerStatus>>("indexerstatus").await?;
+        let indexers = indexers.into_iter()
+            .filter(|indexer| self.is_used(indexer.id))
+            .map(|indexer| IndexerHealth {
+                disabled_till: statuses.iter()
+                    .find(|status| status.indexer_id == indexer.id)
+                    .and_then(|status| status.disabled_till),
+                name: indexer.name,
+                enabled: indexer.enable,
+            })
+            .collect();
+        Ok(Health { version: status.version, issues, indexers })
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, ProwlarrError> {
+        let url = format!("{}api/v1/{}?apikey={}", self.base_url, path, self.api_key);
+        Ok(self.send(|| self.client.get(&url), true)
+            .await?
+            .json::<T>()
+            .await?)
     }
 
-    pub async fn download(&self, indexer_id: &u8, guid: &str) -> reqwest::Result<Response> {
-        self.client.post(format!("{}api/v1/search?apikey={}", self.base_url, self.api_key))
+    fn is_used(&self, indexer_id: u32) -> bool {
+        self.indexer_ids.is_empty() || self.indexer_ids.contains(&indexer_id)
+    }
+
+    /// Isn't retried, as a download that timed out may still have been sent to the download client.
+    pub async fn download(&self, indexer_id: &u8, guid: &str) -> Result<Response, ProwlarrError> {
+        let url = format!("{}api/v1/search?apikey={}", self.base_url, self.api_key);
+        self.send(|| self.client.post(&url)
             .header(CONTENT_TYPE, "application/json")
-            .json(&DownloadParams { guid, indexer_id })
-            .send()
+            .json(&DownloadParams { guid, indexer_id }), false)
             .await
     }
 }
 
 #[async_trait]
 impl DownloadMetaProvider for ProwlarrClient {
-    async fn get_download_meta(&self, download_url: &str) -> Result<DownloadMeta, String> {
-        let response = self.client.get(replace_base_url(download_url, &self.base_url)?)
-            .send()
-            .await
-            .map_err(|err| err.to_string())?;
+    async fn get_download_meta(&self, download_url: &str) -> Result<DownloadMeta, ProwlarrError> {
+        let url = replace_base_url(download_url, &self.base_url).map_err(ProwlarrError::Response)?;
+        let response = self.send(|| self.client.get(&url), true).await?;
         if response.status().is_redirection() {
             let magnet = response.headers().get(LOCATION)
-                .ok_or("Missing expected Location header")?
+                .ok_or(ProwlarrError::Response("Missing expected Location header".to_string()))?
                 .to_str()
-                .map_err(|err| err.to_string())?
+                .map_err(|err| ProwlarrError::Response(err.to_string()))?
                 .to_string();
             Ok(DownloadMeta::MagnetLink(magnet))
         } else if response.status().is_success() {
-            let torrent_file = response.bytes()
-                .await
-                .map_err(|err| err.to_string())?;
-            Ok(DownloadMeta::TorrentFile(torrent_file))
+            let is_nzb = response.headers().get(CONTENT_TYPE)
+                .is_some_and(|content_type| content_type.as_bytes().starts_with(NZB_CONTENT_TYPE.as_bytes()));
+            let file = response.bytes().await?;
+            if is_nzb {
+                Ok(DownloadMeta::NzbFile(file))
+            } else {
+                Ok(DownloadMeta::TorrentFile(file))
+            }
         } else {
-            Err(format!("Unexpected response status code: {}", response.status()))
+            Err(ProwlarrError::Response(format!("Unexpected response status code: {}", response.status())))
         }
     }
 }
@@ -144,6 +382,14 @@ fn replace_base_url(url: &str, base_url: &Url) -> Result<String, String> {
     Ok(url.to_string())
 }
 
+fn get_number(env: &str, default: u64) -> u64 {
+    match env::var(env) {
+        Ok(val) => val.parse()
+            .unwrap_or_else(|_| panic!("{env} must be a non-negative number, got \"{val}\"")),
+        Err(_) => default,
+    }
+}
+
 fn get_env(env: &str) -> String {
     env::var(env).unwrap_or_else(|_| panic!("Cannot get the {env} env variable"))
 }
@@ -220,7 +466,10 @@ mod test {
     }
 
     mod client {
-        use crate::core::prowlarr::{ProwlarrClient, PROWLARR_API_KEY_ENV, PROWLARR_BASE_URL_ENV, PROWLARR_DEFAULT_LIMIT_PARAM_ENV};
+        use crate::core::prowlarr::{ProwlarrClient, ProwlarrError, Protocol, PROWLARR_API_KEY_ENV, PROWLARR_BASE_URL_ENV, PROWLARR_BREAKER_FAILURES_ENV, PROWLARR_BREAKER_SECONDS_ENV,
+                                    PROWLARR_DEFAULT_LIMIT_PARAM_ENV, PROWLARR_INDEXER_IDS_ENV, PROWLARR_RETRIES_ENV, PROWLARR_TIMEOUT_ENV};
+        use crate::core::search_cache::CacheStats;
+        use crate::ext::search_cache;
         use chrono::DateTime;
         use reqwest::header::CONTENT_TYPE;
         use reqwest::StatusCode;
@@ -259,7 +508,7 @@ mod test {
                     (PROWLARR_BASE_URL_ENV, Some(&mock_server.uri()))],
                 ProwlarrClient::from_env);
 
-            let result = prowlarr_client.search("Ubuntu").await.unwrap();
+            let result = prowlarr_client.search("Ubuntu", false).await.unwrap();
 
             assert_eq!(result.len(), 1);
 
@@ -276,6 +525,218 @@ mod test {
             assert_eq!(search_result.seeders, 10);
             assert_eq!(search_result.leechers, 20);
             assert_eq!(search_result.grabs, Some(5));
+            assert!(search_result.categories.is_empty());
+            assert!(search_result.indexer_flags.is_empty());
+            assert_eq!(search_result.imdb_url(), None);
+            assert_eq!(search_result.protocol, Protocol::Torrent);
+        }
+
+        #[tokio::test]
+        async fn search_by_id() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/search"))
+                .and(query_param("type", "tvsearch"))
+                .and(query_param("query", "{TmdbId:1396} {Season:2} {Episode:3}"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("[]"))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+
+            let prowlarr_client = temp_env::with_vars(
+                [(PROWLARR_API_KEY_ENV, Some("key123")),
+                    (PROWLARR_BASE_URL_ENV, Some(&mock_server.uri()))],
+                ProwlarrClient::from_env);
+
+            let result = prowlarr_client.search("https://www.themoviedb.org/tv/1396-breaking-bad/season/2/episode/3", false).await.unwrap();
+
+            assert!(result.is_empty());
+        }
+
+        #[tokio::test]
+        async fn cached_search() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/search"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("[]"))
+                .expect(2)
+                .mount(&mock_server)
+                .await;
+
+            let prowlarr_client = temp_env::with_vars(
+                [(PROWLARR_API_KEY_ENV, Some("key123")),
+                    (PROWLARR_BASE_URL_ENV, Some(mock_server.uri().as_str())),
+                    ("SEARCH_CACHE_TTL_SECONDS", Some("300")),
+                    ("REDIS_URL", None)],
+                || ProwlarrClient::from_env().with_cache(search_cache::create().unwrap()));
+
+            prowlarr_client.search("Ubuntu", false).await.unwrap();
+            prowlarr_client.search(" ubuntu ", false).await.unwrap();
+            prowlarr_client.search("Ubuntu", true).await.unwrap();
+
+            assert_eq!(prowlarr_client.cache_stats(), Some(CacheStats { hits: 1, misses: 1 }));
+        }
+
+        #[tokio::test]
+        async fn search_result_details() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/search"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(
+                        "[{\"guid\":\"101\",\"indexerId\":1,\"indexer\":\"Linux Tracker\",\"title\":\"Title\",\
+                        \"size\":20000,\"publishDate\":\"2015-05-15T00:00:00Z\",\"infoUrl\":\"info url\",\
+                        \"seeders\":10,\"leechers\":20,\"protocol\":\"usenet\",\"imdbId\":133093,\
+                        \"infoHash\":\"ABCDEF\",\"indexerFlags\":[\"freeleech\",\"scene\"],\
+                        \"categories\":[{\"id\":2000,\"name\":\"Movies\",\"subCategories\":[]},\
+                        {\"id\":100002,\"name\":\"Films HD\",\"subCategories\":[]}]}]"))
+                .mount(&mock_server)
+                .await;
+
+            let prowlarr_client = temp_env::with_vars(
+                [(PROWLARR_API_KEY_ENV, Some("key123")),
+                    (PROWLARR_BASE_URL_ENV, Some(&mock_server.uri()))],
+                ProwlarrClient::from_env);
+
+            let result = prowlarr_client.search("Ubuntu", false).await.unwrap();
+
+            let search_result = result.first().unwrap();
+            assert_eq!(search_result.indexer, Some("Linux Tracker".to_string()));
+            assert_eq!(search_result.category_names().collect::<Vec<_>>(), vec!["Movies"]);
+            assert_eq!(search_result.indexer_flags, vec!["freeleech", "scene"]);
+            assert_eq!(search_result.imdb_url(), Some("https://www.imdb.com/title/tt0133093/".to_string()));
+            assert_eq!(search_result.info_hash, Some("ABCDEF".to_string()));
+            assert_eq!(search_result.protocol, Protocol::Usenet);
+        }
+
+        #[tokio::test]
+        async fn usenet_result_without_peers() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/search"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(
+                        "[{\"guid\":\"101\",\"indexerId\":1,\"title\":\"Title\",\"size\":20000,\
+                        \"publishDate\":\"2015-05-15T00:00:00Z\",\"infoUrl\":\"info url\",\
+                        \"seeders\":null,\"grabs\":7,\"protocol\":\"usenet\"}]"))
+                .mount(&mock_server)
+                .await;
+
+            let prowlarr_client = temp_env::with_vars(
+                [(PROWLARR_API_KEY_ENV, Some("key123")),
+                    (PROWLARR_BASE_URL_ENV, Some(&mock_server.uri()))],
+                ProwlarrClient::from_env);
+
+            let result = prowlarr_client.search("Ubuntu", false).await.unwrap();
+
+            let search_result = result.first().unwrap();
+            assert_eq!(search_result.seeders, 0);
+            assert_eq!(search_result.leechers, 0);
+            assert_eq!(search_result.popularity(), 7);
+        }
+
+        #[tokio::test]
+        async fn count_indexers() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/indexer"))
+                .and(query_param("apikey", "key123"))
+                .respond_with(ResponseTemplate::new(200)
+                    .set_body_string(
+                        "[{\"id\":1,\"enable\":true},{\"id\":2,\"enable\":false},\
+                        {\"id\":3,\"enable\":true},{\"id\":4,\"enable\":true}]"))
+                .mount(&mock_server)
+                .await;
+
+            let all_indexers_client = temp_env::with_vars(
+                [(PROWLARR_API_KEY_ENV, Some("key123")),
+                    (PROWLARR_BASE_URL_ENV, Some(&mock_server.uri()))],
+                ProwlarrClient::from_env);
+            let selected_indexers_client = temp_env::with_vars(
+                [(PROWLARR_API_KEY_ENV, Some("key123")),
+                    (PROWLARR_INDEXER_IDS_ENV, Some("1,2,3")),
+                    (PROWLARR_BASE_URL_ENV, Some(&mock_server.uri()))],
+                ProwlarrClient::from_env);
+
+            assert_eq!(all_indexers_client.count_indexers().await.unwrap(), 3);
+            assert_eq!(selected_indexers_client.count_indexers().await.unwrap(), 2);
+        }
+
+        #[tokio::test]
+        async fn health() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/system/status"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("{\"appName\":\"Prowlarr\",\"version\":\"1.24.3\"}"))
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/health"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(
+                    "[{\"source\":\"IndexerStatusCheck\",\"type\":\"warning\",\
+                    \"message\":\"Indexers unavailable due to failures: B\",\"wikiUrl\":\"\"}]"))
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/indexer"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(
+                    "[{\"id\":1,\"name\":\"A\",\"enable\":true},{\"id\":2,\"name\":\"B\",\"enable\":true},\
+                    {\"id\":3,\"name\":\"C\",\"enable\":false},{\"id\":4,\"name\":\"D\",\"enable\":true}]"))
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/indexerstatus"))
+                .and(query_param("apikey", "key123"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(
+                    "[{\"id\":7,\"indexerId\":2,\"disabledTill\":\"2015-05-15T00:30:00Z\",\
+                    \"mostRecentFailure\":\"2015-05-15T00:00:00Z\",\"initialFailure\":\"2015-05-14T00:00:00Z\"}]"))
+                .mount(&mock_server)
+                .await;
+
+            let prowlarr_client = temp_env::with_vars(
+                [(PROWLARR_API_KEY_ENV, Some("key123")),
+                    (PROWLARR_INDEXER_IDS_ENV, Some("1,2,3")),
+                    (PROWLARR_BASE_URL_ENV, Some(&mock_server.uri()))],
+                ProwlarrClient::from_env);
+
+            let health = prowlarr_client.health().await.unwrap();
+
+            assert_eq!(health.version, "1.24.3");
+            assert_eq!(health.issues.len(), 1);
+            assert_eq!(health.indexers.iter().map(|indexer| indexer.name.as_str()).collect::<Vec<_>>(), vec!["A", "B", "C"]);
+            assert_eq!(health.indexers[1].disabled_till, Some(DateTime::from_timestamp(1431649800, 0).unwrap()));
+            assert_eq!(health.problems(DateTime::from_timestamp(1431648000, 0).unwrap()),
+                       vec!["B", "Indexers unavailable due to failures: B"]);
+        }
+
+        #[tokio::test]
+        async fn failed_health_check_is_one_failure() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/system/status"))
+                .respond_with(ResponseTemplate::new(503))
+                .expect(2)
+                .mount(&mock_server)
+                .await;
+            for endpoint in ["/api/v1/health", "/api/v1/indexer", "/api/v1/indexerstatus"] {
+                Mock::given(method("GET"))
+                    .and(path(endpoint))
+                    .respond_with(ResponseTemplate::new(503))
+                    .expect(0)
+                    .mount(&mock_server)
+                    .await;
+            }
+
+            let prowlarr_client = temp_env::with_vars(
+                [(PROWLARR_API_KEY_ENV, Some("key123")),
+                    (PROWLARR_RETRIES_ENV, Some("0")),
+                    (PROWLARR_BREAKER_FAILURES_ENV, Some("2")),
+                    (PROWLARR_BASE_URL_ENV, Some(&mock_server.uri()))],
+                ProwlarrClient::from_env);
+
+            assert!(matches!(prowlarr_client.health().await, Err(ProwlarrError::Request(_))));
+            assert!(matches!(prowlarr_client.health().await, Err(ProwlarrError::Request(_))));
+            assert!(matches!(prowlarr_client.health().await, Err(ProwlarrError::Unavailable)));
         }
 
         #[tokio::test]
@@ -299,6 +760,144 @@ mod test {
             assert_eq!(result.status(), StatusCode::OK);
         }
 
+        #[tokio::test]
+        async fn search_is_retried_after_server_error() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/search"))
+                .respond_with(ResponseTemplate::new(503))
+                .up_to_n_times(1)
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/search"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("[]"))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+
+            let prowlarr_client = temp_env::with_vars(
+                [(PROWLARR_API_KEY_ENV, Some("key123")),
+                    (PROWLARR_BASE_URL_ENV, Some(&mock_server.uri()))],
+                ProwlarrClient::from_env);
+
+            assert!(prowlarr_client.search("Ubuntu", false).await.unwrap().is_empty());
+        }
+
+        #[tokio::test]
+        async fn search_is_not_retried_after_timeout() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/search"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("[]").set_delay(std::time::Duration::from_secs(2)))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+
+            let prowlarr_client = temp_env::with_vars(
+                [(PROWLARR_API_KEY_ENV, Some("key123")),
+                    (PROWLARR_TIMEOUT_ENV, Some("1")),
+                    (PROWLARR_BASE_URL_ENV, Some(&mock_server.uri()))],
+                ProwlarrClient::from_env);
+
+            assert!(matches!(prowlarr_client.search("Ubuntu", false).await, Err(ProwlarrError::Request(err)) if err.is_timeout()));
+        }
+
+        #[tokio::test]
+        async fn download_is_not_retried() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("POST"))
+                .and(path("/api/v1/search"))
+                .respond_with(ResponseTemplate::new(503))
+                .expect(1)
+                .mount(&mock_server)
+                .await;
+
+            let prowlarr_client = temp_env::with_vars(
+                [(PROWLARR_API_KEY_ENV, Some("key123")),
+                    (PROWLARR_BASE_URL_ENV, Some(&mock_server.uri()))],
+                ProwlarrClient::from_env);
+
+            let result = prowlarr_client.download(&1, "guid123").await.unwrap();
+
+            assert_eq!(result.status(), StatusCode::SERVICE_UNAVAILABLE);
+        }
+
+        #[tokio::test]
+        async fn circuit_opens_after_failures() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/indexer"))
+                .respond_with(ResponseTemplate::new(503))
+                .expect(2)
+                .mount(&mock_server)
+                .await;
+
+            let prowlarr_client = temp_env::with_vars(
+                [(PROWLARR_API_KEY_ENV, Some("key123")),
+                    (PROWLARR_RETRIES_ENV, Some("0")),
+                    (PROWLARR_BREAKER_FAILURES_ENV, Some("2")),
+                    (PROWLARR_BASE_URL_ENV, Some(&mock_server.uri()))],
+                ProwlarrClient::from_env);
+
+            assert!(matches!(prowlarr_client.count_indexers().await, Err(ProwlarrError::Request(_))));
+            assert!(matches!(prowlarr_client.count_indexers().await, Err(ProwlarrError::Request(_))));
+            assert!(matches!(prowlarr_client.count_indexers().await, Err(ProwlarrError::Unavailable)));
+        }
+
+        #[tokio::test]
+        async fn half_open_health_check_succeeds_after_recovery() {
+            let mock_server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/system/status"))
+                .respond_with(ResponseTemplate::new(503))
+                .up_to_n_times(1)
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/system/status"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("{\"version\":\"1.24.3\"}"))
+                .mount(&mock_server)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/api/v1/search"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("[]").set_delay(std::time::Duration::from_secs(2)))
+                .mount(&mock_server)
+                .await;
+            for endpoint in ["/api/v1/health", "/api/v1/indexer", "/api/v1/indexerstatus"] {
+                Mock::given(method("GET"))
+                    .and(path(endpoint))
+                    .respond_with(ResponseTemplate::new(200).set_body_string("[]"))
+                    .mount(&mock_server)
+                    .await;
+            }
+
+            let prowlarr_client = temp_env::with_vars(
+                [(PROWLARR_API_KEY_ENV, Some("key123")),
+                    (PROWLARR_RETRIES_ENV, Some("0")),
+                    (PROWLARR_BREAKER_FAILURES_ENV, Some("1")),
+                    (PROWLARR_BREAKER_SECONDS_ENV, Some("1")),
+                    (PROWLARR_BASE_URL_ENV, Some(&mock_server.uri()))],
+                ProwlarrClient::from_env);
+
+            assert!(matches!(prowlarr_client.health().await, Err(ProwlarrError::Request(_))));
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+            let cancelled_probe = prowlarr_client.search("Ubuntu", false);
+            assert!(tokio::time::timeout(std::time::Duration::from_millis(100), cancelled_probe).await.is_err());
+            assert_eq!(prowlarr_client.health().await.unwrap().version, "1.24.3");
+        }
+
+        #[test]
+        #[should_panic(expected = "PROWLARR_TIMEOUT must be a non-negative number, got \"soon\"")]
+        fn bad_timeout() {
+            temp_env::with_vars([(PROWLARR_API_KEY_ENV, Some("key")),
+                                    (PROWLARR_BASE_URL_ENV, Some("http://localhost:9696")),
+                                    (PROWLARR_TIMEOUT_ENV, Some("soon"))], || {
+                ProwlarrClient::from_env()
+            });
+        }
+
         mod download_url_content {
             use crate::core::prowlarr::{ProwlarrClient, PROWLARR_API_KEY_ENV, PROWLARR_BASE_URL_ENV};
             use reqwest::header::LOCATION;
@@ -328,7 +927,7 @@ mod test {
                 let result = prowlarr_client.get_download_meta(DOWNLOAD_URL).await.unwrap();
                 match result {
                     DownloadMeta::MagnetLink(link) => assert_eq!(link, magnet_link.to_string()),
-                    DownloadMeta::TorrentFile(_) => panic!("torrent file unexpected")
+                    DownloadMeta::TorrentFile(_) | DownloadMeta::NzbFile(_) => panic!("torrent file unexpected")
                 }
             }
 
@@ -350,7 +949,30 @@ mod test {
                 let result = prowlarr_client.get_download_meta(DOWNLOAD_URL).await.unwrap();
                 match result {
                     DownloadMeta::MagnetLink(_) => panic!("magnet link unexpected"),
-                    DownloadMeta::TorrentFile(file) => assert_eq!(file, "file contents")
+                    DownloadMeta::TorrentFile(file) => assert_eq!(file, "file contents"),
+                    DownloadMeta::NzbFile(_) => panic!("nzb file unexpected"),
+                }
+            }
+
+            #[tokio::test]
+            async fn nzb_file() {
+                let mock_server = MockServer::start().await;
+                Mock::given(method("GET"))
+                    .and(path("/content"))
+                    .respond_with(ResponseTemplate::new(200)
+                        .set_body_raw("nzb contents", "application/x-nzb"))
+                    .mount(&mock_server)
+                    .await;
+
+                let prowlarr_client = temp_env::with_vars(
+                    [(PROWLARR_API_KEY_ENV, Some("key123")),
+                        (PROWLARR_BASE_URL_ENV, Some(&mock_server.uri()))],
+                    ProwlarrClient::from_env);
+
+                let result = prowlarr_client.get_download_meta(DOWNLOAD_URL).await.unwrap();
+                match result {
+                    DownloadMeta::NzbFile(file) => assert_eq!(file, "nzb contents"),
+                    DownloadMeta::MagnetLink(_) | DownloadMeta::TorrentFile(_) => panic!("nzb file expected"),
                 }
             }
 
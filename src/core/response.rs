@@ -0,0 +1,31 @@
+use bytes::Bytes;
+
+use crate::core::prowlarr::SearchResult;
+use crate::core::traits::input::{Command, Locale};
+
+/// A search result along with the id it can be downloaded by.
+pub struct FoundResult {
+    pub uuid: String,
+    pub search_result: SearchResult,
+}
+
+/// One of the options offered to the user, picked by sending its command.
+pub struct Choice {
+    pub label: String,
+    /// `None` if the option is shown for information only.
+    pub command: Option<Command>,
+}
+
+/// What the bot answers with. The core decides what to answer, while every front-end decides how it's rendered.
+pub enum Response {
+    /// Search results, most seeded first.
+    Results { results: Vec<FoundResult>, locale: Locale },
+    /// Informational text, already localized.
+    Notice(String),
+    /// Text explaining why a request failed, already localized.
+    Error(String),
+    Magnet(String),
+    File { filename: String, content: Bytes },
+    /// Options to pick from, each rendered along with the command picking it in the front-end's syntax.
+    Choices { heading: String, choices: Vec<Choice> },
+}
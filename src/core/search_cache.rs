@@ -0,0 +1,74 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::core::prowlarr::SearchResult;
+use crate::core::traits::search_cache::SearchCache;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Results of recent searches, so repeating a search within `ttl` doesn't hit every indexer again.
+/// Storage errors are logged and treated as misses, as the results can always be searched for again.
+pub struct SearchResultsCache {
+    storage: Box<dyn SearchCache>,
+    ttl: Duration,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl SearchResultsCache {
+    pub fn new(storage: Box<dyn SearchCache>, ttl: Duration) -> SearchResultsCache {
+        SearchResultsCache { storage, ttl, hits: AtomicU64::new(0), misses: AtomicU64::new(0) }
+    }
+
+    /// Searches differing only in case and whitespace share a key, as indexers ignore both.
+    pub fn key(query: &str, indexer_ids: &[u32], search_type: &str) -> String {
+        let mut indexer_ids = indexer_ids.to_vec();
+        indexer_ids.sort_unstable();
+        let indexers = indexer_ids.iter().map(u32::to_string).collect::<Vec<_>>().join(",");
+        let query = query.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase();
+        format!("{search_type}:{indexers}:{query}")
+    }
+
+    pub async fn get(&self, key: &str) -> Option<Vec<SearchResult>> {
+        let results = self.storage.get(key)
+            .await
+            .unwrap_or_else(|err| {
+                log::warn!("Could not get cached results of \"{}\": {}", key, err);
+                None
+            });
+        match results {
+            Some(_) => self.hits.fetch_add(1, Ordering::Relaxed),
+            None => self.misses.fetch_add(1, Ordering::Relaxed),
+        };
+        results
+    }
+
+    pub async fn put(&self, key: &str, results: &[SearchResult]) {
+        if let Err(err) = self.storage.put(key, results, self.ttl).await {
+            log::warn!("Could not cache results of \"{}\": {}", key, err);
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats { hits: self.hits.load(Ordering::Relaxed), misses: self.misses.load(Ordering::Relaxed) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::search_cache::SearchResultsCache;
+
+    #[test]
+    fn key_is_normalized() {
+        assert_eq!(SearchResultsCache::key("  Ubuntu   22.04 ", &[3, 1], "search"),
+                   SearchResultsCache::key("ubuntu 22.04", &[1, 3], "search"));
+        assert_ne!(SearchResultsCache::key("ubuntu", &[1], "search"), SearchResultsCache::key("ubuntu", &[2], "search"));
+        assert_ne!(SearchResultsCache::key("tt0111161", &[], "movie"), SearchResultsCache::key("tt0111161", &[], "search"));
+    }
+}
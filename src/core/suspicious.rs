@@ -0,0 +1,198 @@
+use chrono::{DateTime, TimeDelta, Utc};
+
+use crate::core::prowlarr::{Category, SearchResult};
+use crate::core::torrent_files::TorrentFiles;
+
+const SUSPICIOUS_RELEASES_ENV: &str = "SUSPICIOUS_RELEASES";
+
+/// A sign of a fake release, such as malware posing as a new movie.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Suspicion {
+    /// Far too small or too large for its category, e.g. a 5 MB movie.
+    ImplausibleSize,
+    /// Published moments ago but already seeded by a crowd, which real releases take time to gather.
+    NewWithManySeeders,
+    /// Has executables, while it's not software.
+    Executables,
+    /// Has nothing but archives, which hide what's inside.
+    ArchivesOnly,
+}
+
+impl Suspicion {
+    /// Locale key of the reason shown to users.
+    pub fn locale_key(self) -> &'static str {
+        match self {
+            Suspicion::ImplausibleSize => "suspicious_size",
+            Suspicion::NewWithManySeeders => "suspicious_new",
+            Suspicion::Executables => "suspicious_executables",
+            Suspicion::ArchivesOnly => "suspicious_archives",
+        }
+    }
+}
+
+/// Localized warning listing the reasons a release looks fake, `None` if it doesn't.
+pub fn warning(suspicions: &[Suspicion], locale: &str) -> Option<String> {
+    if suspicions.is_empty() {
+        return None;
+    }
+    let reasons = suspicions.iter()
+        .map(|suspicion| t!(suspicion.locale_key(), locale = locale).to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    Some(t!("suspicious", locale = locale, reasons = reasons).to_string())
+}
+
+/// What to do with suspicious releases, set by `SUSPICIOUS_RELEASES`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SuspiciousPolicy {
+    /// Mark them with a warning, leaving the choice to users.
+    #[default]
+    Warn,
+    /// Mark them and refuse to download them.
+    Block,
+}
+
+impl SuspiciousPolicy {
+    pub fn from_env() -> SuspiciousPolicy {
+        match std::env::var(SUSPICIOUS_RELEASES_ENV).as_deref() {
+            Err(_) | Ok("warn") => SuspiciousPolicy::Warn,
+            Ok("block") => SuspiciousPolicy::Block,
+            Ok(other) => panic!("{SUSPICIOUS_RELEASES_ENV} must be either \"warn\" or \"block\", got \"{other}\""),
+        }
+    }
+}
+
+const MB: u128 = 1_000_000;
+const GB: u128 = 1_000 * MB;
+
+/// Newznab category ranges with plausible sizes of their releases.
+const SIZE_RANGES: &[(u32, u32, u128, u128)] = &[
+    (2000, 2999, 100 * MB, 200 * GB), // Movies
+    (5000, 5999, 20 * MB, 500 * GB),  // TV, including season packs
+];
+
+/// Software, where executables and archives are expected.
+const PC_CATEGORIES: (u32, u32) = (4000, 4999);
+
+const NEW_RELEASE_AGE: TimeDelta = TimeDelta::hours(1);
+const NEW_RELEASE_MAX_SEEDERS: u32 = 200;
+
+const EXECUTABLE_EXTENSIONS: &[&str] = &["exe", "scr", "bat", "cmd", "com", "msi", "lnk", "vbs", "ps1", "jar", "apk"];
+const ARCHIVE_EXTENSIONS: &[&str] = &["zip", "rar", "7z", "tar", "gz", "bz2", "xz"];
+
+/// Signs of a fake release that a search result alone reveals.
+pub fn check_result(search_result: &SearchResult, now: DateTime<Utc>) -> Vec<Suspicion> {
+    let implausible_size = search_result.categories.iter()
+        .filter_map(|category| SIZE_RANGES.iter().find(|(start, end, _, _)| (*start..=*end).contains(&category.id)))
+        .any(|(_, _, min, max)| !(*min..=*max).contains(&search_result.size));
+    let new_with_many_seeders = now - search_result.publish_date < NEW_RELEASE_AGE
+        && search_result.seeders > NEW_RELEASE_MAX_SEEDERS;
+    [(implausible_size, Suspicion::ImplausibleSize), (new_with_many_seeders, Suspicion::NewWithManySeeders)]
+        .into_iter()
+        .filter_map(|(found, suspicion)| found.then_some(suspicion))
+        .collect()
+}
+
+/// Signs of a fake release in the files of its torrent. Software of `categories` is expected to have executables.
+pub fn check_files(files: &TorrentFiles, categories: &[Category]) -> Vec<Suspicion> {
+    if categories.iter().any(|category| (PC_CATEGORIES.0..=PC_CATEGORIES.1).contains(&category.id)) {
+        return vec![];
+    }
+    let extensions: Vec<String> = files.paths()
+        .map(|path| path.extension().unwrap_or_default().to_string_lossy().to_lowercase())
+        .collect();
+    let executables = extensions.iter().any(|extension| EXECUTABLE_EXTENSIONS.contains(&extension.as_str()));
+    let archives_only = !extensions.is_empty() && extensions.iter().all(|extension| is_archive(extension));
+    [(executables, Suspicion::Executables), (archives_only, Suspicion::ArchivesOnly)]
+        .into_iter()
+        .filter_map(|(found, suspicion)| found.then_some(suspicion))
+        .collect()
+}
+
+/// Multipart RAR archives also come as `.r00`, `.r01` and so on.
+fn is_archive(extension: &str) -> bool {
+    ARCHIVE_EXTENSIONS.contains(&extension)
+        || extension.strip_prefix('r').is_some_and(|part| part.len() == 2 && part.chars().all(|c| c.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, TimeDelta};
+
+    use crate::core::prowlarr::{Category, Protocol, SearchResult};
+    use crate::core::suspicious::{check_files, check_result, Suspicion, SuspiciousPolicy};
+    use crate::core::torrent_files::TorrentFiles;
+
+    fn search_result(category: u32, size: u128, seeders: u32) -> SearchResult {
+        SearchResult {
+            guid: "guid".to_string(),
+            indexer_id: 1,
+            indexer: None,
+            title: "The Movie 2023 2160p".to_string(),
+            size,
+            publish_date: DateTime::from_timestamp(1431648000, 0).unwrap(),
+            download_url: None,
+            magnet_url: None,
+            info_url: "http://localhost/movie".to_string(),
+            seeders,
+            leechers: 0,
+            grabs: None,
+            categories: vec![Category { id: category, name: None }],
+            indexer_flags: vec![],
+            imdb_id: 0,
+            info_hash: None,
+            protocol: Protocol::Torrent,
+            other_indexers: vec![],
+        }
+    }
+
+    /// A multi-file torrent with the given file names, each 1000 bytes long.
+    fn torrent_files(names: &[&str]) -> TorrentFiles {
+        let files: String = names.iter()
+            .map(|name| format!("d6:lengthi1000e4:pathl{}:{}ee", name.len(), name))
+            .collect();
+        TorrentFiles::parse(format!("d4:infod5:filesl{files}e4:name5:Movie12:piece lengthi262144e6:pieces0:ee").as_bytes())
+            .unwrap()
+    }
+
+    #[test]
+    fn implausible_size() {
+        let later = DateTime::from_timestamp(1431648000, 0).unwrap() + TimeDelta::days(30);
+
+        assert_eq!(check_result(&search_result(2040, 5_000_000, 10), later), vec![Suspicion::ImplausibleSize]);
+        assert_eq!(check_result(&search_result(2040, 4_000_000_000, 10), later), vec![]);
+        assert_eq!(check_result(&search_result(3000, 5_000_000, 10), later), vec![]);
+    }
+
+    #[test]
+    fn new_with_many_seeders() {
+        let published = DateTime::from_timestamp(1431648000, 0).unwrap();
+
+        assert_eq!(check_result(&search_result(2040, 4_000_000_000, 5000), published + TimeDelta::minutes(5)),
+                   vec![Suspicion::NewWithManySeeders]);
+        assert_eq!(check_result(&search_result(2040, 4_000_000_000, 5000), published + TimeDelta::days(1)), vec![]);
+        assert_eq!(check_result(&search_result(2040, 4_000_000_000, 20), published + TimeDelta::minutes(5)), vec![]);
+    }
+
+    #[test]
+    fn suspicious_files() {
+        let movie = [Category { id: 2040, name: None }];
+
+        assert_eq!(check_files(&torrent_files(&["movie.mkv", "codec.EXE"]), &movie), vec![Suspicion::Executables]);
+        assert_eq!(check_files(&torrent_files(&["movie.rar", "movie.r00", "movie.r01"]), &movie), vec![Suspicion::ArchivesOnly]);
+        assert_eq!(check_files(&torrent_files(&["movie.mkv", "subs.zip"]), &movie), vec![]);
+        assert_eq!(check_files(&torrent_files(&["setup.exe", "data.rar"]), &[Category { id: 4050, name: None }]), vec![]);
+    }
+
+    #[test]
+    fn policy() {
+        temp_env::with_var_unset("SUSPICIOUS_RELEASES", || assert_eq!(SuspiciousPolicy::from_env(), SuspiciousPolicy::Warn));
+        temp_env::with_var("SUSPICIOUS_RELEASES", Some("block"), || assert_eq!(SuspiciousPolicy::from_env(), SuspiciousPolicy::Block));
+    }
+
+    #[test]
+    #[should_panic(expected = "SUSPICIOUS_RELEASES must be either \"warn\" or \"block\", got \"ignore\"")]
+    fn unknown_policy() {
+        temp_env::with_var("SUSPICIOUS_RELEASES", Some("ignore"), SuspiciousPolicy::from_env);
+    }
+}
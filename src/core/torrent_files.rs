@@ -0,0 +1,114 @@
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+use hightorrent::TorrentFile;
+
+use crate::core::util::human_readable_size;
+
+const INDENT: &str = "  ";
+
+/// Files of a .torrent, to check what a release contains before downloading it.
+pub struct TorrentFiles {
+    pub name: String,
+    /// Paths relative to the torrent's root directory along with sizes, in the order of the torrent.
+    files: Vec<(PathBuf, u64)>,
+}
+
+impl TorrentFiles {
+    pub fn parse(torrent: &[u8]) -> Result<TorrentFiles, String> {
+        let torrent = TorrentFile::from_slice(torrent).map_err(|err| err.to_string())?;
+        // hightorrent panics on torrents of the v2-only format, whose file tree it doesn't support yet
+        let files = catch_unwind(AssertUnwindSafe(|| torrent.decoded.files()))
+            .map_err(|_| format!("Could not list files of torrent {}", torrent.hash()))?
+            .map_err(|err| err.to_string())?;
+        Ok(TorrentFiles {
+            name: torrent.name.clone(),
+            files: files.into_iter().map(|file| (file.path, file.size)).collect(),
+        })
+    }
+
+    pub fn count(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(|(path, _)| path.as_path())
+    }
+
+    pub fn total_size(&self) -> u128 {
+        self.files.iter().map(|(_, size)| u128::from(*size)).sum()
+    }
+
+    /// Directories and files with sizes, one per line and indented by depth.
+    /// Only the first `max_files` files are listed, followed by the number of the rest.
+    pub fn tree(&self, max_files: usize) -> String {
+        let mut lines: Vec<String> = Vec::new();
+        let mut directory: Vec<String> = Vec::new();
+        for (path, size) in self.files.iter().take(max_files) {
+            let mut components: Vec<String> = path.components()
+                .map(|component| component.as_os_str().to_string_lossy().to_string())
+                .collect();
+            let name = components.pop().unwrap_or_default();
+            let common = directory.iter().zip(&components).take_while(|(a, b)| a == b).count();
+            for (depth, dir) in components.iter().enumerate().skip(common) {
+                lines.push(format!("{}{}/", INDENT.repeat(depth), dir));
+            }
+            lines.push(format!("{}{} ({})", INDENT.repeat(components.len()), name, human_readable_size(u128::from(*size))));
+            directory = components;
+        }
+        if self.files.len() > max_files {
+            lines.push(format!("… +{}", self.files.len() - max_files));
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::torrent_files::TorrentFiles;
+
+    fn multi_file_torrent() -> Vec<u8> {
+        let files = [("Sample", "sample.mkv", 50_000_000), ("Subs", "en.srt", 40_000), ("Subs", "ru.srt", 42_000)];
+        let mut torrent = b"d4:infod5:filesl".to_vec();
+        for (directory, name, length) in files {
+            torrent.extend(format!("d6:lengthi{}e4:pathl{}:{}{}:{}ee", length, directory.len(), directory, name.len(), name).bytes());
+        }
+        torrent.extend(b"d6:lengthi4400000000e4:pathl9:movie.mkveee");
+        torrent.extend(b"4:name5:Movie12:piece lengthi262144e6:pieces0:ee");
+        torrent
+    }
+
+    #[test]
+    fn tree_of_directories() {
+        let files = TorrentFiles::parse(&multi_file_torrent()).unwrap();
+
+        assert_eq!(files.name, "Movie");
+        assert_eq!(files.count(), 4);
+        assert_eq!(files.total_size(), 4_450_082_000);
+        assert_eq!(files.tree(10), "Sample/\n  \
+            sample.mkv (50 MB)\n\
+            Subs/\n  \
+            en.srt (40 KB)\n  \
+            ru.srt (42 KB)\n\
+            movie.mkv (4.4 GB)");
+    }
+
+    #[test]
+    fn long_tree_is_cut() {
+        let files = TorrentFiles::parse(&multi_file_torrent()).unwrap();
+
+        assert_eq!(files.tree(2), "Sample/\n  sample.mkv (50 MB)\nSubs/\n  en.srt (40 KB)\n… +2");
+    }
+
+    #[test]
+    fn single_file() {
+        let files = TorrentFiles::parse(b"d4:infod6:lengthi1234e4:name9:movie.exe12:piece lengthi262144e6:pieces0:ee").unwrap();
+
+        assert_eq!(files.tree(10), "movie.exe (1.23 KB)");
+    }
+
+    #[test]
+    fn not_a_torrent() {
+        assert!(TorrentFiles::parse(b"<html></html>").is_err());
+    }
+}
@@ -1,8 +1,8 @@
 use derive_more::Display;
-use hightorrent::{MagnetLink, TorrentFile};
+use hightorrent::MagnetLink;
 use serde::{Deserialize, Serialize};
 
-use crate::core::prowlarr::SearchResult;
+use crate::core::prowlarr::{Protocol, SearchResult};
 use crate::core::download_meta::{DownloadMeta, DownloadMetaProvider};
 
 #[derive(Clone, Display, Serialize, Deserialize)]
@@ -12,6 +12,10 @@ pub struct TorrentMeta {
     pub indexer_id: u8,
     pub download_url: Option<String>,
     pub magnet_url: Option<String>,
+    #[serde(default)]
+    pub protocol: Protocol,
+    #[serde(default)]
+    pub search_result: Option<SearchResult>,
 }
 
 impl From<&SearchResult> for TorrentMeta {
@@ -21,12 +25,17 @@ impl From<&SearchResult> for TorrentMeta {
             download_url: value.download_url.clone(),
             guid: value.guid.clone(),
             magnet_url: value.magnet_url.clone(),
+            protocol: value.protocol,
+            search_result: Some(value.clone()),
         }
     }
 }
 
 impl TorrentMeta {
     pub async fn get_torrent_hash(&self, download_meta_provider: &impl DownloadMetaProvider) -> Result<String, String> {
+        if self.protocol == Protocol::Usenet {
+            return Err(format!("Usenet release {} has no torrent hash", self));
+        }
         if let Some(magnet_hash) = self.magnet_url.as_ref()
             .and_then(|url| MagnetLink::new(url).ok())
             .map(|magnet| magnet.hash().to_string()) {
@@ -38,18 +47,8 @@ impl TorrentMeta {
             return Err(format!("Neither magnet nor download link exist for torrent {}", self));
         }
         match download_meta_provider.get_download_meta(url.unwrap()).await {
-            Ok(content) => match content {
-                DownloadMeta::MagnetLink(link) =>
-                    Ok(MagnetLink::new(&link)
-                        .map_err(|err| err.to_string())? // todo add info about where an error occurred
-                        .hash()
-                        .to_string()),
-                DownloadMeta::TorrentFile(torrent_file) =>
-                    Ok(TorrentFile::from_slice(torrent_file.as_ref())
-                        .map_err(|err| err.to_string())?
-                        .hash()
-                        .to_string()),
-            }
+            Ok(DownloadMeta::NzbFile(_)) => Err(format!("Got an .nzb file instead of a torrent for {}", self)),
+            Ok(content) => content.torrent_hash(), // todo add info about where an error occurred
             Err(err) => Err(format!("Error when interacting with Prowlarr: {}", err)),
         }
     }
@@ -60,7 +59,7 @@ mod tests {
     use bytes::Bytes;
     use mockall::{predicate::*};
 
-    use crate::core::prowlarr::SearchResult;
+    use crate::core::prowlarr::{Protocol, SearchResult};
     use crate::core::download_meta::{DownloadMeta, MockDownloadMetaProvider};
     use crate::core::torrent_meta::TorrentMeta;
 
@@ -72,6 +71,8 @@ mod tests {
             indexer_id: 0,
             download_url: None,
             magnet_url: Some("magnet:?xt=urn:btih:c811b41641a09d192b8ed81b14064fff55d85ce3".to_string()),
+            protocol: Protocol::Torrent,
+            search_result: None,
         };
 
         let hash = torrent_meta.get_torrent_hash(&meta_provider)
@@ -96,6 +97,8 @@ mod tests {
             indexer_id: 0,
             download_url: Some("download_url".to_string()),
             magnet_url: None,
+            protocol: Protocol::Torrent,
+            search_result: None,
         };
 
         let hash = torrent_meta.get_torrent_hash(&meta_provider)
@@ -120,6 +123,8 @@ mod tests {
             indexer_id: 0,
             download_url: Some("download_url".to_string()),
             magnet_url: None,
+            protocol: Protocol::Torrent,
+            search_result: None,
         };
 
         let hash = torrent_meta.get_torrent_hash(&meta_provider)
@@ -129,11 +134,29 @@ mod tests {
         assert_eq!(hash, "d55be2cd263efa84aeb9495333a4fabc428a4250");
     }
 
+    #[tokio::test]
+    async fn no_hash_for_usenet() {
+        let meta_provider = MockDownloadMetaProvider::new();
+        let torrent_meta = TorrentMeta {
+            guid: "".to_string(),
+            indexer_id: 0,
+            download_url: Some("download_url".to_string()),
+            magnet_url: None,
+            protocol: Protocol::Usenet,
+            search_result: None,
+        };
+
+        let result = torrent_meta.get_torrent_hash(&meta_provider).await;
+
+        assert!(result.unwrap_err().starts_with("Usenet release"));
+    }
+
     #[test]
     fn search_result_to_torrent_meta() {
         let search_result = SearchResult {
             guid: "ubuntu_22_04".to_string(),
             indexer_id: 2,
+            indexer: None,
             title: "".to_string(),
             size: 0,
             publish_date: Default::default(),
@@ -143,6 +166,12 @@ mod tests {
             seeders: 0,
             leechers: 0,
             grabs: None,
+            categories: vec![],
+            indexer_flags: vec![],
+            imdb_id: 0,
+            info_hash: None,
+            protocol: Protocol::Usenet,
+            other_indexers: vec![],
         };
 
         let result: TorrentMeta = (&search_result).into();
@@ -151,5 +180,7 @@ mod tests {
         assert_eq!(result.indexer_id, 2);
         assert_eq!(result.magnet_url, Some("magnet".to_string()));
         assert_eq!(result.download_url, Some("download".to_string()));
+        assert_eq!(result.protocol, Protocol::Usenet);
+        assert_eq!(result.search_result.map(|r| r.guid), Some("ubuntu_22_04".to_string()));
     }
 }
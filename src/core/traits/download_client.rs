@@ -0,0 +1,12 @@
+use async_trait::async_trait;
+#[cfg(test)]
+use mockall::automock;
+
+use crate::core::download_meta::DownloadMeta;
+
+/// A torrent client that torrents sent by users are added to directly, as Prowlarr only downloads its own results.
+#[async_trait]
+#[cfg_attr(test, automock)]
+pub trait DownloadClient: Send + Sync {
+    async fn add(&self, torrent: &DownloadMeta) -> Result<(), String>;
+}
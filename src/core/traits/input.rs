@@ -1,14 +1,37 @@
+use crate::core::download_meta::DownloadMeta;
+
 pub type SearchQuery = Box<str>;
 pub type Source = u64;
 pub type Destination = i64;
 pub type ReplyToMessage = i32;
 pub type ItemUuid = Box<str>;
 pub type Locale = Box<str>;
+pub type WatchNumber = Box<str>;
 
+#[derive(Clone, PartialEq, Debug)]
 pub enum Command {
     Search(SearchQuery),
+    /// Searches without reusing cached results.
+    Refresh(SearchQuery),
     GetLink(ItemUuid),
     Download(ItemUuid),
+    ShowResult(ItemUuid),
+    /// Lists the files of a found torrent, to check what it contains before downloading it.
+    Files(ItemUuid),
+    Watch(SearchQuery),
+    Watches,
+    Unwatch(WatchNumber),
+    Series(SearchQuery),
+    Movie(SearchQuery),
+    /// Adds a looked up series or movie, `uuid` to pick its quality profile or `uuid_profileId` to add it.
+    Add(ItemUuid),
+    /// A magnet link or a .torrent file sent by the user, to add to the download client directly.
+    Submit(DownloadMeta),
+    /// A file sent by the user that couldn't be received from the chat service.
+    UploadFailed,
+    /// Shows whether Prowlarr and its indexers are working.
+    Health,
+    Start,
     Help
 }
 
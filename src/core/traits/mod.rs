@@ -2,3 +2,7 @@ pub mod sender;
 pub mod input;
 pub mod uuid_mapper;
 pub mod search_result_serializer;
+pub mod notifier;
+pub mod download_client;
+pub mod search_cache;
+pub mod watch_storage;
@@ -0,0 +1,19 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+#[cfg(test)]
+use mockall::automock;
+
+use crate::core::HandlingResult;
+use crate::core::traits::input::Source;
+
+/// Notification channels configured for every user, in addition to the chat the bot is used from.
+pub type NotificationChannels = HashMap<Source, Vec<Arc<dyn Notifier>>>;
+
+/// Delivers notifications outside the chat, e.g. by email or push.
+#[async_trait]
+#[cfg_attr(test, automock)]
+pub trait Notifier: Send + Sync {
+    async fn notify(&self, title: &str, message: &str) -> HandlingResult;
+}
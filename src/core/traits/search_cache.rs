@@ -0,0 +1,12 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::core::prowlarr::SearchResult;
+
+/// Storage of search results, which are dropped once their time to live passes.
+#[async_trait]
+pub trait SearchCache: Send + Sync {
+    async fn get(&self, key: &str) -> Result<Option<Vec<SearchResult>>, String>;
+    async fn put(&self, key: &str, results: &[SearchResult], ttl: Duration) -> Result<(), String>;
+}
@@ -1,5 +1,12 @@
 use crate::core::prowlarr::SearchResult;
+use crate::core::response::FoundResult;
 
 pub trait SearchResultSerializer: Send + Sync {
     fn serialize(&self, search_result: &SearchResult, bot_uuid: &str, locale: &str) -> String;
+
+    fn serialize_all(&self, results: &[FoundResult], locale: &str) -> String {
+        results.iter()
+            .map(|found| self.serialize(&found.search_result, &found.uuid, locale))
+            .collect()
+    }
 }
@@ -1,15 +1,19 @@
 use async_trait::async_trait;
-use bytes::Bytes;
+#[cfg(test)]
+use mockall::automock;
 
-use crate::core::HandlingResult;
+use crate::core::{HandlingError, HandlingResult};
+use crate::core::response::Response;
 use crate::core::traits::input::{Destination, ReplyToMessage};
 
+pub type MessageHandle = Box<str>;
+
 #[async_trait]
+#[cfg_attr(test, automock)]
 pub trait Sender: Send + Sync {
-    async fn send_reply(&self, destination: Destination, reply_to_message: ReplyToMessage, message: &str) -> HandlingResult;
     async fn send_progress_indication(&self, destination: Destination) -> HandlingResult;
-    async fn send_plain_message(&self, destination: Destination, message: &str) -> HandlingResult;
-    async fn send_plain_reply(&self, destination: Destination, reply_to_message: ReplyToMessage, message: &str) -> HandlingResult;
-    async fn send_magnet(&self, destination: Destination, link: &str) -> HandlingResult;
-    async fn send_torrent_file(&self, destination: Destination, filename: &str, file: Bytes) -> HandlingResult;
+    async fn send(&self, destination: Destination, response: &Response) -> HandlingResult;
+    async fn send_editable_reply(&self, destination: Destination, reply_to_message: ReplyToMessage, response: &Response) -> Result<MessageHandle, HandlingError>;
+    /// Replaces the message with a new response, which can't be a file.
+    async fn edit(&self, destination: Destination, message_handle: &MessageHandle, response: &Response) -> HandlingResult;
 }
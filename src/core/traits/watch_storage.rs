@@ -0,0 +1,11 @@
+use async_trait::async_trait;
+
+use crate::core::watches::{Watch, WatchId};
+
+/// Storage of saved searches, so that they survive restarts.
+#[async_trait]
+pub trait WatchStorage: Send + Sync {
+    async fn load(&self) -> Result<Vec<(WatchId, Watch)>, String>;
+    async fn save(&self, id: WatchId, watch: &Watch) -> Result<(), String>;
+    async fn remove(&self, id: WatchId) -> Result<(), String>;
+}
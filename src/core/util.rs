@@ -1,5 +1,8 @@
 use std::net::{IpAddr, Ipv4Addr};
 
+use byte_unit::Byte;
+use byte_unit::UnitType::Decimal;
+
 pub fn parse_ip(env_var: &str) -> IpAddr {
     IpAddr::V4(std::env::var(env_var)
         .unwrap_or_else(|_| "0.0.0.0".to_string())
@@ -7,6 +10,13 @@ pub fn parse_ip(env_var: &str) -> IpAddr {
         .unwrap_or_else(|_| panic!("Cannot parse the {env_var} env variable")))
 }
 
+pub fn human_readable_size(size: u128) -> String {
+    Byte::from_u128(size)
+        .map(|b| b.get_appropriate_unit(Decimal))
+        .map(|b| format!("{b:#.2}"))
+        .unwrap_or_else(|| "???".to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use crate::core::util::parse_ip;
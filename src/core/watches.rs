@@ -0,0 +1,326 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use dashmap::DashMap;
+use serde::{Deserialize, Serialize};
+use tokio::time::{interval_at, Instant};
+
+use crate::core::download_rules::DownloadRules;
+use crate::core::downloads_tracker::User;
+use crate::core::input_handler::InputHandler;
+use crate::core::prowlarr::SearchResult;
+use crate::core::traits::input::{Destination, SearchQuery, Source};
+use crate::core::traits::watch_storage::WatchStorage;
+
+const WATCH_INTERVAL_MINUTES_ENV: &str = "WATCH_INTERVAL_MINUTES";
+const DEFAULT_WATCH_INTERVAL_MINUTES: u64 = 60;
+/// How many guids of seen results are remembered per saved search, the oldest ones are forgotten first.
+/// Several pages of results, so that a release dropping out of the results for a while isn't sent again.
+const MAX_SEEN: usize = 1000;
+
+pub type WatchId = u64;
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Watch {
+    query: SearchQuery,
+    rules: Option<DownloadRules>,
+    user: User,
+    /// Guids of seen results, oldest first.
+    seen: VecDeque<String>,
+}
+
+impl Watch {
+    /// Adds guids of results that weren't seen before, returning whether `guid` is one of them.
+    fn see(&mut self, guid: &str) -> bool {
+        if self.seen.iter().any(|seen| seen == guid) {
+            return false;
+        }
+        if self.seen.len() == MAX_SEEN {
+            self.seen.pop_front();
+        }
+        self.seen.push_back(guid.to_string());
+        true
+    }
+}
+
+/// Saved searches, which are re-run periodically to notify users about releases they haven't seen yet.
+/// They're kept in memory and, if there's a storage, also saved to it to survive restarts.
+pub struct Watches {
+    watches: DashMap<WatchId, Watch>,
+    next_id: AtomicU64,
+    storage: Option<Box<dyn WatchStorage>>,
+}
+
+impl Watches {
+
+    pub fn new() -> Watches {
+        Watches {
+            watches: DashMap::new(),
+            next_id: AtomicU64::new(1),
+            storage: None,
+        }
+    }
+
+    /// Saved searches loaded from `storage`, where changes are saved from now on.
+    /// Starts with none if they couldn't be loaded.
+    pub async fn restore(storage: Box<dyn WatchStorage>) -> Watches {
+        let watches = Watches::new();
+        match storage.load().await {
+            Ok(loaded) => {
+                log::info!("Restored {} saved searches", loaded.len());
+                for (id, watch) in loaded {
+                    watches.next_id.fetch_max(id + 1, Ordering::Relaxed);
+                    watches.watches.insert(id, watch);
+                }
+            }
+            Err(err) => log::error!("Could not restore saved searches: {}", err),
+        }
+        Watches { storage: Some(storage), ..watches }
+    }
+
+    /// Saves a search, `seen` are guids of the results the user already knows about.
+    /// New results matching `rules`, if there are any, are downloaded automatically.
+    pub async fn add(&self, query: SearchQuery, rules: Option<DownloadRules>, user: User, seen: impl IntoIterator<Item = String>) -> WatchId {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let mut watch = Watch { query, rules, user, seen: VecDeque::new() };
+        seen.into_iter().for_each(|guid| { watch.see(&guid); });
+        self.watches.insert(id, watch.clone());
+        self.persist(id, &watch).await;
+        id
+    }
+
+    /// Searches saved by a user in a chat, with their download rules if any, in the order they were saved.
+    pub fn list(&self, source: Source, destination: Destination) -> Vec<(WatchId, String)> {
+        let mut watches: Vec<(WatchId, String)> = self.watches.iter()
+            .filter(|watch| watch.user.source == source && watch.user.destination == destination)
+            .map(|watch| (*watch.key(), match &watch.rules {
+                Some(rules) => format!("{} | {}", watch.query, rules),
+                None => watch.query.to_string(),
+            }))
+            .collect();
+        watches.sort_unstable_by_key(|(id, _)| *id);
+        watches
+    }
+
+    /// Removes a search if it was saved by the user, returning its query.
+    pub async fn remove(&self, id: WatchId, source: Source) -> Option<SearchQuery> {
+        let (_, watch) = self.watches.remove_if(&id, |_, watch| watch.user.source == source)?;
+        if let Some(storage) = &self.storage {
+            if let Err(err) = storage.remove(id).await {
+                log::error!("Could not remove saved search {} from the storage: {}", id, err);
+            }
+        }
+        Some(watch.query)
+    }
+
+    pub fn queries(&self) -> Vec<(WatchId, SearchQuery)> {
+        self.watches.iter()
+            .map(|watch| (*watch.key(), watch.query.clone()))
+            .collect()
+    }
+
+    /// Marks results of a search as seen, returning the user who saved it, its download rules
+    /// and the results that weren't seen before. Returns `None` if the search was removed in the meantime.
+    pub async fn take_unseen(&self, id: WatchId, results: Vec<SearchResult>) -> Option<(User, Option<DownloadRules>, Vec<SearchResult>)> {
+        let (watch, unseen) = {
+            let mut watch = self.watches.get_mut(&id)?;
+            let unseen: Vec<SearchResult> = results.into_iter()
+                .filter(|result| watch.see(&result.guid))
+                .collect();
+            (watch.clone(), unseen)
+        };
+        if !unseen.is_empty() {
+            self.persist(id, &watch).await;
+        }
+        Some((watch.user, watch.rules, unseen))
+    }
+
+    async fn persist(&self, id: WatchId, watch: &Watch) {
+        if let Some(storage) = &self.storage {
+            if let Err(err) = storage.save(id, watch).await {
+                log::error!("Could not save saved search {} to the storage: {}", id, err);
+            }
+        }
+    }
+}
+
+/// Re-runs saved searches every `WATCH_INTERVAL_MINUTES`.
+pub async fn run(input_handler: Arc<InputHandler>) {
+    let period = Duration::from_secs(60 * get_interval_minutes());
+    let mut ticks = interval_at(Instant::now() + period, period);
+    loop {
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            _ = ticks.tick() => input_handler.check_watches().await,
+        }
+    }
+}
+
+fn get_interval_minutes() -> u64 {
+    match std::env::var(WATCH_INTERVAL_MINUTES_ENV) {
+        Ok(val) => val.parse()
+            .ok()
+            .filter(|minutes| *minutes > 0)
+            .unwrap_or_else(|| panic!("{WATCH_INTERVAL_MINUTES_ENV} must be a positive number, got \"{val}\"")),
+        Err(_) => DEFAULT_WATCH_INTERVAL_MINUTES,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use chrono::DateTime;
+    use url::Url;
+
+    use crate::core::downloads_tracker::User;
+    use crate::core::prowlarr::{Protocol, SearchResult};
+    use crate::core::traits::watch_storage::WatchStorage;
+    use crate::core::watches::{get_interval_minutes, Watch, WatchId, Watches, MAX_SEEN};
+    use crate::ext::matrix::MatrixClient;
+
+    fn user(source: u64) -> User {
+        User { source, destination: 10, locale: "en".into() }
+    }
+
+    fn search_result(guid: &str) -> SearchResult {
+        SearchResult {
+            guid: guid.to_string(),
+            indexer_id: 1,
+            indexer: None,
+            title: "Ubuntu".to_string(),
+            size: 1234567,
+            publish_date: DateTime::from_timestamp(1431648000, 0).unwrap(),
+            download_url: None,
+            magnet_url: None,
+            info_url: "http://localhost/ubuntu".to_string(),
+            seeders: 1,
+            leechers: 0,
+            grabs: None,
+            categories: vec![],
+            indexer_flags: vec![],
+            imdb_id: 0,
+            info_hash: None,
+            protocol: Protocol::Torrent,
+            other_indexers: vec![],
+        }
+    }
+
+    /// Keeps saved searches as JSON, like a real storage would.
+    #[derive(Default)]
+    struct TestStorage {
+        saved: Mutex<BTreeMap<WatchId, String>>,
+    }
+
+    #[async_trait]
+    impl WatchStorage for &TestStorage {
+        async fn load(&self) -> Result<Vec<(WatchId, Watch)>, String> {
+            Ok(self.saved.lock().unwrap().iter()
+                .map(|(id, watch)| (*id, serde_json::from_str(watch).unwrap()))
+                .collect())
+        }
+
+        async fn save(&self, id: WatchId, watch: &Watch) -> Result<(), String> {
+            self.saved.lock().unwrap().insert(id, serde_json::to_string(watch).unwrap());
+            Ok(())
+        }
+
+        async fn remove(&self, id: WatchId) -> Result<(), String> {
+            self.saved.lock().unwrap().remove(&id);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn only_unseen_results_are_returned_once() {
+        let watches = Watches::new();
+        let id = watches.add("ubuntu".into(), None, user(1), vec!["1".to_string()]).await;
+
+        let (user, _, unseen) = watches.take_unseen(id, vec![search_result("1"), search_result("2")]).await.unwrap();
+        assert_eq!(user.source, 1);
+        assert_eq!(unseen.iter().map(|result| result.guid.as_str()).collect::<Vec<_>>(), vec!["2"]);
+
+        let (_, _, unseen) = watches.take_unseen(id, vec![search_result("1"), search_result("2")]).await.unwrap();
+        assert!(unseen.is_empty());
+    }
+
+    #[tokio::test]
+    async fn oldest_seen_results_are_forgotten() {
+        let watches = Watches::new();
+        let id = watches.add("ubuntu".into(), None, user(1), (0..MAX_SEEN).map(|guid| guid.to_string())).await;
+
+        let (_, _, unseen) = watches.take_unseen(id, vec![search_result("new")]).await.unwrap();
+        assert_eq!(unseen.len(), 1);
+        assert_eq!(watches.watches.get(&id).unwrap().seen.len(), MAX_SEEN);
+        let (_, _, unseen) = watches.take_unseen(id, vec![search_result("1"), search_result("0")]).await.unwrap();
+        assert_eq!(unseen.iter().map(|result| result.guid.as_str()).collect::<Vec<_>>(), vec!["0"]);
+    }
+
+    #[tokio::test]
+    async fn list_and_remove() {
+        let watches = Watches::new();
+        let first = watches.add("ubuntu".into(), None, user(1), vec![]).await;
+        let second = watches.add("debian".into(), Some("seeders>=5".parse().unwrap()), user(1), vec![]).await;
+        let others = watches.add("arch".into(), None, user(2), vec![]).await;
+
+        assert_eq!(watches.list(1, 10), vec![(first, "ubuntu".to_string()), (second, "debian | seeders>=5".to_string())]);
+        assert_eq!(watches.remove(others, 1).await, None);
+        assert_eq!(watches.remove(first, 1).await, Some("ubuntu".into()));
+        assert_eq!(watches.list(1, 10), vec![(second, "debian | seeders>=5".to_string())]);
+        assert!(watches.take_unseen(first, vec![]).await.is_none());
+        assert_eq!(watches.queries().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn restored_after_restart() {
+        let storage: &'static TestStorage = Box::leak(Box::default());
+        let watches = Watches::restore(Box::new(storage)).await;
+        let first = watches.add("ubuntu".into(), None, user(1), vec!["1".to_string()]).await;
+        let second = watches.add("debian".into(), Some("seeders>=5".parse().unwrap()), user(1), vec![]).await;
+        watches.take_unseen(first, vec![search_result("2")]).await;
+        watches.remove(second, 1).await;
+
+        let restarted = Watches::restore(Box::new(storage)).await;
+
+        assert_eq!(restarted.list(1, 10), vec![(first, "ubuntu".to_string())]);
+        let (_, _, unseen) = restarted.take_unseen(first, vec![search_result("1"), search_result("2"), search_result("3")]).await.unwrap();
+        assert_eq!(unseen.iter().map(|result| result.guid.as_str()).collect::<Vec<_>>(), vec!["3"]);
+        assert!(restarted.add("arch".into(), None, user(1), vec![]).await > first);
+    }
+
+    #[tokio::test]
+    async fn matrix_watch_restored_through_fresh_client() {
+        let storage: &'static TestStorage = Box::leak(Box::default());
+        let client = MatrixClient::new(Url::parse("http://localhost").unwrap(), "token".to_string());
+        let alice = User { source: client.users.id("@alice:localhost") as u64, destination: client.rooms.id("!alice:localhost"), locale: "en".into() };
+        let watches = Watches::restore(Box::new(storage)).await;
+        let id = watches.add("ubuntu".into(), None, alice, vec![]).await;
+
+        let restarted_client = MatrixClient::new(Url::parse("http://localhost").unwrap(), "token".to_string());
+        let bob = (restarted_client.users.id("@bob:localhost") as u64, restarted_client.rooms.id("!bob:localhost"));
+        let alice = (restarted_client.users.id("@alice:localhost") as u64, restarted_client.rooms.id("!alice:localhost"));
+        let restarted = Watches::restore(Box::new(storage)).await;
+
+        assert!(restarted.list(bob.0, bob.1).is_empty());
+        assert_eq!(restarted.remove(id, bob.0).await, None);
+        assert_eq!(restarted.list(alice.0, alice.1), vec![(id, "ubuntu".to_string())]);
+        let (user, _, _) = restarted.take_unseen(id, vec![]).await.unwrap();
+        assert_eq!(restarted_client.rooms.name(user.destination), Some("!alice:localhost".to_string()));
+    }
+
+    #[test]
+    fn interval() {
+        temp_env::with_var_unset("WATCH_INTERVAL_MINUTES", || assert_eq!(get_interval_minutes(), 60));
+        temp_env::with_var("WATCH_INTERVAL_MINUTES", Some("15"), || assert_eq!(get_interval_minutes(), 15));
+    }
+
+    #[test]
+    #[should_panic(expected = "WATCH_INTERVAL_MINUTES must be a positive number, got \"0\"")]
+    fn bad_interval() {
+        temp_env::with_var("WATCH_INTERVAL_MINUTES", Some("0"), get_interval_minutes);
+    }
+}
@@ -0,0 +1,330 @@
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use warp::http::StatusCode;
+use warp::reply::{Reply, Response};
+use warp::{Filter, Rejection};
+
+use crate::core::download_meta::DownloadMeta;
+use crate::core::input_handler::InputHandler;
+use crate::core::prowlarr::Protocol;
+use crate::core::response::FoundResult;
+use crate::core::util;
+use crate::core::OperationError;
+
+const API_PORT_ENV: &str = "API_PORT";
+const API_TOKEN_ENV: &str = "API_TOKEN";
+
+#[derive(Debug)]
+struct Unauthorized;
+
+impl warp::reject::Reject for Unauthorized {}
+
+#[derive(Deserialize)]
+struct SearchParams {
+    query: String,
+    #[serde(default)]
+    refresh: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiSearchResult<'a> {
+    id: &'a str,
+    title: &'a str,
+    indexer_id: u8,
+    size: u128,
+    publish_date: DateTime<Utc>,
+    info_url: &'a str,
+    seeders: u32,
+    leechers: u32,
+    grabs: Option<u32>,
+    indexer: Option<&'a str>,
+    other_indexers: &'a [String],
+    categories: Vec<&'a str>,
+    indexer_flags: &'a [String],
+    imdb_id: u32,
+    info_hash: Option<&'a str>,
+    protocol: Protocol,
+}
+
+impl<'a> From<&'a FoundResult> for ApiSearchResult<'a> {
+    fn from(found: &'a FoundResult) -> Self {
+        ApiSearchResult {
+            id: &found.uuid,
+            title: &found.search_result.title,
+            indexer_id: found.search_result.indexer_id,
+            size: found.search_result.size,
+            publish_date: found.search_result.publish_date,
+            info_url: &found.search_result.info_url,
+            seeders: found.search_result.seeders,
+            leechers: found.search_result.leechers,
+            grabs: found.search_result.grabs,
+            indexer: found.search_result.indexer.as_deref(),
+            other_indexers: &found.search_result.other_indexers,
+            categories: found.search_result.category_names().collect(),
+            indexer_flags: &found.search_result.indexer_flags,
+            imdb_id: found.search_result.imdb_id,
+            info_hash: found.search_result.info_hash.as_deref(),
+            protocol: found.search_result.protocol,
+        }
+    }
+}
+
+pub async fn run(input_handler: Arc<InputHandler>) {
+    if let Ok(port) = std::env::var(API_PORT_ENV) {
+        let token = std::env::var(API_TOKEN_ENV)
+            .unwrap_or_else(|_| panic!("{API_TOKEN_ENV} env variable must be set when {API_PORT_ENV} is set"));
+        let addr = SocketAddr::new(util::parse_ip("API_IP"), port.parse().unwrap());
+        let fut = warp::serve(routes(input_handler, token))
+            .bind(addr).await
+            .graceful(async {
+                tokio::signal::ctrl_c()
+                    .await
+                    .expect("failed to listen to shutdown signal");
+            })
+            .run();
+        log::info!("API listening on http://{}", addr);
+        fut.await;
+    }
+}
+
+fn routes(input_handler: Arc<InputHandler>, token: String) -> impl Filter<Extract = (Response,), Error = Infallible> + Clone {
+    let with_input_handler = warp::any().map(move || input_handler.clone());
+    let search = warp::get()
+        .and(warp::path!("api" / "search"))
+        .and(warp::query::<SearchParams>())
+        .and(with_input_handler.clone())
+        .then(search);
+    let download = warp::post()
+        .and(warp::path!("api" / "download" / String))
+        .and(with_input_handler.clone())
+        .then(download);
+    let link = warp::get()
+        .and(warp::path!("api" / "link" / String))
+        .and(with_input_handler.clone())
+        .then(link);
+    let cache_stats = warp::get()
+        .and(warp::path!("api" / "cache"))
+        .and(with_input_handler)
+        .map(cache_stats);
+    authorized(token)
+        .and(search.or(download).unify().or(link).unify().or(cache_stats).unify())
+        .recover(rejection)
+        .unify()
+}
+
+fn authorized(token: String) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    let expected = format!("Bearer {token}");
+    warp::header::optional::<String>("authorization")
+        .and_then(move |authorization: Option<String>| {
+            let authorized = authorization.is_some_and(|authorization| constant_time_eq(authorization.as_bytes(), expected.as_bytes()));
+            async move {
+                if authorized {
+                    Ok(())
+                } else {
+                    Err(warp::reject::custom(Unauthorized))
+                }
+            }
+        })
+        .untuple_one()
+}
+
+/// Compares every byte regardless of where the first difference is, so that the time taken doesn't tell
+/// how much of a guessed token is right.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |difference, (a, b)| difference | (a ^ b)) == 0
+}
+
+async fn search(params: SearchParams, input_handler: Arc<InputHandler>) -> Response {
+    log::info!("API | Received search request \"{}\"", params.query);
+    match input_handler.find(&params.query, params.refresh).await {
+        Ok(found_results) => warp::reply::json(&json!({
+            "results": found_results.iter().map(ApiSearchResult::from).collect::<Vec<_>>()
+        })).into_response(),
+        Err(err) => error_reply(err),
+    }
+}
+
+async fn download(uuid: String, input_handler: Arc<InputHandler>) -> Response {
+    log::info!("API | Received download request for {}", uuid);
+    match input_handler.start_download(&uuid).await {
+        Ok(meta) => {
+            log::info!("API | Sent {} for downloading", meta);
+            warp::reply::with_status(warp::reply::json(&json!({ "status": "sent" })), StatusCode::ACCEPTED)
+                .into_response()
+        }
+        Err(err) => error_reply(err),
+    }
+}
+
+async fn link(uuid: String, input_handler: Arc<InputHandler>) -> Response {
+    log::info!("API | Received get link request for {}", uuid);
+    match input_handler.get_download_link(&uuid).await {
+        Ok(DownloadMeta::MagnetLink(link)) => warp::reply::json(&json!({ "magnet": link })).into_response(),
+        Ok(DownloadMeta::TorrentFile(file)) => file_reply(&file, "application/x-bittorrent", &format!("{uuid}.torrent")),
+        Ok(DownloadMeta::NzbFile(file)) => file_reply(&file, "application/x-nzb", &format!("{uuid}.nzb")),
+        Err(err) => error_reply(err),
+    }
+}
+
+/// Hits and misses of the search cache since the bot started.
+fn cache_stats(input_handler: Arc<InputHandler>) -> Response {
+    match input_handler.search_cache_stats() {
+        Some(stats) => warp::reply::json(&stats).into_response(),
+        None => json_error("Search cache is disabled", StatusCode::NOT_FOUND),
+    }
+}
+
+fn file_reply(file: &[u8], content_type: &str, filename: &str) -> Response {
+    warp::reply::with_header(
+        warp::reply::with_header(file.to_vec(), "content-type", content_type),
+        "content-disposition", format!("attachment; filename=\"{filename}\""))
+        .into_response()
+}
+
+fn error_reply(err: OperationError) -> Response {
+    let status = match err {
+        OperationError::NotFound(_) | OperationError::NoLink(_) => StatusCode::NOT_FOUND,
+        OperationError::Prowlarr(_) | OperationError::DownloadRejected(_) => StatusCode::BAD_GATEWAY,
+        OperationError::ProwlarrUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+        OperationError::Suspicious(_, _) => StatusCode::FORBIDDEN,
+        OperationError::Mapper(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    if status.is_server_error() {
+        log::error!("API | {}", err);
+    } else {
+        log::warn!("API | {}", err);
+    }
+    json_error(&err.to_string(), status)
+}
+
+async fn rejection(rejection: Rejection) -> Result<Response, Infallible> {
+    Ok(if rejection.find::<Unauthorized>().is_some() {
+        json_error("Unauthorized", StatusCode::UNAUTHORIZED)
+    } else if rejection.is_not_found() {
+        json_error("Not found", StatusCode::NOT_FOUND)
+    } else {
+        json_error(&format!("{rejection:?}"), StatusCode::BAD_REQUEST)
+    })
+}
+
+fn json_error(message: &str, status: StatusCode) -> Response {
+    warp::reply::with_status(warp::reply::json(&json!({ "error": message })), status).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::Value;
+    use warp::http::StatusCode;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::core::downloads_tracker::DownloadsTracker;
+    use crate::core::input_handler::InputHandler;
+    use crate::core::prowlarr::ProwlarrClient;
+    use crate::core::torrent_meta::TorrentMeta;
+    use crate::core::traits::sender::MockSender;
+    use crate::ext::api::web::{constant_time_eq, routes};
+    use crate::ext::uuid_mapper;
+
+    async fn input_handler() -> (MockServer, Arc<InputHandler>) {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/api/v1/search"))
+            .respond_with(ResponseTemplate::new(200)
+                .set_body_string(
+                    "[{\"guid\":\"1\",\"indexerId\":1,\"title\":\"Ubuntu\",\
+                    \"size\":20000,\"publishDate\":\"2015-05-15T00:00:00Z\",\
+                    \"infoUrl\":\"info url\",\"downloadUrl\":\"http://localhost/secret?apikey=key\",\
+                    \"magnetUrl\":\"magnet:?xt=urn:btih:c811b41641a09d192b8ed81b14064fff55d85ce3\",\
+                    \"seeders\":10,\"leechers\":2,\"indexerFlags\":[\"freeleech\"]}]"))
+            .mount(&mock_server)
+            .await;
+        let prowlarr = temp_env::with_vars(
+            [("PROWLARR_API_KEY", Some("key")), ("PROWLARR_BASE_URL", Some(&mock_server.uri()))],
+            ProwlarrClient::from_env);
+        let input_handler = Arc::new(InputHandler::new(
+            prowlarr,
+            uuid_mapper::create::<TorrentMeta>(),
+            Arc::new(DownloadsTracker::new()),
+            vec![],
+            Arc::new(MockSender::new())));
+        (mock_server, input_handler)
+    }
+
+    #[tokio::test]
+    async fn unauthorized() {
+        let (_mock_server, input_handler) = input_handler().await;
+
+        let response = warp::test::request()
+            .path("/api/search?query=Ubuntu")
+            .header("authorization", "Bearer wrong")
+            .reply(&routes(input_handler, "token".to_string()))
+            .await;
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn tokens_are_compared_in_full() {
+        assert!(constant_time_eq(b"Bearer token", b"Bearer token"));
+        assert!(!constant_time_eq(b"Bearer token", b"Bearer tokem"));
+        assert!(!constant_time_eq(b"Bearer token", b"Bearer token2"));
+        assert!(!constant_time_eq(b"", b"Bearer token"));
+    }
+
+    #[tokio::test]
+    async fn search_and_get_link() {
+        let (_mock_server, input_handler) = input_handler().await;
+        let routes = routes(input_handler, "token".to_string());
+
+        let response = warp::test::request()
+            .path("/api/search?query=Ubuntu")
+            .header("authorization", "Bearer token")
+            .reply(&routes)
+            .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = serde_json::from_slice(response.body()).unwrap();
+        let result = &body["results"][0];
+        assert_eq!(result["title"], "Ubuntu");
+        assert_eq!(result["seeders"], 10);
+        assert_eq!(result["indexerFlags"][0], "freeleech");
+        assert_eq!(result["protocol"], "torrent");
+        assert!(result.get("downloadUrl").is_none());
+        let id = result["id"].as_str().unwrap();
+
+        let response = warp::test::request()
+            .path(&format!("/api/link/{id}"))
+            .header("authorization", "Bearer token")
+            .reply(&routes)
+            .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["magnet"], "magnet:?xt=urn:btih:c811b41641a09d192b8ed81b14064fff55d85ce3");
+    }
+
+    #[tokio::test]
+    async fn download_unknown_result() {
+        let (_mock_server, input_handler) = input_handler().await;
+
+        let response = warp::test::request()
+            .method("POST")
+            .path("/api/download/unknown")
+            .header("authorization", "Bearer token")
+            .reply(&routes(input_handler, "token".to_string()))
+            .await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let body: Value = serde_json::from_slice(response.body()).unwrap();
+        assert_eq!(body["error"], "Link for uuid unknown not found");
+    }
+}
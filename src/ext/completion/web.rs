@@ -7,15 +7,20 @@ use warp::reply::WithStatus;
 use crate::core::{completion, util};
 use crate::core::completion::CompletionRequest;
 use crate::core::downloads_tracker::DownloadsTracker;
+use crate::core::traits::notifier::NotificationChannels;
 use crate::core::traits::sender::Sender;
 
-pub async fn run(sender: Arc<dyn Sender>, downloads_tracker: Arc<DownloadsTracker>) {
+pub async fn run(sender: Arc<dyn Sender>,
+                 downloads_tracker: Arc<DownloadsTracker>,
+                 notification_channels: NotificationChannels) {
+    let notification_channels = Arc::new(notification_channels);
     if let Ok(port) = std::env::var("COMPLETE_PORT") {
         let filter = warp::put()
             .and(warp::path("complete"))
             .and(warp::body::json())
             .and(warp::any().map(move || downloads_tracker.clone()))
             .and(warp::any().map(move || sender.clone()))
+            .and(warp::any().map(move || notification_channels.clone()))
             .then(completion);
         let addr = SocketAddr::new(util::parse_ip("COMPLETE_IP"), port.parse().unwrap());
         let fut = warp::serve(filter)
@@ -34,7 +39,8 @@ pub async fn run(sender: Arc<dyn Sender>, downloads_tracker: Arc<DownloadsTracke
 
 async fn completion(request: CompletionRequest,
                     downloads_tracker: Arc<DownloadsTracker>,
-                    sender: Arc<dyn Sender>) -> WithStatus<String> {
-    completion::notify(request, downloads_tracker, sender).await;
+                    sender: Arc<dyn Sender>,
+                    notification_channels: Arc<NotificationChannels>) -> WithStatus<String> {
+    tokio::spawn(completion::notify(request, downloads_tracker, sender, notification_channels));
     warp::reply::with_status(String::new(), warp::http::StatusCode::ACCEPTED)
 }
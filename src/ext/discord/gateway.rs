@@ -0,0 +1,310 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use thiserror::Error;
+use tokio::net::TcpStream;
+use tokio::time::{interval_at, Instant, Interval};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+pub const GATEWAY_URL: &str = "wss://gateway.discord.gg/?v=10&encoding=json";
+
+const OP_DISPATCH: u8 = 0;
+const OP_HEARTBEAT: u8 = 1;
+const OP_IDENTIFY: u8 = 2;
+const OP_RECONNECT: u8 = 7;
+const OP_INVALID_SESSION: u8 = 9;
+const OP_HELLO: u8 = 10;
+
+#[derive(Error, Debug)]
+pub enum GatewayError {
+    #[error("Discord gateway connection error: {0}")]
+    Connection(String),
+    #[error("Discord gateway closed the connection")]
+    Closed,
+    #[error("Discord gateway asked to reconnect")]
+    Reconnect,
+    #[error("Unexpected Discord gateway payload: {0}")]
+    Protocol(String),
+}
+
+/// A connection carrying the gateway's JSON payloads, the websocket in production and channels in tests.
+#[async_trait]
+pub trait Transport: Send {
+    async fn send(&mut self, payload: String) -> Result<(), GatewayError>;
+    /// Returns `None` when the connection is closed.
+    async fn receive(&mut self) -> Option<Result<String, GatewayError>>;
+}
+
+pub enum GatewayEvent {
+    Ready { application_id: String },
+    Interaction(Interaction),
+}
+
+#[derive(Deserialize)]
+pub struct Interaction {
+    pub id: String,
+    pub token: String,
+    #[serde(rename = "type")]
+    pub interaction_type: u8,
+    pub channel_id: Option<String>,
+    pub member: Option<Member>,
+    pub user: Option<User>,
+    pub locale: Option<String>,
+    pub data: Option<InteractionData>,
+}
+
+#[derive(Deserialize)]
+pub struct Member {
+    pub user: User,
+}
+
+#[derive(Deserialize)]
+pub struct User {
+    pub id: String,
+}
+
+#[derive(Deserialize)]
+pub struct InteractionData {
+    pub name: String,
+    #[serde(default)]
+    pub options: Vec<InteractionOption>,
+}
+
+#[derive(Deserialize)]
+pub struct InteractionOption {
+    pub name: String,
+    pub value: Value,
+}
+
+#[derive(Deserialize)]
+struct Payload {
+    op: u8,
+    #[serde(default)]
+    d: Value,
+    s: Option<u64>,
+    t: Option<String>,
+}
+
+/// Discord gateway session: identifies the bot, keeps the connection alive with heartbeats
+/// and turns dispatched payloads into events the bot is interested in.
+pub struct Gateway<T: Transport> {
+    transport: T,
+    heartbeat: Interval,
+    sequence: Option<u64>,
+}
+
+impl<T: Transport> Gateway<T> {
+    pub async fn connect(mut transport: T, token: &str) -> Result<Gateway<T>, GatewayError> {
+        let hello = receive_payload(&mut transport).await?;
+        if hello.op != OP_HELLO {
+            return Err(GatewayError::Protocol(format!("expected Hello, got op {}", hello.op)));
+        }
+        let heartbeat_interval = hello.d["heartbeat_interval"].as_u64()
+            .map(Duration::from_millis)
+            .ok_or_else(|| GatewayError::Protocol("Hello without heartbeat_interval".to_string()))?;
+        transport.send(json!({
+            "op": OP_IDENTIFY,
+            "d": {
+                "token": token,
+                "intents": 0,
+                "properties": { "os": std::env::consts::OS, "browser": "prowlarr-telegram-client", "device": "prowlarr-telegram-client" },
+            },
+        }).to_string()).await?;
+        Ok(Gateway {
+            transport,
+            heartbeat: interval_at(Instant::now() + heartbeat_interval, heartbeat_interval),
+            sequence: None,
+        })
+    }
+
+    pub async fn next_event(&mut self) -> Result<GatewayEvent, GatewayError> {
+        loop {
+            let payload = tokio::select! {
+                _ = self.heartbeat.tick() => {
+                    self.send_heartbeat().await?;
+                    continue;
+                }
+                payload = receive_payload(&mut self.transport) => payload?,
+            };
+            if payload.s.is_some() {
+                self.sequence = payload.s;
+            }
+            match payload.op {
+                OP_DISPATCH => match payload.t.as_deref() {
+                    Some("READY") => return Ok(GatewayEvent::Ready {
+                        application_id: payload.d["application"]["id"].as_str()
+                            .ok_or_else(|| GatewayError::Protocol("READY without application id".to_string()))?
+                            .to_string()
+                    }),
+                    Some("INTERACTION_CREATE") => return serde_json::from_value(payload.d)
+                        .map(GatewayEvent::Interaction)
+                        .map_err(|err| GatewayError::Protocol(err.to_string())),
+                    _ => {}
+                },
+                OP_HEARTBEAT => self.send_heartbeat().await?,
+                OP_RECONNECT | OP_INVALID_SESSION => return Err(GatewayError::Reconnect),
+                _ => {}
+            }
+        }
+    }
+
+    async fn send_heartbeat(&mut self) -> Result<(), GatewayError> {
+        self.transport.send(json!({ "op": OP_HEARTBEAT, "d": self.sequence }).to_string()).await
+    }
+}
+
+async fn receive_payload(transport: &mut impl Transport) -> Result<Payload, GatewayError> {
+    let payload = transport.receive().await.ok_or(GatewayError::Closed)??;
+    serde_json::from_str(&payload).map_err(|err| GatewayError::Protocol(err.to_string()))
+}
+
+pub struct WebSocketTransport(WebSocketStream<MaybeTlsStream<TcpStream>>);
+
+impl WebSocketTransport {
+    pub async fn connect(url: &str) -> Result<WebSocketTransport, GatewayError> {
+        tokio_tungstenite::connect_async(url)
+            .await
+            .map(|(stream, _)| WebSocketTransport(stream))
+            .map_err(|err| GatewayError::Connection(err.to_string()))
+    }
+}
+
+#[async_trait]
+impl Transport for WebSocketTransport {
+    async fn send(&mut self, payload: String) -> Result<(), GatewayError> {
+        self.0.send(Message::text(payload))
+            .await
+            .map_err(|err| GatewayError::Connection(err.to_string()))
+    }
+
+    async fn receive(&mut self) -> Option<Result<String, GatewayError>> {
+        loop {
+            match self.0.next().await? {
+                Ok(Message::Text(text)) => return Some(Ok(text.to_string())),
+                Ok(Message::Close(_)) => return None,
+                Ok(_) => {}
+                Err(err) => return Some(Err(GatewayError::Connection(err.to_string()))),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use async_trait::async_trait;
+    use serde_json::{json, Value};
+    use tokio::sync::mpsc;
+
+    use crate::ext::discord::gateway::{Gateway, GatewayError, GatewayEvent, Transport};
+
+    /// Transport driven by the test: payloads pushed to `server` are received by the gateway,
+    /// payloads sent by the gateway can be read from `sent`.
+    pub struct FakeTransport {
+        incoming: mpsc::UnboundedReceiver<String>,
+        outgoing: mpsc::UnboundedSender<String>,
+    }
+
+    pub struct FakeServer {
+        pub server: mpsc::UnboundedSender<String>,
+        pub sent: mpsc::UnboundedReceiver<String>,
+    }
+
+    impl FakeServer {
+        pub fn push(&self, payload: Value) {
+            self.server.send(payload.to_string()).unwrap();
+        }
+
+        pub async fn next_sent(&mut self) -> Value {
+            serde_json::from_str(&self.sent.recv().await.unwrap()).unwrap()
+        }
+    }
+
+    pub fn fake_transport() -> (FakeTransport, FakeServer) {
+        let (server, incoming) = mpsc::unbounded_channel();
+        let (outgoing, sent) = mpsc::unbounded_channel();
+        (FakeTransport { incoming, outgoing }, FakeServer { server, sent })
+    }
+
+    #[async_trait]
+    impl Transport for FakeTransport {
+        async fn send(&mut self, payload: String) -> Result<(), GatewayError> {
+            self.outgoing.send(payload).map_err(|_| GatewayError::Closed)
+        }
+
+        async fn receive(&mut self) -> Option<Result<String, GatewayError>> {
+            self.incoming.recv().await.map(Ok)
+        }
+    }
+
+    pub fn hello() -> Value {
+        json!({ "op": 10, "d": { "heartbeat_interval": 45000 } })
+    }
+
+    #[tokio::test]
+    async fn identifies_after_hello() {
+        let (transport, mut server) = fake_transport();
+        server.push(hello());
+
+        Gateway::connect(transport, "token").await.unwrap();
+
+        let identify = server.next_sent().await;
+        assert_eq!(identify["op"], 2);
+        assert_eq!(identify["d"]["token"], "token");
+    }
+
+    #[tokio::test]
+    async fn dispatched_events() {
+        let (transport, mut server) = fake_transport();
+        server.push(hello());
+        server.push(json!({ "op": 0, "s": 1, "t": "READY", "d": { "application": { "id": "app1" } } }));
+        server.push(json!({ "op": 0, "s": 2, "t": "GUILD_CREATE", "d": {} }));
+        server.push(json!({ "op": 0, "s": 3, "t": "INTERACTION_CREATE", "d": {
+            "id": "int1", "token": "int-token", "type": 2, "channel_id": "100", "locale": "ru",
+            "member": { "user": { "id": "42" } },
+            "data": { "name": "search", "options": [{ "name": "query", "type": 3, "value": "Ubuntu" }] }
+        }}));
+        let mut gateway = Gateway::connect(transport, "token").await.unwrap();
+
+        match gateway.next_event().await.unwrap() {
+            GatewayEvent::Ready { application_id } => assert_eq!(application_id, "app1"),
+            GatewayEvent::Interaction(_) => panic!("READY expected"),
+        }
+        match gateway.next_event().await.unwrap() {
+            GatewayEvent::Interaction(interaction) => {
+                assert_eq!(interaction.id, "int1");
+                assert_eq!(interaction.member.unwrap().user.id, "42");
+                assert_eq!(interaction.data.unwrap().options[0].value, "Ubuntu");
+            }
+            GatewayEvent::Ready { .. } => panic!("INTERACTION_CREATE expected"),
+        }
+        server.next_sent().await;
+    }
+
+    #[tokio::test]
+    async fn heartbeat_on_request_with_last_sequence() {
+        let (transport, mut server) = fake_transport();
+        server.push(hello());
+        server.push(json!({ "op": 0, "s": 5, "t": "GUILD_CREATE", "d": {} }));
+        server.push(json!({ "op": 1 }));
+        server.push(json!({ "op": 7 }));
+        let mut gateway = Gateway::connect(transport, "token").await.unwrap();
+
+        assert!(matches!(gateway.next_event().await, Err(GatewayError::Reconnect)));
+        server.next_sent().await;
+        assert_eq!(server.next_sent().await, json!({ "op": 1, "d": 5 }));
+    }
+
+    #[tokio::test]
+    async fn closed_connection() {
+        let (transport, server) = fake_transport();
+        server.push(hello());
+        let mut gateway = Gateway::connect(transport, "token").await.unwrap();
+        drop(server);
+
+        assert!(matches!(gateway.next_event().await, Err(GatewayError::Closed)));
+    }
+}
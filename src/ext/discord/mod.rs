@@ -0,0 +1,185 @@
+use bytes::Bytes;
+use reqwest::multipart::{Form, Part};
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+use serde_json::Value;
+use url::Url;
+
+pub mod gateway;
+
+const DISCORD_TOKEN_ENV: &str = "DISCORD_TOKEN";
+const DISCORD_API_URL: &str = "https://discord.com/api/v10/";
+
+/// Client for the subset of the Discord REST API needed by the bot.
+pub struct DiscordClient {
+    api_url: Url,
+    token: String,
+    client: Client,
+}
+
+#[derive(Deserialize)]
+struct CreatedMessage {
+    id: String,
+}
+
+impl DiscordClient {
+    pub fn from_env() -> DiscordClient {
+        DiscordClient::new(
+            Url::parse(DISCORD_API_URL).unwrap(),
+            std::env::var(DISCORD_TOKEN_ENV)
+                .unwrap_or_else(|_| panic!("Cannot get the {DISCORD_TOKEN_ENV} env variable")))
+    }
+
+    pub fn new(api_url: Url, token: String) -> DiscordClient {
+        DiscordClient {
+            api_url,
+            token,
+            client: Client::new(),
+        }
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    /// Creates a message in a channel and returns its id.
+    pub async fn create_message(&self, channel_id: &str, message: &Value) -> reqwest::Result<String> {
+        Ok(self.authorized(self.client.post(self.url(&["channels", channel_id, "messages"])))
+            .json(message)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<CreatedMessage>()
+            .await?
+            .id)
+    }
+
+    pub async fn create_message_with_file(&self, channel_id: &str, filename: &str, file: Bytes) -> reqwest::Result<String> {
+        let form = Form::new()
+            .part("files[0]", Part::stream(file).file_name(filename.to_string()));
+        Ok(self.authorized(self.client.post(self.url(&["channels", channel_id, "messages"])))
+            .multipart(form)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<CreatedMessage>()
+            .await?
+            .id)
+    }
+
+    pub async fn edit_message(&self, channel_id: &str, message_id: &str, message: &Value) -> reqwest::Result<()> {
+        self.authorized(self.client.patch(self.url(&["channels", channel_id, "messages", message_id])))
+            .json(message)
+            .send()
+            .await?
+            .error_for_status()
+            .map(|_| {})
+    }
+
+    pub async fn trigger_typing(&self, channel_id: &str) -> reqwest::Result<()> {
+        self.authorized(self.client.post(self.url(&["channels", channel_id, "typing"])))
+            .send()
+            .await?
+            .error_for_status()
+            .map(|_| {})
+    }
+
+    pub async fn create_interaction_response(&self, interaction_id: &str, interaction_token: &str, response: &Value) -> reqwest::Result<()> {
+        self.client.post(self.url(&["interactions", interaction_id, interaction_token, "callback"]))
+            .json(response)
+            .send()
+            .await?
+            .error_for_status()
+            .map(|_| {})
+    }
+
+    /// Replaces all global slash commands of the application.
+    pub async fn overwrite_commands(&self, application_id: &str, commands: &Value) -> reqwest::Result<()> {
+        self.authorized(self.client.put(self.url(&["applications", application_id, "commands"])))
+            .json(commands)
+            .send()
+            .await?
+            .error_for_status()
+            .map(|_| {})
+    }
+
+    fn url(&self, path_segments: &[&str]) -> Url {
+        let mut url = self.api_url.clone();
+        url.path_segments_mut()
+            .expect("Discord API URL must be a base URL")
+            .pop_if_empty()
+            .extend(path_segments);
+        url
+    }
+
+    fn authorized(&self, request: RequestBuilder) -> RequestBuilder {
+        request.header(reqwest::header::AUTHORIZATION, format!("Bot {}", self.token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use serde_json::json;
+    use url::Url;
+    use wiremock::matchers::{body_json, body_string_contains, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::ext::discord::DiscordClient;
+
+    fn client(mock_server: &MockServer) -> DiscordClient {
+        DiscordClient::new(Url::parse(&format!("{}/api/v10/", mock_server.uri())).unwrap(), "token".to_string())
+    }
+
+    #[tokio::test]
+    async fn create_message() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v10/channels/100/messages"))
+            .and(header("Authorization", "Bot token"))
+            .and(body_json(json!({ "content": "hi" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "200" })))
+            .mount(&mock_server)
+            .await;
+
+        let message_id = client(&mock_server).create_message("100", &json!({ "content": "hi" })).await.unwrap();
+
+        assert_eq!(message_id, "200");
+    }
+
+    #[tokio::test]
+    async fn create_message_with_file() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v10/channels/100/messages"))
+            .and(body_string_contains("filename=\"uuid.torrent\""))
+            .and(body_string_contains("torrent contents"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "200" })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        client(&mock_server)
+            .create_message_with_file("100", "uuid.torrent", Bytes::from("torrent contents"))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn interaction_response_is_not_authorized_with_bot_token() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v10/interactions/1/interaction-token/callback"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        client(&mock_server)
+            .create_interaction_response("1", "interaction-token", &json!({ "type": 4 }))
+            .await
+            .unwrap();
+        let requests = mock_server.received_requests().await.unwrap();
+        assert!(requests[0].headers.get("Authorization").is_none());
+    }
+}
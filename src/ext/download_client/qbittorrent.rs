@@ -0,0 +1,174 @@
+use async_trait::async_trait;
+use reqwest::header::{CONTENT_TYPE, COOKIE, SET_COOKIE};
+use reqwest::multipart::{Form, Part};
+use reqwest::{Client, RequestBuilder};
+use url::{form_urlencoded, Url};
+
+use crate::core::download_meta::DownloadMeta;
+use crate::core::traits::download_client::DownloadClient;
+
+const QBITTORRENT_URL_ENV: &str = "QBITTORRENT_URL";
+const QBITTORRENT_USERNAME_ENV: &str = "QBITTORRENT_USERNAME";
+const QBITTORRENT_PASSWORD_ENV: &str = "QBITTORRENT_PASSWORD";
+
+/// Adds torrents through the [qBittorrent Web API](https://github.com/qbittorrent/qBittorrent/wiki/WebUI-API-(qBittorrent-4.1)).
+pub struct QBittorrentClient {
+    url: Url,
+    credentials: Option<(String, String)>,
+    client: Client,
+}
+
+impl QBittorrentClient {
+    /// `None` if `QBITTORRENT_URL` isn't set. Credentials may be omitted if the Web UI doesn't require them,
+    /// e.g. for clients on localhost.
+    pub fn from_env() -> Option<QBittorrentClient> {
+        let url_string = std::env::var(QBITTORRENT_URL_ENV).ok()?;
+        let url = Url::parse(&url_string)
+            .unwrap_or_else(|err| panic!("Could not parse {}: {}: \"{}\"", QBITTORRENT_URL_ENV, err, url_string));
+        let credentials = std::env::var(QBITTORRENT_USERNAME_ENV).ok()
+            .map(|username| (username, std::env::var(QBITTORRENT_PASSWORD_ENV)
+                .unwrap_or_else(|_| panic!("{QBITTORRENT_PASSWORD_ENV} env variable must be set when {QBITTORRENT_USERNAME_ENV} is set"))));
+        Some(QBittorrentClient { url, credentials, client: Client::new() })
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}api/v2/{}", self.url, path)
+    }
+
+    /// Session cookie to authorize further requests with, `None` if there are no credentials.
+    async fn login(&self) -> Result<Option<String>, String> {
+        let Some((username, password)) = &self.credentials else {
+            return Ok(None);
+        };
+        let body: String = form_urlencoded::Serializer::new(String::new())
+            .append_pair("username", username)
+            .append_pair("password", password)
+            .finish();
+        let response = self.client.post(self.url("auth/login"))
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .body(body)
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+        response.headers()
+            .get_all(SET_COOKIE)
+            .iter()
+            .filter_map(|cookie| cookie.to_str().ok())
+            .filter_map(|cookie| cookie.split(';').next())
+            .find(|cookie| cookie.starts_with("SID="))
+            .map(|cookie| Some(cookie.to_string()))
+            .ok_or_else(|| format!("qBittorrent login failed with {}", response.status()))
+    }
+
+    fn authorized(&self, request: RequestBuilder, session: Option<String>) -> RequestBuilder {
+        match session {
+            Some(session) => request.header(COOKIE, session),
+            None => request,
+        }
+    }
+}
+
+#[async_trait]
+impl DownloadClient for QBittorrentClient {
+    async fn add(&self, torrent: &DownloadMeta) -> Result<(), String> {
+        let form = match torrent {
+            DownloadMeta::MagnetLink(link) => Form::new().text("urls", link.clone()),
+            DownloadMeta::TorrentFile(file) => Form::new()
+                .part("torrents", Part::stream(file.clone()).file_name("upload.torrent")),
+            DownloadMeta::NzbFile(_) => return Err("qBittorrent can't download .nzb files".to_string()),
+        };
+        let session = self.login().await?;
+        let response = self.authorized(self.client.post(self.url("torrents/add")), session)
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|err| err.to_string())?;
+        let status = response.status();
+        let text = response.text().await.unwrap_or_default();
+        // qBittorrent before 5.0 answers with 200 and "Fails." if the torrent couldn't be added
+        if status.is_success() && text.trim() != "Fails." {
+            Ok(())
+        } else {
+            Err(format!("{} {}", status, text))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use wiremock::matchers::{body_string, body_string_contains, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::core::download_meta::DownloadMeta;
+    use crate::core::traits::download_client::DownloadClient;
+    use crate::ext::download_client::qbittorrent::QBittorrentClient;
+
+    fn client(mock_server: &MockServer, username: Option<&str>) -> QBittorrentClient {
+        temp_env::with_vars(
+            [("QBITTORRENT_URL", Some(mock_server.uri().as_str())),
+                ("QBITTORRENT_USERNAME", username),
+                ("QBITTORRENT_PASSWORD", Some("p@ss"))],
+            || QBittorrentClient::from_env().unwrap())
+    }
+
+    #[tokio::test]
+    async fn adds_magnet_link_after_login() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v2/auth/login"))
+            .and(body_string("username=admin&password=p%40ss"))
+            .respond_with(ResponseTemplate::new(200)
+                .insert_header("set-cookie", "SID=abc123; HttpOnly; path=/")
+                .set_body_string("Ok."))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/api/v2/torrents/add"))
+            .and(header("cookie", "SID=abc123"))
+            .and(body_string_contains("magnet:?xt=urn:btih:c811b41641a09d192b8ed81b14064fff55d85ce3"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("Ok."))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        client(&mock_server, Some("admin"))
+            .add(&DownloadMeta::MagnetLink("magnet:?xt=urn:btih:c811b41641a09d192b8ed81b14064fff55d85ce3".to_string()))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejected_login() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v2/auth/login"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("Fails."))
+            .mount(&mock_server)
+            .await;
+
+        let result = client(&mock_server, Some("admin"))
+            .add(&DownloadMeta::TorrentFile(Bytes::from_static(b"d4:infode")))
+            .await;
+
+        assert_eq!(result, Err("qBittorrent login failed with 200 OK".to_string()));
+    }
+
+    #[tokio::test]
+    async fn rejected_torrent_without_credentials() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v2/torrents/add"))
+            .and(body_string_contains("filename=\"upload.torrent\""))
+            .respond_with(ResponseTemplate::new(200).set_body_string("Fails."))
+            .mount(&mock_server)
+            .await;
+
+        let result = client(&mock_server, None)
+            .add(&DownloadMeta::TorrentFile(Bytes::from_static(b"d4:infode")))
+            .await;
+
+        assert_eq!(result, Err("200 OK Fails.".to_string()));
+    }
+}
@@ -0,0 +1,117 @@
+use std::sync::Arc;
+
+use tokio::io::{AsyncBufRead, AsyncBufReadExt};
+
+use crate::core::input_handler::InputHandler;
+use crate::core::traits::input::{Command, Destination, Input, Locale, ReplyToMessage, Source};
+use crate::ext::input_handler;
+
+struct CliInput {
+    command: Command,
+    locale: Locale,
+}
+
+impl Input for CliInput {
+    fn get_command(&self) -> Command {
+        self.command.clone()
+    }
+
+    fn get_source(&self) -> Source {
+        0
+    }
+
+    fn get_destination(&self) -> Destination {
+        0
+    }
+
+    fn get_reply_to_message(&self) -> ReplyToMessage {
+        0
+    }
+
+    fn get_locale(&self) -> Locale {
+        self.locale.clone()
+    }
+}
+
+/// Parses the same commands as Telegram, so result ids printed in the terminal can be copied as they are.
+fn parse_command(text: &str) -> Command {
+    input_handler::parse_command('/', text)
+}
+
+/// Handles one command per line until the end of input, waiting for every command to complete,
+/// so the output of a piped script is in the order of its commands.
+pub async fn run<R: AsyncBufRead + Unpin>(input: R, locale: Locale, input_handler: Arc<InputHandler>) {
+    let mut lines = input.lines();
+    loop {
+        let line = tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            line = lines.next_line() => line,
+        };
+        match line {
+            Ok(Some(line)) if line.trim().is_empty() => {}
+            Ok(Some(line)) => {
+                let input = CliInput { command: parse_command(line.trim()), locale: locale.clone() };
+                if let Err(err) = input_handler.handle(Box::new(input)).await {
+                    log::error!("Error when handling a command: {}", err);
+                }
+            }
+            Ok(None) => break,
+            Err(err) => {
+                log::error!("Could not read a command: {}", err);
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::core::downloads_tracker::DownloadsTracker;
+    use crate::core::input_handler::InputHandler;
+    use crate::core::prowlarr::ProwlarrClient;
+    use crate::core::torrent_meta::TorrentMeta;
+    use crate::core::traits::input::Command;
+    use crate::core::traits::sender::MockSender;
+    use crate::ext::input_handler::cli::{parse_command, run};
+    use crate::ext::uuid_mapper;
+
+    #[test]
+    fn commands() {
+        assert_eq!(parse_command("Ubuntu 22.04"), Command::Search("Ubuntu 22.04".into()));
+        assert_eq!(parse_command("/d_uuid"), Command::Download("uuid".into()));
+        assert_eq!(parse_command("/m_uuid"), Command::GetLink("uuid".into()));
+        assert_eq!(parse_command("/r_uuid"), Command::ShowResult("uuid".into()));
+        assert_eq!(parse_command("/start"), Command::Start);
+        assert_eq!(parse_command("/refresh Ubuntu 22.04"), Command::Refresh("Ubuntu 22.04".into()));
+        assert_eq!(parse_command("/health"), Command::Health);
+        assert_eq!(parse_command("/watch Ubuntu 22.04"), Command::Watch("Ubuntu 22.04".into()));
+        assert_eq!(parse_command("/watches"), Command::Watches);
+        assert_eq!(parse_command("/unwatch 3"), Command::Unwatch("3".into()));
+        assert_eq!(parse_command("/series The Office"), Command::Series("The Office".into()));
+        assert_eq!(parse_command("/movie Alien"), Command::Movie("Alien".into()));
+        assert_eq!(parse_command("/a_uuid_4"), Command::Add("uuid_4".into()));
+        assert_eq!(parse_command("/files_uuid"), Command::Files("uuid".into()));
+        assert_eq!(parse_command("/unknown"), Command::Help);
+    }
+
+    #[tokio::test]
+    async fn handles_every_line_until_end_of_input() {
+        let mut sender = MockSender::new();
+        sender.expect_send()
+            .times(2)
+            .returning(|_, _| Box::pin(async { Ok(()) }));
+        let prowlarr = temp_env::with_vars(
+            [("PROWLARR_API_KEY", Some("key")), ("PROWLARR_BASE_URL", Some("http://localhost"))],
+            ProwlarrClient::from_env);
+        let input_handler = Arc::new(InputHandler::new(
+            prowlarr,
+            uuid_mapper::create::<TorrentMeta>(),
+            Arc::new(DownloadsTracker::new()),
+            vec![],
+            Arc::new(sender)));
+
+        run(&b"/help\n\n/start\n"[..], "en".into(), input_handler).await;
+    }
+}
@@ -0,0 +1,302 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::core::input_handler::InputHandler;
+use crate::core::traits::input::Command::{Add, Download, Files, GetLink, Health, Help, Movie, Refresh, Search, Series, Unwatch, Watch, Watches};
+use crate::core::traits::input::{Command, Destination, Input, Locale, ReplyToMessage, Source};
+use crate::ext::discord::gateway::{Gateway, GatewayError, GatewayEvent, Interaction, Transport, WebSocketTransport, GATEWAY_URL};
+use crate::ext::discord::DiscordClient;
+
+const APPLICATION_COMMAND_INTERACTION: u8 = 2;
+const CHANNEL_MESSAGE_WITH_SOURCE: u8 = 4;
+const EPHEMERAL_FLAG: u32 = 1 << 6;
+const STRING_OPTION: u8 = 3;
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+struct SlashCommand {
+    name: &'static str,
+    description: &'static str,
+    option: Option<(&'static str, &'static str)>,
+    to_command: fn(&str) -> Command,
+}
+
+const COMMANDS: &[SlashCommand] = &[
+    SlashCommand { name: "search", description: "command_search", option: Some(("query", "option_query")), to_command: |query| Search(query.into()) },
+    SlashCommand { name: "download", description: "command_download", option: Some(("id", "option_id")), to_command: |uuid| Download(uuid.into()) },
+    SlashCommand { name: "link", description: "command_link", option: Some(("id", "option_id")), to_command: |uuid| GetLink(uuid.into()) },
+    SlashCommand { name: "help", description: "command_help", option: None, to_command: |_| Help },
+    SlashCommand { name: "watch", description: "command_watch", option: Some(("query", "option_query")), to_command: |query| Watch(query.into()) },
+    SlashCommand { name: "watches", description: "command_watches", option: None, to_command: |_| Watches },
+    SlashCommand { name: "unwatch", description: "command_unwatch", option: Some(("number", "option_watch_number")), to_command: |number| Unwatch(number.into()) },
+    SlashCommand { name: "series", description: "command_series", option: Some(("query", "option_title")), to_command: |query| Series(query.into()) },
+    SlashCommand { name: "movie", description: "command_movie", option: Some(("query", "option_title")), to_command: |query| Movie(query.into()) },
+    SlashCommand { name: "add", description: "command_add", option: Some(("id", "option_id")), to_command: |argument| Add(argument.into()) },
+    SlashCommand { name: "files", description: "command_files", option: Some(("id", "option_id")), to_command: |uuid| Files(uuid.into()) },
+    SlashCommand { name: "refresh", description: "command_refresh", option: Some(("query", "option_query")), to_command: |query| Refresh(query.into()) },
+    SlashCommand { name: "health", description: "command_health", option: None, to_command: |_| Health },
+];
+
+/// Text of a slash command the bot suggests, as the user would type it.
+pub fn command_text(command: &Command) -> Option<String> {
+    let (name, argument) = match command {
+        Download(uuid) => ("download", uuid),
+        GetLink(uuid) => ("link", uuid),
+        Files(uuid) => ("files", uuid),
+        Add(argument) => ("add", argument),
+        _ => return None,
+    };
+    COMMANDS.iter()
+        .find(|command| command.name == name)
+        .and_then(|command| command.option)
+        .map(|(option, _)| format!("/{name} {option}:{argument}"))
+}
+
+struct DiscordInput {
+    command: Command,
+    source: Source,
+    destination: Destination,
+    locale: Locale,
+}
+
+impl Input for DiscordInput {
+    fn get_command(&self) -> Command {
+        self.command.clone()
+    }
+
+    fn get_source(&self) -> Source {
+        self.source
+    }
+
+    fn get_destination(&self) -> Destination {
+        self.destination
+    }
+
+    /// Slash commands aren't messages, so there is nothing to reply to.
+    fn get_reply_to_message(&self) -> ReplyToMessage {
+        0
+    }
+
+    fn get_locale(&self) -> Locale {
+        self.locale.clone()
+    }
+}
+
+fn to_input(interaction: &Interaction) -> Option<DiscordInput> {
+    if interaction.interaction_type != APPLICATION_COMMAND_INTERACTION {
+        return None;
+    }
+    let data = interaction.data.as_ref()?;
+    let command = COMMANDS.iter().find(|command| command.name == data.name)?;
+    let argument = data.options.iter()
+        .find(|option| command.option.is_some_and(|(name, _)| name == option.name))
+        .and_then(|option| option.value.as_str())
+        .unwrap_or_default()
+        .trim();
+    let user = interaction.member.as_ref()
+        .map(|member| &member.user)
+        .or(interaction.user.as_ref())?;
+    Some(DiscordInput {
+        command: (command.to_command)(argument),
+        source: user.id.parse().ok()?,
+        destination: interaction.channel_id.as_ref()?.parse().ok()?,
+        locale: interaction.locale.as_deref()
+            .and_then(|locale| locale.split('-').next())
+            .unwrap_or("en")
+            .into(),
+    })
+}
+
+fn slash_commands() -> Value {
+    COMMANDS.iter()
+        .map(|command| {
+            let mut definition = localized("name", command.name, command.description);
+            if let Some((option, description)) = command.option {
+                let mut option = localized("name", option, description);
+                option["type"] = STRING_OPTION.into();
+                option["required"] = true.into();
+                definition["options"] = json!([option]);
+            }
+            definition
+        })
+        .collect()
+}
+
+fn localized(name_field: &str, name: &str, description: &str) -> Value {
+    let localizations: serde_json::Map<String, Value> = rust_i18n::available_locales!().iter()
+        .filter(|locale| *locale != "en")
+        .map(|locale| (locale.to_string(), t!(description, locale = locale).into()))
+        .collect();
+    json!({
+        name_field: name,
+        "description": t!(description, locale = "en"),
+        "description_localizations": localizations,
+    })
+}
+
+pub async fn run(client: Arc<DiscordClient>, input_handler: Arc<InputHandler>) {
+    log::info!("Starting Discord bot...");
+    loop {
+        let session = async {
+            let transport = WebSocketTransport::connect(GATEWAY_URL).await?;
+            let gateway = Gateway::connect(transport, client.token()).await?;
+            Err::<(), GatewayError>(serve(&client, gateway, &input_handler).await)
+        };
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            result = session => if let Err(err) = result {
+                log::warn!("{}, reconnecting in {:?}", err, RECONNECT_DELAY);
+            },
+        }
+        tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            _ = tokio::time::sleep(RECONNECT_DELAY) => {}
+        }
+    }
+}
+
+/// Handles gateway events until the session ends, returning the reason it ended.
+async fn serve<T: Transport>(client: &DiscordClient, mut gateway: Gateway<T>, input_handler: &Arc<InputHandler>) -> GatewayError {
+    loop {
+        match gateway.next_event().await {
+            Ok(GatewayEvent::Ready { application_id }) => {
+                match client.overwrite_commands(&application_id, &slash_commands()).await {
+                    Ok(_) => log::info!("Registered Discord slash commands"),
+                    Err(err) => log::error!("Could not register Discord slash commands: {}", err),
+                }
+            }
+            Ok(GatewayEvent::Interaction(interaction)) => {
+                let Some(input) = to_input(&interaction) else {
+                    continue;
+                };
+                // Discord requires an interaction to be answered within 3 seconds,
+                // while results are sent to the channel as separate messages
+                let acknowledgement = json!({
+                    "type": CHANNEL_MESSAGE_WITH_SOURCE,
+                    "data": { "content": "⏳", "flags": EPHEMERAL_FLAG },
+                });
+                if let Err(err) = client.create_interaction_response(&interaction.id, &interaction.token, &acknowledgement).await {
+                    log::error!("Could not acknowledge Discord interaction {}: {}", interaction.id, err);
+                }
+                let input_handler = input_handler.clone();
+                tokio::spawn(async move {
+                    if let Err(err) = input_handler.handle(Box::new(input)).await {
+                        log::error!("Error when handling a Discord interaction: {}", err);
+                    }
+                });
+            }
+            Err(err) => return err,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::json;
+    use url::Url;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::core::downloads_tracker::DownloadsTracker;
+    use crate::core::input_handler::InputHandler;
+    use crate::core::prowlarr::ProwlarrClient;
+    use crate::core::torrent_meta::TorrentMeta;
+    use crate::core::traits::input::{Command, Input};
+    use crate::core::traits::sender::MockSender;
+    use crate::ext::discord::gateway::tests::{fake_transport, hello};
+    use crate::ext::discord::gateway::{Gateway, GatewayError, Interaction};
+    use crate::ext::discord::DiscordClient;
+    use crate::ext::input_handler::discord::{serve, slash_commands, to_input};
+    use crate::ext::uuid_mapper;
+
+    fn interaction(data: serde_json::Value) -> Interaction {
+        serde_json::from_value(json!({
+            "id": "int1", "token": "int-token", "type": 2, "channel_id": "100", "locale": "ru",
+            "user": { "id": "42" },
+            "data": data,
+        })).unwrap()
+    }
+
+    #[test]
+    fn interaction_to_input() {
+        let input = to_input(&interaction(json!({
+            "name": "search", "options": [{ "name": "query", "type": 3, "value": " Ubuntu " }]
+        }))).unwrap();
+
+        assert_eq!(input.get_command(), Command::Search("Ubuntu".into()));
+        assert_eq!(input.get_source(), 42);
+        assert_eq!(input.get_destination(), 100);
+        assert_eq!(input.get_locale().as_ref(), "ru");
+    }
+
+    #[test]
+    fn command_text() {
+        assert_eq!(super::command_text(&Command::Add("abc1_4".into())), Some("/add id:abc1_4".to_string()));
+        assert_eq!(super::command_text(&Command::Files("abc1".into())), Some("/files id:abc1".to_string()));
+        assert_eq!(super::command_text(&Command::Help), None);
+    }
+
+    #[test]
+    fn unknown_command_is_ignored() {
+        assert!(to_input(&interaction(json!({ "name": "unknown" }))).is_none());
+    }
+
+    #[test]
+    fn slash_command_definitions() {
+        let commands = slash_commands();
+
+        assert_eq!(commands[0]["name"], "search");
+        assert_eq!(commands[0]["options"][0]["name"], "query");
+        assert_eq!(commands[0]["options"][0]["required"], true);
+        assert_eq!(commands[3]["name"], "help");
+        assert_eq!(commands[3]["description_localizations"]["ru"], "Как пользоваться ботом");
+        assert!(commands[3].get("options").is_none());
+    }
+
+    #[tokio::test]
+    async fn registers_commands_and_acknowledges_interactions() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/applications/app1/commands"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/interactions/int1/int-token/callback"))
+            .and(body_partial_json(json!({ "type": 4 })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let client = DiscordClient::new(Url::parse(&mock_server.uri()).unwrap(), "token".to_string());
+        let mut sender = MockSender::new();
+        sender.expect_send()
+            .returning(|_, _| Box::pin(async { Ok(()) }));
+        let prowlarr = temp_env::with_vars(
+            [("PROWLARR_API_KEY", Some("key")), ("PROWLARR_BASE_URL", Some(&mock_server.uri()))],
+            ProwlarrClient::from_env);
+        let input_handler = Arc::new(InputHandler::new(
+            prowlarr,
+            uuid_mapper::create::<TorrentMeta>(),
+            Arc::new(DownloadsTracker::new()),
+            vec![],
+            Arc::new(sender)));
+        let (transport, server) = fake_transport();
+        server.push(hello());
+        server.push(json!({ "op": 0, "s": 1, "t": "READY", "d": { "application": { "id": "app1" } } }));
+        server.push(json!({ "op": 0, "s": 2, "t": "INTERACTION_CREATE", "d": {
+            "id": "int1", "token": "int-token", "type": 2, "channel_id": "100",
+            "user": { "id": "42" }, "data": { "name": "help" }
+        }}));
+        server.push(json!({ "op": 7 }));
+        let gateway = Gateway::connect(transport, "token").await.unwrap();
+
+        let result = serve(&client, gateway, &input_handler).await;
+
+        assert!(matches!(result, GatewayError::Reconnect));
+    }
+}
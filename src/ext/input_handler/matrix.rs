@@ -0,0 +1,206 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::core::input_handler::InputHandler;
+use crate::core::traits::input::{Command, Destination, Input, Locale, ReplyToMessage, Source};
+use crate::ext::input_handler;
+use crate::ext::matrix::{MatrixClient, RoomEvent, SyncResponse};
+
+const MESSAGE_EVENT_TYPE: &str = "m.room.message";
+const SYNC_RETRY_DELAY: Duration = Duration::from_secs(5);
+
+struct MatrixInput {
+    command: Command,
+    source: Source,
+    destination: Destination,
+    reply_to_message: ReplyToMessage,
+    locale: Locale,
+}
+
+impl Input for MatrixInput {
+    fn get_command(&self) -> Command {
+        self.command.clone()
+    }
+
+    fn get_source(&self) -> Source {
+        self.source
+    }
+
+    fn get_destination(&self) -> Destination {
+        self.destination
+    }
+
+    fn get_reply_to_message(&self) -> ReplyToMessage {
+        self.reply_to_message
+    }
+
+    fn get_locale(&self) -> Locale {
+        self.locale.clone()
+    }
+}
+
+/// Matrix clients handle `/` commands themselves, so bot commands start with `!` instead.
+fn parse_command(text: &str) -> Command {
+    input_handler::parse_command('!', text)
+}
+
+/// Text of a command generated by the bot, the inverse of `parse_command`.
+pub fn command_text(command: &Command) -> Option<String> {
+    input_handler::command_text('!', command)
+}
+
+fn to_input(client: &MatrixClient, bot_user_id: &str, room_id: &str, event: &RoomEvent, locale: &Locale) -> Option<MatrixInput> {
+    if event.event_type != MESSAGE_EVENT_TYPE || event.sender == bot_user_id
+        || event.content["msgtype"] != "m.text"
+        || event.content["m.relates_to"]["rel_type"] == "m.replace" {
+        return None;
+    }
+    let text = event.content["body"].as_str()?.trim();
+    Some(MatrixInput {
+        command: parse_command(text),
+        source: client.users.id(&event.sender) as Source,
+        destination: client.rooms.id(room_id),
+        reply_to_message: event.event_id.as_ref()
+            .and_then(|event_id| ReplyToMessage::try_from(client.events.id(event_id)).ok())
+            .unwrap_or_default(),
+        locale: locale.clone(),
+    })
+}
+
+pub async fn run(client: Arc<MatrixClient>,
+                 bot_user_id: String,
+                 allowed_users: Vec<Source>,
+                 locale: Locale,
+                 input_handler: Arc<InputHandler>) {
+    log::info!("Starting Matrix bot {}...", bot_user_id);
+    let mut since = match client.sync(None).await {
+        Ok(response) => {
+            // The initial sync lists every joined room, so that saved searches restored from the storage
+            // can be sent to their rooms before anything new is said there.
+            for room_id in response.rooms.join.keys() {
+                client.rooms.id(room_id);
+            }
+            join_invited_rooms(&client, &allowed_users, &response).await;
+            response.next_batch
+        }
+        Err(err) => panic!("Could not sync with the Matrix homeserver: {err}"),
+    };
+    loop {
+        let response = tokio::select! {
+            _ = tokio::signal::ctrl_c() => break,
+            response = client.sync(Some(&since)) => response,
+        };
+        match response {
+            Ok(response) => {
+                join_invited_rooms(&client, &allowed_users, &response).await;
+                for (room_id, room) in response.rooms.join.iter() {
+                    for event in room.timeline.events.iter() {
+                        if let Some(input) = to_input(&client, &bot_user_id, room_id, event, &locale) {
+                            let input_handler = input_handler.clone();
+                            tokio::spawn(async move {
+                                if let Err(err) = input_handler.handle(Box::new(input)).await {
+                                    log::error!("Error when handling a Matrix message: {}", err);
+                                }
+                            });
+                        }
+                    }
+                }
+                since = response.next_batch;
+            }
+            Err(err) => {
+                log::error!("Could not sync with the Matrix homeserver: {}", err);
+                tokio::time::sleep(SYNC_RETRY_DELAY).await;
+            }
+        }
+    }
+}
+
+async fn join_invited_rooms(client: &MatrixClient, allowed_users: &[Source], response: &SyncResponse) {
+    for (room_id, room) in response.rooms.invite.iter() {
+        let invited_by_allowed_user = room.invite_state.events.iter()
+            .filter(|event| event.event_type == "m.room.member" && event.content["membership"] == "invite")
+            .any(|event| allowed_users.is_empty()
+                || allowed_users.contains(&(client.users.id(&event.sender) as Source)));
+        if invited_by_allowed_user {
+            match client.join(room_id).await {
+                Ok(_) => log::info!("Joined Matrix room {}", room_id),
+                Err(err) => log::error!("Could not join Matrix room {}: {}", room_id, err),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use url::Url;
+
+    use crate::core::traits::input::{Command, Input};
+    use crate::ext::input_handler::matrix::{command_text, parse_command, to_input};
+    use crate::ext::matrix::{MatrixClient, RoomEvent};
+
+    fn message(sender: &str, content: serde_json::Value) -> RoomEvent {
+        RoomEvent {
+            event_type: "m.room.message".to_string(),
+            sender: sender.to_string(),
+            event_id: Some("$event".to_string()),
+            content,
+        }
+    }
+
+    #[test]
+    fn commands() {
+        assert_eq!(parse_command("Ubuntu 22.04"), Command::Search("Ubuntu 22.04".into()));
+        assert_eq!(parse_command("!d_abc1"), Command::Download("abc1".into()));
+        assert_eq!(parse_command("!m_abc1"), Command::GetLink("abc1".into()));
+        assert_eq!(parse_command("!r_abc1"), Command::ShowResult("abc1".into()));
+        assert_eq!(parse_command("!start"), Command::Start);
+        assert_eq!(parse_command("!refresh Ubuntu 22.04"), Command::Refresh("Ubuntu 22.04".into()));
+        assert_eq!(parse_command("!health"), Command::Health);
+        assert_eq!(parse_command("!watch Ubuntu 22.04"), Command::Watch("Ubuntu 22.04".into()));
+        assert_eq!(parse_command("!watches"), Command::Watches);
+        assert_eq!(parse_command("!unwatch 3"), Command::Unwatch("3".into()));
+        assert_eq!(parse_command("!series The Office"), Command::Series("The Office".into()));
+        assert_eq!(parse_command("!movie Alien"), Command::Movie("Alien".into()));
+        assert_eq!(parse_command("!a_abc1_4"), Command::Add("abc1_4".into()));
+        assert_eq!(parse_command("!files_abc1"), Command::Files("abc1".into()));
+        assert_eq!(parse_command("!watchers"), Command::Help);
+        assert_eq!(parse_command("!unknown"), Command::Help);
+    }
+
+    #[test]
+    fn command_text_is_parsed_back() {
+        for command in [Command::Download("abc1".into()), Command::GetLink("abc1".into()), Command::ShowResult("abc1".into()), Command::Add("abc1_4".into())] {
+            assert_eq!(parse_command(&command_text(&command).unwrap()), command);
+        }
+        assert_eq!(command_text(&Command::Help), None);
+    }
+
+    #[test]
+    fn text_message_to_input() {
+        let client = MatrixClient::new(Url::parse("http://localhost").unwrap(), "token".to_string());
+        let event = message("@alice:localhost", json!({ "msgtype": "m.text", "body": " Ubuntu " }));
+
+        let input = to_input(&client, "@bot:localhost", "!room:localhost", &event, &"ru".into()).unwrap();
+
+        assert_eq!(input.get_command(), Command::Search("Ubuntu".into()));
+        assert_eq!(input.get_source() as i64, client.users.id("@alice:localhost"));
+        assert_eq!(input.get_destination(), client.rooms.id("!room:localhost"));
+        assert_eq!(i64::from(input.get_reply_to_message()), client.events.id("$event"));
+        assert_eq!(input.get_locale().as_ref(), "ru");
+    }
+
+    #[test]
+    fn ignored_events() {
+        let client = MatrixClient::new(Url::parse("http://localhost").unwrap(), "token".to_string());
+        let own_message = message("@bot:localhost", json!({ "msgtype": "m.text", "body": "Searching…" }));
+        let edit = message("@alice:localhost", json!({
+            "msgtype": "m.text", "body": "* Ubuntu", "m.relates_to": { "rel_type": "m.replace" }
+        }));
+        let image = message("@alice:localhost", json!({ "msgtype": "m.image", "body": "cat.png" }));
+
+        for event in [own_message, edit, image] {
+            assert!(to_input(&client, "@bot:localhost", "!room:localhost", &event, &"en".into()).is_none());
+        }
+    }
+}
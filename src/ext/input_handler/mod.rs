@@ -1 +1,98 @@
-pub mod telegram;
\ No newline at end of file
+use crate::core::download_meta::DownloadMeta;
+use crate::core::traits::input::Command;
+use crate::core::traits::input::Command::{Add, Download, Files, GetLink, Health, Help, Movie, Refresh, Series, ShowResult, Start, Unwatch, Watch, Watches};
+
+enum CommandKind {
+    /// `name [arguments]`, shown in command menus with a localized description.
+    Named { description: &'static str },
+    /// `prefix_argument`, generated by the bot itself and not shown in menus.
+    Prefixed,
+}
+
+struct TextCommand {
+    name: &'static str,
+    kind: CommandKind,
+    to_command: fn(&str) -> Command,
+}
+
+/// Commands of the text front-ends, which only differ in the leading character: `/` or `!`.
+const COMMANDS: &[TextCommand] = &[
+    TextCommand { name: "start", kind: CommandKind::Named { description: "command_start" }, to_command: start },
+    TextCommand { name: "help", kind: CommandKind::Named { description: "command_help" }, to_command: |_| Help },
+    TextCommand { name: "refresh", kind: CommandKind::Named { description: "command_refresh" }, to_command: |query| Refresh(query.into()) },
+    TextCommand { name: "watch", kind: CommandKind::Named { description: "command_watch" }, to_command: |query| Watch(query.into()) },
+    TextCommand { name: "watches", kind: CommandKind::Named { description: "command_watches" }, to_command: |_| Watches },
+    TextCommand { name: "unwatch", kind: CommandKind::Named { description: "command_unwatch" }, to_command: |number| Unwatch(number.into()) },
+    TextCommand { name: "series", kind: CommandKind::Named { description: "command_series" }, to_command: |query| Series(query.into()) },
+    TextCommand { name: "movie", kind: CommandKind::Named { description: "command_movie" }, to_command: |query| Movie(query.into()) },
+    TextCommand { name: "health", kind: CommandKind::Named { description: "command_health" }, to_command: |_| Health },
+    TextCommand { name: "d_", kind: CommandKind::Prefixed, to_command: |uuid| Download(uuid.into()) },
+    TextCommand { name: "m_", kind: CommandKind::Prefixed, to_command: |uuid| GetLink(uuid.into()) },
+    TextCommand { name: "r_", kind: CommandKind::Prefixed, to_command: |uuid| ShowResult(uuid.into()) },
+    TextCommand { name: "a_", kind: CommandKind::Prefixed, to_command: |argument| Add(argument.into()) },
+    TextCommand { name: "files_", kind: CommandKind::Prefixed, to_command: |uuid| Files(uuid.into()) },
+];
+
+/// Prefixed commands that only show something, allowed as deep link payloads.
+const DEEP_LINK_COMMANDS: &[&str] = &["r_", "m_"];
+
+/// Text of a command generated by the bot with the front-end's leading character, the inverse of `parse_command`.
+fn command_text(leading: char, command: &Command) -> Option<String> {
+    let (prefix, argument) = match command {
+        Download(uuid) => ("d_", uuid),
+        GetLink(uuid) => ("m_", uuid),
+        ShowResult(uuid) => ("r_", uuid),
+        Add(argument) => ("a_", argument),
+        Files(uuid) => ("files_", uuid),
+        _ => return None,
+    };
+    Some(format!("{leading}{prefix}{argument}"))
+}
+
+/// Parses `text` starting with the front-end's leading character as one of the `COMMANDS`,
+/// ignoring a `@bot_name` after the command name, and any other text as `text_command`.
+fn parse_command(leading: char, text: &str) -> Command {
+    let Some(text) = text.strip_prefix(leading) else {
+        return text_command(text);
+    };
+    let (name, arguments) = text.split_once(char::is_whitespace)
+        .map(|(name, arguments)| (name, arguments.trim()))
+        .unwrap_or((text, ""));
+    let name = name.split_once('@')
+        .map(|(name, _bot_name)| name)
+        .unwrap_or(name);
+    COMMANDS.iter()
+        .find_map(|command| match command.kind {
+            CommandKind::Named { .. } if command.name == name => Some((command.to_command)(arguments)),
+            CommandKind::Prefixed => name.strip_prefix(command.name)
+                .map(|argument| (command.to_command)(argument)),
+            _ => None,
+        })
+        .unwrap_or(Help)
+}
+
+/// Handles `start` as well as Telegram deep links `t.me/<bot>?start=<payload>`, where the payload is
+/// one of the `DEEP_LINK_COMMANDS` without the leading slash. Other commands are ignored,
+/// so that a shared link can't start a download or change the library with a single tap.
+fn start(payload: &str) -> Command {
+    COMMANDS.iter()
+        .filter(|command| DEEP_LINK_COMMANDS.contains(&command.name))
+        .find_map(|command| payload.strip_prefix(command.name)
+            .map(|argument| (command.to_command)(argument)))
+        .unwrap_or(Start)
+}
+
+/// Text without a command is a search, unless it's a magnet link to download.
+fn text_command(text: &str) -> Command {
+    if text.starts_with("magnet:?") {
+        Command::Submit(DownloadMeta::MagnetLink(text.to_string()))
+    } else {
+        Command::Search(text.into())
+    }
+}
+
+pub mod telegram;
+pub mod matrix;
+pub mod cli;
+#[cfg(feature = "discord")]
+pub mod discord;
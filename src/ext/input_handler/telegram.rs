@@ -1,63 +1,95 @@
 use std::net::SocketAddr;
 use std::sync::Arc;
 
+use bytes::Bytes;
 use teloxide::dispatching::{Dispatcher, UpdateFilterExt};
-use teloxide::prelude::{LoggingErrorHandler, Message, Update};
+use teloxide::net::Download as _;
+use teloxide::payloads::SetMyCommandsSetters;
+use teloxide::prelude::{LoggingErrorHandler, Message, Requester, Update};
+use teloxide::types::{BotCommand, Document};
 use teloxide::update_listeners::webhooks;
 use teloxide::{dptree, Bot};
 
+use crate::core::download_meta::DownloadMeta;
 use crate::core::input_handler::InputHandler;
-use crate::core::traits::input::Command::{Download, GetLink, Help, Search};
+use crate::core::traits::input::Command::{Help, Submit, UploadFailed};
 use crate::core::traits::input::{Command, Destination, Input, Locale, ReplyToMessage, Source};
 use crate::core::util;
 use crate::core::HandlingResult;
+use crate::ext::input_handler;
+use crate::ext::input_handler::{CommandKind, COMMANDS};
 
-struct TelegramInput(Message);
+const TORRENT_MIME_TYPE: &str = "application/x-bittorrent";
+
+/// Text of a command generated by the bot, the inverse of `parse_command`.
+pub fn command_text(command: &Command) -> Option<String> {
+    input_handler::command_text('/', command)
+}
+
+fn parse_command(text: &str) -> Command {
+    input_handler::parse_command('/', text)
+}
+
+fn menu_commands(locale: &str) -> Vec<BotCommand> {
+    COMMANDS.iter()
+        .filter_map(|command| match command.kind {
+            CommandKind::Named { description } =>
+                Some(BotCommand::new(command.name, t!(description, locale = locale))),
+            CommandKind::Prefixed => None,
+        })
+        .collect()
+}
+
+async fn register_commands(bot: &Bot) {
+    if let Err(err) = bot.set_my_commands(menu_commands("en")).await {
+        log::error!("Could not register bot commands: {}", err);
+    }
+    for locale in rust_i18n::available_locales!() {
+        if let Err(err) = bot.set_my_commands(menu_commands(&locale)).language_code(locale.clone()).await {
+            log::error!("Could not register bot commands for locale {}: {}", locale, err);
+        }
+    }
+}
+
+struct TelegramInput {
+    message: Message,
+    command: Command,
+}
 
 impl Input for TelegramInput {
     fn get_command(&self) -> Command {
-        if let Some(msg_text) = self.0.text() {
-            return if !msg_text.starts_with('/') {
-                Search(msg_text.into())
-            } else if let Some(item_uuid) = msg_text.strip_prefix("/d_") {
-                Download(item_uuid.into())
-            } else if let Some(item_uuid) = msg_text.strip_prefix("/m_") {
-                GetLink(item_uuid.into())
-            } else {
-                Help
-            }
-        }
-        Help
+        self.command.clone()
     }
 
     fn get_source(&self) -> Source {
-        self.0.from.as_ref().map(|from| from.id.0).unwrap_or_default()
+        self.message.from.as_ref().map(|from| from.id.0).unwrap_or_default()
     }
 
     fn get_destination(&self) -> Destination {
-        self.0.chat.id.0
+        self.message.chat.id.0
     }
 
     fn get_reply_to_message(&self) -> ReplyToMessage {
-        self.0.id.0
+        self.message.id.0
     }
 
     fn get_locale(&self) -> Locale {
-        self.0.from.as_ref()
+        self.message.from.as_ref()
             .and_then(|u| u.language_code.clone())
             .map(|s| s.as_str().into())
             .unwrap_or_else(|| "en".into())
     }
 }
 
-pub async fn run(bot: Bot, input_handler: InputHandler) {
+pub async fn run(bot: Bot, input_handler: Arc<InputHandler>) {
     log::info!("Starting torrents bot...");
+    register_commands(&bot).await;
 
     let handler = dptree::entry()
         .branch(Update::filter_message().endpoint(handle));
 
     let mut dispatcher = Dispatcher::builder(bot.clone(), handler)
-        .dependencies(dptree::deps![Arc::new(input_handler)])
+        .dependencies(dptree::deps![input_handler])
         .enable_ctrlc_handler()
         .build();
     if let (Ok(port), Ok(url)) = (std::env::var("WEBHOOK_PORT"), std::env::var("WEBHOOK_URL")) {
@@ -74,6 +106,121 @@ pub async fn run(bot: Bot, input_handler: InputHandler) {
     }
 }
 
-async fn handle(input_handler: Arc<InputHandler>, msg: Message) -> HandlingResult {
-    input_handler.handle(Box::new(TelegramInput(msg))).await
+async fn handle(bot: Bot, input_handler: Arc<InputHandler>, msg: Message) -> HandlingResult {
+    let command = match msg.document() {
+        Some(document) if is_torrent_file(document) => {
+            // documents of strangers aren't even fetched
+            let source = msg.from.as_ref().map(|from| from.id.0).unwrap_or_default();
+            if !input_handler.is_allowed(source) {
+                return Ok(());
+            }
+            match download(&bot, document).await {
+                Ok(content) => Submit(DownloadMeta::TorrentFile(content)),
+                Err(err) => {
+                    log::error!("Could not download Telegram document {}: {}", document.file.id, err);
+                    UploadFailed
+                }
+            }
+        }
+        _ => msg.text()
+            .map(parse_command)
+            .unwrap_or(Help),
+    };
+    input_handler.handle(Box::new(TelegramInput { message: msg, command })).await
+}
+
+fn is_torrent_file(document: &Document) -> bool {
+    document.mime_type.as_ref().is_some_and(|mime_type| mime_type.essence_str() == TORRENT_MIME_TYPE)
+        || document.file_name.as_ref().is_some_and(|file_name| file_name.to_lowercase().ends_with(".torrent"))
+}
+
+/// Contents of a document sent to the bot.
+async fn download(bot: &Bot, document: &Document) -> Result<Bytes, String> {
+    let file = bot.get_file(document.file.id.clone()).await.map_err(|err| err.to_string())?;
+    let mut content = Vec::new();
+    bot.download_file(&file.path, &mut content).await.map_err(|err| err.to_string())?;
+    Ok(content.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::core::download_meta::DownloadMeta;
+    use crate::core::traits::input::Command;
+    use crate::ext::input_handler::telegram::{command_text, menu_commands, parse_command};
+
+    fn assert_command(text: &str, expected: Command) {
+        assert_eq!(parse_command(text), expected, "unexpected command for \"{text}\"");
+    }
+
+    #[test]
+    fn plain_text_is_search() {
+        assert_command("Ubuntu 22.04", Command::Search("Ubuntu 22.04".into()));
+    }
+
+    #[test]
+    fn magnet_link_is_downloaded() {
+        assert_command("magnet:?xt=urn:btih:c811b41641a09d192b8ed81b14064fff55d85ce3",
+                       Command::Submit(DownloadMeta::MagnetLink("magnet:?xt=urn:btih:c811b41641a09d192b8ed81b14064fff55d85ce3".to_string())));
+    }
+
+    #[test]
+    fn prefixed_commands() {
+        assert_command("/d_abc1", Command::Download("abc1".into()));
+        assert_command("/m_abc1", Command::GetLink("abc1".into()));
+        assert_command("/d_abc1@torrents_bot", Command::Download("abc1".into()));
+        assert_command("/a_abc1_4", Command::Add("abc1_4".into()));
+        assert_command("/files_abc1", Command::Files("abc1".into()));
+    }
+
+    #[test]
+    fn command_text_is_parsed_back() {
+        for command in [Command::Download("abc1".into()), Command::ShowResult("abc1".into()), Command::Add("abc1_4".into())] {
+            assert_command(&command_text(&command).unwrap(), command);
+        }
+        assert_eq!(command_text(&Command::Help), None);
+    }
+
+    #[test]
+    fn menu_commands_are_matched_by_full_name() {
+        assert_command("/help", Command::Help);
+        assert_command("/help@torrents_bot", Command::Help);
+        assert_command("/start", Command::Start);
+        assert_command("/startle", Command::Help);
+        assert_command("/unknown", Command::Help);
+    }
+
+    #[test]
+    fn commands_with_arguments() {
+        assert_command("/watch Ubuntu 22.04", Command::Watch("Ubuntu 22.04".into()));
+        assert_command("/watch@torrents_bot  Ubuntu ", Command::Watch("Ubuntu".into()));
+        assert_command("/watch", Command::Watch("".into()));
+        assert_command("/watches", Command::Watches);
+        assert_command("/unwatch 3", Command::Unwatch("3".into()));
+        assert_command("/series The Office", Command::Series("The Office".into()));
+        assert_command("/movie Alien", Command::Movie("Alien".into()));
+        assert_command("/refresh Ubuntu", Command::Refresh("Ubuntu".into()));
+        assert_command("/health", Command::Health);
+    }
+
+    #[test]
+    fn start_with_deep_link_payload() {
+        assert_command("/start m_abc1", Command::GetLink("abc1".into()));
+        assert_command("/start r_abc1", Command::ShowResult("abc1".into()));
+        assert_command("/start unknown", Command::Start);
+    }
+
+    #[test]
+    fn start_with_changing_payload_is_rejected() {
+        assert_command("/start d_abc1", Command::Start);
+        assert_command("/start a_abc1_4", Command::Start);
+        assert_command("/start files_abc1", Command::Start);
+    }
+
+    #[test]
+    fn localized_menu() {
+        let commands = menu_commands("ru");
+
+        assert_eq!(commands.iter().map(|c| c.command.as_str()).collect::<Vec<_>>(), vec!["start", "help", "refresh", "watch", "watches", "unwatch", "series", "movie", "health"]);
+        assert_eq!(commands[1].description, "Как пользоваться ботом");
+    }
 }
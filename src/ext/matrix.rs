@@ -0,0 +1,394 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bytes::Bytes;
+use dashmap::DashMap;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use url::Url;
+
+const MATRIX_HOMESERVER_URL_ENV: &str = "MATRIX_HOMESERVER_URL";
+const MATRIX_ACCESS_TOKEN_ENV: &str = "MATRIX_ACCESS_TOKEN";
+const SYNC_TIMEOUT_MILLIS: u64 = 30_000;
+const TYPING_TIMEOUT_MILLIS: u64 = 30_000;
+const MAX_REGISTRY_SIZE: i64 = 10_000;
+
+/// Client for the subset of the Matrix client-server API needed by the bot.
+pub struct MatrixClient {
+    homeserver_url: Url,
+    access_token: String,
+    client: Client,
+    transaction_sequence: AtomicU64,
+    pub rooms: IdRegistry,
+    pub users: IdRegistry,
+    pub events: IdRegistry,
+}
+
+/// The core works with numeric chat, user and message ids, while Matrix identifiers are strings,
+/// so every identifier seen by the bot gets a number assigned.
+pub struct IdRegistry {
+    ids: DashMap<String, i64>,
+    names: DashMap<i64, String>,
+    /// Next sequential id, or `None` if ids are derived from the identifiers.
+    sequence: Option<AtomicI64>,
+}
+
+#[derive(Deserialize)]
+pub struct SyncResponse {
+    pub next_batch: String,
+    #[serde(default)]
+    pub rooms: SyncRooms,
+}
+
+#[derive(Deserialize, Default)]
+pub struct SyncRooms {
+    #[serde(default)]
+    pub join: HashMap<String, JoinedRoom>,
+    #[serde(default)]
+    pub invite: HashMap<String, InvitedRoom>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct JoinedRoom {
+    #[serde(default)]
+    pub timeline: Timeline,
+}
+
+#[derive(Deserialize, Default)]
+pub struct Timeline {
+    #[serde(default)]
+    pub events: Vec<RoomEvent>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct InvitedRoom {
+    #[serde(default)]
+    pub invite_state: InviteState,
+}
+
+#[derive(Deserialize, Default)]
+pub struct InviteState {
+    #[serde(default)]
+    pub events: Vec<RoomEvent>,
+}
+
+#[derive(Deserialize)]
+pub struct RoomEvent {
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub sender: String,
+    pub event_id: Option<String>,
+    #[serde(default)]
+    pub content: Value,
+}
+
+#[derive(Deserialize)]
+struct WhoAmIResponse {
+    user_id: String,
+}
+
+#[derive(Deserialize)]
+struct SendEventResponse {
+    event_id: String,
+}
+
+#[derive(Deserialize)]
+struct UploadResponse {
+    content_uri: String,
+}
+
+impl MatrixClient {
+    pub fn from_env() -> MatrixClient {
+        let url_string = std::env::var(MATRIX_HOMESERVER_URL_ENV)
+            .unwrap_or_else(|_| panic!("Cannot get the {MATRIX_HOMESERVER_URL_ENV} env variable"));
+        MatrixClient::new(
+            Url::parse(&url_string)
+                .unwrap_or_else(|err| panic!("Could not parse {}: {}: \"{}\"", MATRIX_HOMESERVER_URL_ENV, err, url_string)),
+            std::env::var(MATRIX_ACCESS_TOKEN_ENV)
+                .unwrap_or_else(|_| panic!("Cannot get the {MATRIX_ACCESS_TOKEN_ENV} env variable")))
+    }
+
+    pub fn new(homeserver_url: Url, access_token: String) -> MatrixClient {
+        MatrixClient {
+            homeserver_url,
+            access_token,
+            client: Client::new(),
+            transaction_sequence: AtomicU64::new(0),
+            rooms: IdRegistry::hashed(),
+            users: IdRegistry::hashed(),
+            events: IdRegistry::sequential(),
+        }
+    }
+
+    pub async fn whoami(&self) -> reqwest::Result<String> {
+        Ok(self.authorized(self.client.get(self.url(&["client", "v3", "account", "whoami"])))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<WhoAmIResponse>()
+            .await?
+            .user_id)
+    }
+
+    /// Long-polls the homeserver for new events; `since` is `None` for the initial sync.
+    pub async fn sync(&self, since: Option<&str>) -> reqwest::Result<SyncResponse> {
+        let mut url = self.url(&["client", "v3", "sync"]);
+        match since {
+            Some(since) => url.query_pairs_mut()
+                .append_pair("since", since)
+                .append_pair("timeout", &SYNC_TIMEOUT_MILLIS.to_string()),
+            None => url.query_pairs_mut()
+                .append_pair("timeout", "0"),
+        };
+        self.authorized(self.client.get(url))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<SyncResponse>()
+            .await
+    }
+
+    pub async fn join(&self, room_id: &str) -> reqwest::Result<()> {
+        self.authorized(self.client.post(self.url(&["client", "v3", "join", room_id])))
+            .json(&json!({}))
+            .send()
+            .await?
+            .error_for_status()
+            .map(|_| {})
+    }
+
+    pub async fn send_message(&self, room_id: &str, content: &Value) -> reqwest::Result<String> {
+        let transaction_id = format!("{}-{}",
+                                     SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis(),
+                                     self.transaction_sequence.fetch_add(1, Ordering::SeqCst));
+        Ok(self.authorized(self.client.put(self.url(&["client", "v3", "rooms", room_id, "send", "m.room.message", &transaction_id])))
+            .json(content)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<SendEventResponse>()
+            .await?
+            .event_id)
+    }
+
+    pub async fn typing(&self, room_id: &str, user_id: &str) -> reqwest::Result<()> {
+        self.authorized(self.client.put(self.url(&["client", "v3", "rooms", room_id, "typing", user_id])))
+            .json(&json!({ "typing": true, "timeout": TYPING_TIMEOUT_MILLIS }))
+            .send()
+            .await?
+            .error_for_status()
+            .map(|_| {})
+    }
+
+    /// Uploads a file to the media repository and returns its `mxc://` URI.
+    pub async fn upload(&self, filename: &str, content_type: &str, file: Bytes) -> reqwest::Result<String> {
+        let mut url = self.url(&["media", "v3", "upload"]);
+        url.query_pairs_mut().append_pair("filename", filename);
+        Ok(self.authorized(self.client.post(url))
+            .header(CONTENT_TYPE, content_type)
+            .body(file)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<UploadResponse>()
+            .await?
+            .content_uri)
+    }
+
+    fn url(&self, path_segments: &[&str]) -> Url {
+        let mut url = self.homeserver_url.clone();
+        url.path_segments_mut()
+            .expect("homeserver URL must be a base URL")
+            .clear()
+            .push("_matrix")
+            .extend(path_segments);
+        url
+    }
+
+    fn authorized(&self, request: RequestBuilder) -> RequestBuilder {
+        request.bearer_auth(&self.access_token)
+    }
+}
+
+impl IdRegistry {
+    /// Ids derived from the identifiers, which stay the same after restarts, so that ids of configured users and rooms
+    /// and ids saved with watches keep matching. Nothing is forgotten, as there are only so many users and rooms.
+    fn hashed() -> IdRegistry {
+        IdRegistry { ids: DashMap::new(), names: DashMap::new(), sequence: None }
+    }
+
+    /// Sequential ids, of which only the last `MAX_REGISTRY_SIZE` are remembered.
+    fn sequential() -> IdRegistry {
+        IdRegistry { ids: DashMap::new(), names: DashMap::new(), sequence: Some(AtomicI64::new(1)) }
+    }
+
+    pub fn id(&self, name: &str) -> i64 {
+        let mut created = false;
+        let id = *self.ids.entry(name.to_string())
+            .or_insert_with(|| {
+                created = true;
+                match &self.sequence {
+                    Some(sequence) => sequence.fetch_add(1, Ordering::SeqCst),
+                    None => stable_hash(name),
+                }
+            });
+        if created {
+            self.names.insert(id, name.to_string());
+            if self.sequence.is_some() && id > MAX_REGISTRY_SIZE {
+                if let Some((_, evicted)) = self.names.remove(&(id - MAX_REGISTRY_SIZE)) {
+                    self.ids.remove(&evicted);
+                }
+            }
+        }
+        id
+    }
+
+    pub fn name(&self, id: i64) -> Option<String> {
+        self.names.get(&id).map(|e| e.value().clone())
+    }
+}
+
+/// Non-negative 64-bit FNV-1a hash, which unlike the std hashers is the same in every build.
+fn stable_hash(name: &str) -> i64 {
+    let hash = name.bytes()
+        .fold(0xcbf29ce484222325_u64, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3));
+    (hash & i64::MAX as u64) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use serde_json::json;
+    use url::Url;
+    use wiremock::matchers::{body_json, header, method, path, path_regex, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::ext::matrix::{MatrixClient, MAX_REGISTRY_SIZE};
+
+    fn client(mock_server: &MockServer) -> MatrixClient {
+        MatrixClient::new(Url::parse(&mock_server.uri()).unwrap(), "token".to_string())
+    }
+
+    #[tokio::test]
+    async fn sync() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/_matrix/client/v3/sync"))
+            .and(header("Authorization", "Bearer token"))
+            .and(query_param("since", "s1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "next_batch": "s2",
+                "rooms": {
+                    "join": {
+                        "!room:localhost": { "timeline": { "events": [{
+                            "type": "m.room.message",
+                            "sender": "@alice:localhost",
+                            "event_id": "$event1",
+                            "content": { "msgtype": "m.text", "body": "Ubuntu" }
+                        }]}}
+                    },
+                    "invite": { "!other:localhost": {} }
+                }
+            })))
+            .mount(&mock_server)
+            .await;
+
+        let response = client(&mock_server).sync(Some("s1")).await.unwrap();
+
+        assert_eq!(response.next_batch, "s2");
+        let events = &response.rooms.join["!room:localhost"].timeline.events;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].sender, "@alice:localhost");
+        assert_eq!(events[0].content["body"], "Ubuntu");
+        assert!(response.rooms.invite.contains_key("!other:localhost"));
+    }
+
+    #[tokio::test]
+    async fn send_message() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path_regex(r"^/_matrix/client/v3/rooms/!room:localhost/send/m\.room\.message/.+$"))
+            .and(body_json(json!({ "msgtype": "m.text", "body": "hi" })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "event_id": "$sent" })))
+            .mount(&mock_server)
+            .await;
+
+        let event_id = client(&mock_server)
+            .send_message("!room:localhost", &json!({ "msgtype": "m.text", "body": "hi" }))
+            .await
+            .unwrap();
+
+        assert_eq!(event_id, "$sent");
+    }
+
+    #[tokio::test]
+    async fn upload() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/_matrix/media/v3/upload"))
+            .and(query_param("filename", "file.torrent"))
+            .and(header("Content-Type", "application/x-bittorrent"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "content_uri": "mxc://localhost/abc" })))
+            .mount(&mock_server)
+            .await;
+
+        let uri = client(&mock_server)
+            .upload("file.torrent", "application/x-bittorrent", Bytes::from("contents"))
+            .await
+            .unwrap();
+
+        assert_eq!(uri, "mxc://localhost/abc");
+    }
+
+    #[test]
+    fn registry_assigns_stable_ids() {
+        let client = MatrixClient::new(Url::parse("http://localhost").unwrap(), "token".to_string());
+
+        let room1 = client.rooms.id("!room1:localhost");
+        let room2 = client.rooms.id("!room2:localhost");
+
+        assert_ne!(room1, room2);
+        assert_eq!(client.rooms.id("!room1:localhost"), room1);
+        assert_eq!(client.rooms.name(room2), Some("!room2:localhost".to_string()));
+        assert_eq!(client.rooms.name(1000), None);
+    }
+
+    #[test]
+    fn user_and_room_ids_survive_restarts() {
+        let client = MatrixClient::new(Url::parse("http://localhost").unwrap(), "token".to_string());
+        let room = client.rooms.id("!room:localhost");
+        let user = client.users.id("@alice:localhost");
+
+        let restarted = MatrixClient::new(Url::parse("http://localhost").unwrap(), "token".to_string());
+        restarted.rooms.id("!other:localhost");
+        restarted.users.id("@bob:localhost");
+
+        assert_eq!(restarted.rooms.id("!room:localhost"), room);
+        assert_eq!(restarted.users.id("@alice:localhost"), user);
+        assert!(room >= 0 && user >= 0);
+    }
+
+    #[test]
+    fn users_are_never_forgotten() {
+        let client = MatrixClient::new(Url::parse("http://localhost").unwrap(), "token".to_string());
+        let alice = client.users.id("@alice:localhost");
+
+        (0..=MAX_REGISTRY_SIZE).for_each(|user| { client.users.id(&format!("@user{user}:localhost")); });
+
+        assert_eq!(client.users.name(alice), Some("@alice:localhost".to_string()));
+        assert_eq!(client.users.id("@alice:localhost"), alice);
+    }
+
+    #[test]
+    fn old_events_are_forgotten() {
+        let client = MatrixClient::new(Url::parse("http://localhost").unwrap(), "token".to_string());
+        let first = client.events.id("$first");
+
+        (0..MAX_REGISTRY_SIZE).for_each(|event| { client.events.id(&format!("${event}")); });
+
+        assert_eq!(client.events.name(first), None);
+    }
+}
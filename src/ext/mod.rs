@@ -1,5 +1,13 @@
+pub mod api;
 pub mod completion;
 pub mod uuid_mapper;
 pub mod sender;
 pub mod input_handler;
 pub mod search_result_serializer;
+pub mod matrix;
+pub mod notifier;
+pub mod download_client;
+pub mod search_cache;
+pub mod watch_storage;
+#[cfg(feature = "discord")]
+pub mod discord;
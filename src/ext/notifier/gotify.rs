@@ -0,0 +1,66 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+use url::Url;
+
+use crate::core::traits::notifier::Notifier;
+use crate::core::{HandlingError, HandlingResult};
+
+/// Pushes a message to a [Gotify](https://gotify.net) server, configured as the server url
+/// with an application token, e.g. `https://gotify.example.org/?token=<token>`.
+pub struct GotifyNotifier {
+    client: Client,
+    message_url: Url,
+}
+
+impl GotifyNotifier {
+    pub fn new(client: Client, server_url: Url) -> GotifyNotifier {
+        let mut message_url = server_url;
+        message_url.path_segments_mut()
+            .expect("Gotify url must be a base URL")
+            .pop_if_empty()
+            .push("message");
+        GotifyNotifier { client, message_url }
+    }
+}
+
+#[async_trait]
+impl Notifier for GotifyNotifier {
+    async fn notify(&self, title: &str, message: &str) -> HandlingResult {
+        self.client.post(self.message_url.clone())
+            .json(&json!({ "title": title, "message": message }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map(|_| {})
+            .map_err(|err| HandlingError::SendError(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Client;
+    use serde_json::json;
+    use url::Url;
+    use wiremock::matchers::{body_json, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::core::traits::notifier::Notifier;
+    use crate::ext::notifier::gotify::GotifyNotifier;
+
+    #[tokio::test]
+    async fn pushes_message() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/message"))
+            .and(query_param("token", "abc"))
+            .and(body_json(json!({ "title": "Download complete", "message": "Downloaded \"Ubuntu\"" })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let notifier = GotifyNotifier::new(Client::new(), Url::parse(&format!("{}/?token=abc", mock_server.uri())).unwrap());
+
+        notifier.notify("Download complete", "Downloaded \"Ubuntu\"").await.unwrap();
+    }
+}
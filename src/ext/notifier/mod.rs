@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use reqwest::Client;
+use url::Url;
+
+use crate::core::traits::input::Source;
+use crate::core::traits::notifier::{NotificationChannels, Notifier};
+use crate::ext::notifier::gotify::GotifyNotifier;
+use crate::ext::notifier::ntfy::NtfyNotifier;
+use crate::ext::notifier::webhook::WebhookNotifier;
+
+pub mod gotify;
+pub mod ntfy;
+pub mod webhook;
+#[cfg(feature = "email")]
+pub mod email;
+
+const NOTIFICATION_CHANNELS_ENV: &str = "NOTIFICATION_CHANNELS";
+
+/// Reads a comma separated list of `<user id>=<channel url>` entries, where the channel is selected by the url scheme:
+/// `mailto:` for email, `ntfy+https://` and `gotify+https://` for push notifications
+/// and a plain `https://` for a generic JSON webhook. A user can have several channels.
+pub fn from_env(user_id: impl Fn(&str) -> Option<Source>) -> NotificationChannels {
+    let client = Client::new();
+    let mut channels = NotificationChannels::new();
+    for entry in std::env::var(NOTIFICATION_CHANNELS_ENV).unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty()) {
+        let (user, url) = entry.split_once('=')
+            .unwrap_or_else(|| panic!("{NOTIFICATION_CHANNELS_ENV} entries must look like <user id>=<channel url>. \
+                Value \"{entry}\" is unexpected"));
+        let source = user_id(user.trim())
+            .unwrap_or_else(|| panic!("Could not parse {NOTIFICATION_CHANNELS_ENV}: unexpected user id \"{user}\""));
+        channels.entry(source)
+            .or_default()
+            .push(channel(&client, url.trim()));
+    }
+    channels
+}
+
+fn channel(client: &Client, url: &str) -> Arc<dyn Notifier> {
+    let parse = |url: &str| Url::parse(url)
+        .unwrap_or_else(|err| panic!("Could not parse {}: {}: \"{}\"", NOTIFICATION_CHANNELS_ENV, err, url));
+    if let Some(url) = url.strip_prefix("ntfy+") {
+        Arc::new(NtfyNotifier::new(client.clone(), parse(url)))
+    } else if let Some(url) = url.strip_prefix("gotify+") {
+        Arc::new(GotifyNotifier::new(client.clone(), parse(url)))
+    } else if url.starts_with("http://") || url.starts_with("https://") {
+        Arc::new(WebhookNotifier::new(client.clone(), parse(url)))
+    } else if let Some(address) = url.strip_prefix("mailto:") {
+        email(address)
+    } else {
+        panic!("{NOTIFICATION_CHANNELS_ENV} channel \"{url}\" is unexpected")
+    }
+}
+
+#[cfg(feature = "email")]
+fn email(address: &str) -> Arc<dyn Notifier> {
+    Arc::new(email::EmailNotifier::from_env(address))
+}
+
+#[cfg(not(feature = "email"))]
+fn email(_address: &str) -> Arc<dyn Notifier> {
+    panic!("Email notifications aren't supported by this build, it must be built with the email feature")
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ext::notifier::{from_env, NOTIFICATION_CHANNELS_ENV};
+
+    #[test]
+    fn channels_by_user() {
+        temp_env::with_var(NOTIFICATION_CHANNELS_ENV,
+                           Some("1000=ntfy+https://ntfy.sh/topic, 1000=https://localhost/hook,2000=gotify+https://localhost/?token=abc"),
+                           || {
+                               let channels = from_env(|user| user.parse().ok());
+
+                               assert_eq!(channels.len(), 2);
+                               assert_eq!(channels[&1000].len(), 2);
+                               assert_eq!(channels[&2000].len(), 1);
+                           });
+    }
+
+    #[test]
+    fn no_channels_if_var_is_not_set() {
+        temp_env::with_var_unset(NOTIFICATION_CHANNELS_ENV, || {
+            assert!(from_env(|user| user.parse().ok()).is_empty());
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "NOTIFICATION_CHANNELS channel \"xmpp:alice@localhost\" is unexpected")]
+    fn unknown_channel() {
+        temp_env::with_var(NOTIFICATION_CHANNELS_ENV, Some("1000=xmpp:alice@localhost"), || {
+            from_env(|user| user.parse().ok());
+        });
+    }
+}
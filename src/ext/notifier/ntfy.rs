@@ -0,0 +1,70 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+use url::Url;
+
+use crate::core::traits::notifier::Notifier;
+use crate::core::{HandlingError, HandlingResult};
+
+/// Publishes to an [ntfy](https://ntfy.sh) topic, configured as the topic url, e.g. `https://ntfy.sh/my-topic`.
+pub struct NtfyNotifier {
+    client: Client,
+    server_url: Url,
+    topic: String,
+}
+
+impl NtfyNotifier {
+    pub fn new(client: Client, topic_url: Url) -> NtfyNotifier {
+        let topic = topic_url.path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .filter(|topic| !topic.is_empty())
+            .unwrap_or_else(|| panic!("ntfy url must end with a topic: \"{topic_url}\""))
+            .to_string();
+        let mut server_url = topic_url;
+        server_url.path_segments_mut()
+            .expect("ntfy url must be a base URL")
+            .pop();
+        NtfyNotifier { client, server_url, topic }
+    }
+}
+
+#[async_trait]
+impl Notifier for NtfyNotifier {
+    /// Uses JSON publishing, as titles in headers would have to be ASCII.
+    async fn notify(&self, title: &str, message: &str) -> HandlingResult {
+        self.client.post(self.server_url.clone())
+            .json(&json!({ "topic": self.topic, "title": title, "message": message }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map(|_| {})
+            .map_err(|err| HandlingError::SendError(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Client;
+    use serde_json::json;
+    use url::Url;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::core::traits::notifier::Notifier;
+    use crate::ext::notifier::ntfy::NtfyNotifier;
+
+    #[tokio::test]
+    async fn publishes_to_topic() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/ntfy"))
+            .and(body_json(json!({ "topic": "downloads", "title": "Download complete", "message": "Downloaded \"Ubuntu\"" })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let notifier = NtfyNotifier::new(Client::new(), Url::parse(&format!("{}/ntfy/downloads", mock_server.uri())).unwrap());
+
+        notifier.notify("Download complete", "Downloaded \"Ubuntu\"").await.unwrap();
+    }
+}
@@ -0,0 +1,71 @@
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+use url::Url;
+
+use crate::core::traits::notifier::Notifier;
+use crate::core::{HandlingError, HandlingResult};
+
+/// Posts `{"title": ..., "message": ...}` JSON to an arbitrary url.
+pub struct WebhookNotifier {
+    client: Client,
+    url: Url,
+}
+
+impl WebhookNotifier {
+    pub fn new(client: Client, url: Url) -> WebhookNotifier {
+        WebhookNotifier { client, url }
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn notify(&self, title: &str, message: &str) -> HandlingResult {
+        self.client.post(self.url.clone())
+            .json(&json!({ "title": title, "message": message }))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map(|_| {})
+            .map_err(|err| HandlingError::SendError(err.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::Client;
+    use serde_json::json;
+    use url::Url;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::core::traits::notifier::Notifier;
+    use crate::ext::notifier::webhook::WebhookNotifier;
+
+    #[tokio::test]
+    async fn posts_json() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/hook"))
+            .and(body_json(json!({ "title": "Download complete", "message": "Downloaded \"Ubuntu\"" })))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let notifier = WebhookNotifier::new(Client::new(), Url::parse(&format!("{}/hook", mock_server.uri())).unwrap());
+
+        notifier.notify("Download complete", "Downloaded \"Ubuntu\"").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn error_status() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&mock_server)
+            .await;
+        let notifier = WebhookNotifier::new(Client::new(), Url::parse(&mock_server.uri()).unwrap());
+
+        assert!(notifier.notify("title", "message").await.is_err());
+    }
+}
@@ -0,0 +1,80 @@
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use dashmap::DashMap;
+
+use crate::core::prowlarr::SearchResult;
+use crate::core::traits::search_cache::SearchCache;
+
+pub struct InMemorySearchCache {
+    map: DashMap<String, (Instant, Vec<SearchResult>)>,
+}
+
+impl InMemorySearchCache {
+    pub fn new() -> InMemorySearchCache {
+        InMemorySearchCache { map: DashMap::new() }
+    }
+}
+
+#[async_trait]
+impl SearchCache for InMemorySearchCache {
+    async fn get(&self, key: &str) -> Result<Option<Vec<SearchResult>>, String> {
+        Ok(self.map.get(key)
+            .filter(|entry| entry.0 > Instant::now())
+            .map(|entry| entry.1.clone()))
+    }
+
+    /// Expired results are dropped here, so searches that aren't repeated don't pile up.
+    async fn put(&self, key: &str, results: &[SearchResult], ttl: Duration) -> Result<(), String> {
+        let now = Instant::now();
+        self.map.retain(|_, (expires_at, _)| *expires_at > now);
+        self.map.insert(key.to_string(), (now + ttl, results.to_vec()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::DateTime;
+
+    use crate::core::prowlarr::{Protocol, SearchResult};
+    use crate::core::traits::search_cache::SearchCache;
+    use crate::ext::search_cache::in_memory::InMemorySearchCache;
+
+    fn search_result(guid: &str) -> SearchResult {
+        SearchResult {
+            guid: guid.to_string(),
+            indexer_id: 1,
+            indexer: None,
+            title: "Ubuntu".to_string(),
+            size: 1234567,
+            publish_date: DateTime::from_timestamp(1431648000, 0).unwrap(),
+            download_url: None,
+            magnet_url: None,
+            info_url: "http://localhost/ubuntu".to_string(),
+            seeders: 1,
+            leechers: 0,
+            grabs: None,
+            categories: vec![],
+            indexer_flags: vec![],
+            imdb_id: 0,
+            info_hash: None,
+            protocol: Protocol::Torrent,
+            other_indexers: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn results_expire() {
+        let cache = InMemorySearchCache::new();
+        cache.put("ubuntu", &[search_result("1")], Duration::from_secs(60)).await.unwrap();
+        cache.put("debian", &[search_result("2")], Duration::ZERO).await.unwrap();
+
+        let cached = cache.get("ubuntu").await.unwrap().unwrap();
+        assert_eq!(cached.iter().map(|result| result.guid.as_str()).collect::<Vec<_>>(), vec!["1"]);
+        assert!(cache.get("debian").await.unwrap().is_none());
+        assert!(cache.get("arch").await.unwrap().is_none());
+    }
+}
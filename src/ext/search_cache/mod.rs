@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use crate::core::search_cache::SearchResultsCache;
+use crate::ext::search_cache::in_memory::InMemorySearchCache;
+#[cfg(feature = "redis-storage")]
+use crate::ext::search_cache::redis::RedisSearchCache;
+#[cfg(feature = "redis-storage")]
+use crate::ext::uuid_mapper::REDIS_URL_ENV;
+
+mod in_memory;
+#[cfg(feature = "redis-storage")]
+mod redis;
+
+const SEARCH_CACHE_TTL_SECONDS_ENV: &str = "SEARCH_CACHE_TTL_SECONDS";
+/// Searches aren't cached unless asked to, so that new releases show up right away.
+const DEFAULT_SEARCH_CACHE_TTL_SECONDS: u64 = 0;
+
+/// Caches searches in Redis if it's configured, in memory otherwise. `None` if the TTL is 0 or not set.
+pub fn create() -> Option<SearchResultsCache> {
+    let ttl = match get_ttl_seconds() {
+        0 => return None,
+        seconds => Duration::from_secs(seconds),
+    };
+    #[cfg(feature = "redis-storage")]
+    if let Ok(redis_url) = std::env::var(REDIS_URL_ENV) {
+        let storage = RedisSearchCache::new(&redis_url)
+            .unwrap_or_else(|e| panic!("Cannot create Redis client from {REDIS_URL_ENV}=\"{redis_url}\": {e}"));
+        return Some(SearchResultsCache::new(Box::new(storage), ttl));
+    }
+    Some(SearchResultsCache::new(Box::new(InMemorySearchCache::new()), ttl))
+}
+
+fn get_ttl_seconds() -> u64 {
+    match std::env::var(SEARCH_CACHE_TTL_SECONDS_ENV) {
+        Ok(val) => val.parse()
+            .unwrap_or_else(|_| panic!("{SEARCH_CACHE_TTL_SECONDS_ENV} must be a non-negative number, got \"{val}\"")),
+        Err(_) => DEFAULT_SEARCH_CACHE_TTL_SECONDS,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ext::search_cache::create;
+
+    #[test]
+    fn disabled_by_default() {
+        temp_env::with_var_unset("SEARCH_CACHE_TTL_SECONDS", || assert!(create().is_none()));
+    }
+
+    #[test]
+    fn disabled_with_zero_ttl() {
+        temp_env::with_var("SEARCH_CACHE_TTL_SECONDS", Some("0"), || assert!(create().is_none()));
+    }
+
+    #[test]
+    #[should_panic(expected = "SEARCH_CACHE_TTL_SECONDS must be a non-negative number, got \"5m\"")]
+    fn invalid_ttl() {
+        temp_env::with_var("SEARCH_CACHE_TTL_SECONDS", Some("5m"), create);
+    }
+}
@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use redis::AsyncCommands;
+
+use crate::core::prowlarr::SearchResult;
+use crate::core::traits::search_cache::SearchCache;
+
+const SEARCH_KEY_PREFIX: &str = "search-cache";
+
+pub struct RedisSearchCache {
+    client: redis::Client,
+}
+
+impl RedisSearchCache {
+    pub fn new(url: &str) -> Result<RedisSearchCache, String> {
+        Ok(RedisSearchCache {
+            client: redis::Client::open(url)
+                .map_err(|e| e.to_string())?,
+        })
+    }
+}
+
+#[async_trait]
+impl SearchCache for RedisSearchCache {
+    async fn get(&self, key: &str) -> Result<Option<Vec<SearchResult>>, String> {
+        let mut con = self.client.get_multiplexed_async_connection().await.map_err(|e| e.to_string())?;
+        let value: Option<String> = con.get(format!("{}:{}", SEARCH_KEY_PREFIX, key)).await.map_err(|e| e.to_string())?;
+        value.map(|value| serde_json::from_str(&value).map_err(|e| e.to_string()))
+            .transpose()
+    }
+
+    async fn put(&self, key: &str, results: &[SearchResult], ttl: Duration) -> Result<(), String> {
+        let mut con = self.client.get_multiplexed_async_connection().await.map_err(|e| e.to_string())?;
+        let value = serde_json::to_string(results).map_err(|e| e.to_string())?;
+        con.set_ex::<_, _, ()>(format!("{}:{}", SEARCH_KEY_PREFIX, key), value, ttl.as_secs())
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
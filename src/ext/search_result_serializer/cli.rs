@@ -0,0 +1,94 @@
+use crate::core::prowlarr::SearchResult;
+use crate::core::traits::search_result_serializer::SearchResultSerializer;
+use crate::ext::search_result_serializer::{get_link_key, has_torrent_file, human_readable_size, labels, peers};
+
+/// Serializes search results into plain text for the terminal.
+pub struct CliSearchResultSerializer;
+
+impl SearchResultSerializer for CliSearchResultSerializer {
+    fn serialize(&self, search_result: &SearchResult, bot_uuid: &str, locale: &str) -> String {
+        format!("{}\n{}: {}\n{}{}{} | {} | {} {} | {} {}\n{}: /d_{}\n{}: /m_{}\n{}\n",
+                search_result.title,
+                t!("description", locale = &locale), search_result.info_url,
+                imdb_line(search_result), labels_line(search_result, locale),
+                peers(search_result, " | "), downloads(search_result, locale),
+                t!("registered", locale = &locale), search_result.publish_date.date_naive(),
+                t!("size", locale = &locale), human_readable_size(search_result.size),
+                t!("download", locale = &locale), bot_uuid,
+                t!(get_link_key(search_result), locale = &locale), bot_uuid,
+                files_line(search_result, bot_uuid, locale))
+    }
+}
+
+fn imdb_line(search_result: &SearchResult) -> String {
+    search_result.imdb_url()
+        .map(|url| format!("IMDb: {url}\n"))
+        .unwrap_or_default()
+}
+
+/// Freeleech flags are upper-cased, as there's no other way to highlight them in plain text.
+fn labels_line(search_result: &SearchResult, locale: &str) -> String {
+    let labels = labels(search_result, locale, str::to_string, |flag| flag.to_uppercase());
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n", labels.join(" | "))
+    }
+}
+
+fn downloads(search_result: &SearchResult, locale: &str) -> String {
+    search_result.grabs
+        .map(|grabs| format!("{} {}", t!("downloaded", locale = &locale), grabs))
+        .unwrap_or_default()
+}
+
+fn files_line(search_result: &SearchResult, bot_uuid: &str, locale: &str) -> String {
+    if has_torrent_file(search_result) {
+        format!("{}: /files_{}\n", t!("files", locale = &locale), bot_uuid)
+    } else {
+        String::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use crate::core::prowlarr::{Protocol, SearchResult};
+    use crate::core::traits::search_result_serializer::SearchResultSerializer;
+    use crate::ext::search_result_serializer::cli::CliSearchResultSerializer;
+
+    #[test]
+    fn search_result_to_message() {
+        let search_result = SearchResult {
+            guid: "ubuntu_22_04".to_string(),
+            indexer_id: 2,
+            indexer: Some("Linux Tracker".to_string()),
+            title: "Ubuntu_22.04 [x64]".to_string(),
+            size: 1234567,
+            publish_date: DateTime::from_timestamp(1431648000, 0).unwrap(),
+            download_url: None,
+            magnet_url: None,
+            info_url: "http://localhost/ubuntu".to_string(),
+            seeders: 20,
+            leechers: 10,
+            grabs: Some(10000),
+            categories: vec![],
+            indexer_flags: vec!["freeleech".to_string()],
+            imdb_id: 133093,
+            info_hash: None,
+            protocol: Protocol::Torrent,
+            other_indexers: vec![],
+        };
+
+        let result = CliSearchResultSerializer.serialize(&search_result, "uuid", "en");
+
+        assert_eq!(result, "Ubuntu_22.04 [x64]\n\
+            Description: http://localhost/ubuntu\n\
+            IMDb: https://www.imdb.com/title/tt0133093/\n\
+            Linux Tracker | FREELEECH\n\
+            S 20 | L 10 | Downloaded 10000 | Reg 2015-05-15 | Size 1.23 MB\n\
+            Download: /d_uuid\n\
+            Get link/torrent-file: /m_uuid\n\n")
+    }
+}
@@ -0,0 +1,99 @@
+use crate::core::prowlarr::SearchResult;
+use crate::core::traits::search_result_serializer::SearchResultSerializer;
+use crate::ext::search_result_serializer::{get_link_key, has_torrent_file, human_readable_size, labels, peers};
+
+/// Serializes search results into the HTML subset supported by Matrix clients (`org.matrix.custom.html`).
+pub struct MatrixSearchResultSerializer;
+
+impl SearchResultSerializer for MatrixSearchResultSerializer {
+    fn serialize(&self, search_result: &SearchResult, bot_uuid: &str, locale: &str) -> String {
+        format!("{}<br>\n<a href=\"{}\">{}</a>{}<br>\n{}{} | {} | {} {} | {} {}<br>\n<b>{}</b>: !d_{}<br>\n{}: !m_{}<br>\n{}<br>\n",
+                escape_html(&search_result.title),
+                escape_html(&search_result.info_url), escape_html(&t!("description", locale = &locale)),
+                imdb_link(search_result), labels_line(search_result, locale),
+                peers(search_result, " | "), downloads(search_result, locale),
+                escape_html(&t!("registered", locale = &locale)), search_result.publish_date.date_naive(),
+                escape_html(&t!("size", locale = &locale)), human_readable_size(search_result.size),
+                escape_html(&t!("download", locale = &locale)), bot_uuid,
+                escape_html(&t!(get_link_key(search_result), locale = &locale)), bot_uuid,
+                files_line(search_result, bot_uuid, locale))
+    }
+}
+
+fn imdb_link(search_result: &SearchResult) -> String {
+    search_result.imdb_url()
+        .map(|url| format!(" | <a href=\"{}\">IMDb</a>", escape_html(&url)))
+        .unwrap_or_default()
+}
+
+fn labels_line(search_result: &SearchResult, locale: &str) -> String {
+    let labels = labels(search_result, locale, escape_html, |flag| format!("<b>{flag}</b>"));
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{}<br>\n", labels.join(" | "))
+    }
+}
+
+fn downloads(search_result: &SearchResult, locale: &str) -> String {
+    search_result.grabs
+        .map(|grabs| format!("{} {}", escape_html(&t!("downloaded", locale = &locale)), grabs))
+        .unwrap_or_default()
+}
+
+pub fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn files_line(search_result: &SearchResult, bot_uuid: &str, locale: &str) -> String {
+    if has_torrent_file(search_result) {
+        format!("{}: !files_{}<br>\n", escape_html(&t!("files", locale = &locale)), bot_uuid)
+    } else {
+        String::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use crate::core::prowlarr::{Protocol, SearchResult};
+    use crate::core::traits::search_result_serializer::SearchResultSerializer;
+    use crate::ext::search_result_serializer::matrix::MatrixSearchResultSerializer;
+
+    #[test]
+    fn search_result_to_message() {
+        let search_result = SearchResult {
+            guid: "ubuntu_22_04".to_string(),
+            indexer_id: 2,
+            indexer: Some("Linux Tracker".to_string()),
+            title: "Ubuntu <22.04> & more".to_string(),
+            size: 1234567,
+            publish_date: DateTime::from_timestamp(1431648000, 0).unwrap(),
+            download_url: None,
+            magnet_url: None,
+            info_url: "http://localhost/ubuntu?a=1&b=2".to_string(),
+            seeders: 20,
+            leechers: 10,
+            grabs: Some(10000),
+            categories: vec![],
+            indexer_flags: vec!["freeleech".to_string()],
+            imdb_id: 133093,
+            info_hash: None,
+            protocol: Protocol::Torrent,
+            other_indexers: vec![],
+        };
+
+        let result = MatrixSearchResultSerializer.serialize(&search_result, "uuid", "en");
+
+        assert_eq!(result, "Ubuntu &lt;22.04&gt; &amp; more<br>\n\
+            <a href=\"http://localhost/ubuntu?a=1&amp;b=2\">Description</a> | <a href=\"https://www.imdb.com/title/tt0133093/\">IMDb</a><br>\n\
+            Linux Tracker | <b>freeleech</b><br>\n\
+            S 20 | L 10 | Downloaded 10000 | Reg 2015-05-15 | Size 1.23 MB<br>\n\
+            <b>Download</b>: !d_uuid<br>\n\
+            Get link/torrent-file: !m_uuid<br>\n<br>\n")
+    }
+}
@@ -1 +1,182 @@
-pub mod telegram;
\ No newline at end of file
+use chrono::Utc;
+
+use crate::core::prowlarr::{is_freeleech_flag, Protocol, SearchResult};
+use crate::core::suspicious::{check_result, warning};
+use crate::core::util::human_readable_size;
+
+/// Tests every Telegram serializer has to pass, whatever parse mode it uses.
+/// `$new` creates a serializer from an optional bot username,
+/// `$to_plain` turns its output into the text a user sees.
+#[cfg(test)]
+macro_rules! telegram_serializer_tests {
+    ($new:expr, $to_plain:expr) => {
+        mod shared {
+            use chrono::DateTime;
+
+            use super::*;
+
+            use crate::core::prowlarr::{Category, Protocol, SearchResult};
+            use crate::core::traits::search_result_serializer::SearchResultSerializer;
+
+            fn search_result(title: &str) -> SearchResult {
+                SearchResult {
+                    guid: "guid".to_string(),
+                    indexer_id: 2,
+                    indexer: None,
+                    title: title.to_string(),
+                    size: 1234567,
+                    publish_date: DateTime::from_timestamp(1431648000, 0).unwrap(),
+                    download_url: None,
+                    magnet_url: None,
+                    info_url: "http://localhost/ubuntu".to_string(),
+                    seeders: 20,
+                    leechers: 10,
+                    grabs: Some(10000),
+                    categories: vec![],
+                    indexer_flags: vec![],
+                    imdb_id: 0,
+                    info_hash: None,
+                    protocol: Protocol::Torrent,
+                    other_indexers: vec![],
+                }
+            }
+
+            fn plain(title: &str, bot_username: Option<&str>) -> String {
+                let serialized = ($new)(bot_username.map(str::to_string))
+                    .serialize(&search_result(title), "uuid", "en");
+                ($to_plain)(&serialized)
+            }
+
+            #[test]
+            fn title_with_special_characters() {
+                let title = "Ubuntu_22.04 [x64] *beta* (1/2) <i>&amp; #1 ~`|{}=+-!\\";
+
+                assert!(plain(title, None).starts_with(&format!("{title}\n")));
+            }
+
+            #[test]
+            fn long_title() {
+                let title = "Ubuntu.22.04 ".repeat(30);
+
+                assert!(plain(&title, None).contains(&title));
+            }
+
+            #[test]
+            fn details_and_commands() {
+                let plain = plain("Ubuntu", None);
+
+                assert!(plain.contains("\nDescription\n"));
+                assert!(plain.contains("\nS 20 | L 10 | Downloaded 10000 | Reg 2015-05-15 | Size 1.23 MB\n"));
+                assert!(plain.contains("\nDownload: /d_uuid\n"));
+                assert!(plain.contains("\nGet link/torrent-file: /m_uuid\n"));
+                assert!(!plain.contains("Share"));
+            }
+
+            #[test]
+            fn labels_and_imdb_link() {
+                let search_result = SearchResult {
+                    indexer: Some("Linux Tracker".to_string()),
+                    other_indexers: vec!["Mirror".to_string()],
+                    categories: vec![Category { id: 2000, name: Some("Movies".to_string()) },
+                                     Category { id: 100002, name: Some("Films HD".to_string()) }],
+                    indexer_flags: vec!["freeleech".to_string(), "scene".to_string()],
+                    imdb_id: 133093,
+                    size: 1_500_000_000,
+                    ..search_result("Ubuntu")
+                };
+
+                let plain = ($to_plain)(&($new)(None).serialize(&search_result, "uuid", "en"));
+
+                assert!(plain.contains("\nDescription | IMDb\nLinux Tracker, Mirror | Movies | freeleech | scene\nS 20 | L 10"));
+            }
+
+            #[test]
+            fn usenet_release() {
+                let search_result = SearchResult { protocol: Protocol::Usenet, ..search_result("Ubuntu") };
+
+                let plain = ($to_plain)(&($new)(None).serialize(&search_result, "uuid", "en"));
+
+                assert!(plain.contains("\nUsenet | Downloaded 10000 | Reg 2015-05-15 | Size 1.23 MB\n"));
+                assert!(plain.contains("\nGet .nzb file: /m_uuid\n"));
+            }
+
+            #[test]
+            fn suspicious_release() {
+                let search_result = SearchResult {
+                    categories: vec![Category { id: 2000, name: Some("Movies".to_string()) }],
+                    ..search_result("Ubuntu")
+                };
+
+                let plain = ($to_plain)(&($new)(None).serialize(&search_result, "uuid", "en"));
+
+                assert!(plain.contains("\nMovies | ⚠️ Suspicious: size is implausible for the category\n"));
+            }
+
+            #[test]
+            fn files_of_torrent_file() {
+                let torrent_file = SearchResult { download_url: Some("http://localhost/ubuntu.torrent".to_string()), ..search_result("Ubuntu") };
+                let magnet = SearchResult { magnet_url: Some("magnet:?xt=urn:btih:hash".to_string()), ..torrent_file.clone() };
+
+                assert!(($to_plain)(&($new)(None).serialize(&torrent_file, "uuid", "en")).contains("\nFiles: /files_uuid\n"));
+                assert!(!($to_plain)(&($new)(None).serialize(&magnet, "uuid", "en")).contains("Files"));
+            }
+
+            #[test]
+            fn share_link() {
+                assert!(plain("Ubuntu", Some("torrents_bot")).ends_with("\nShare\n\n"));
+            }
+
+            #[test]
+            fn localized() {
+                let serialized = ($new)(None).serialize(&search_result("Ubuntu"), "uuid", "ru");
+
+                assert!(($to_plain)(&serialized).contains("Скачать: /d_uuid"));
+            }
+        }
+    };
+}
+
+pub mod telegram;
+pub mod telegram_html;
+pub mod matrix;
+pub mod cli;
+pub mod template;
+#[cfg(feature = "discord")]
+pub mod discord;
+
+/// Names of the indexers carrying a result, standard category names, indexer flags and a warning
+/// if the result looks fake, escaped with `escape`. Freeleech flags and the warning are additionally passed through `highlight`.
+fn labels(search_result: &SearchResult, locale: &str, escape: impl Fn(&str) -> String, highlight: impl Fn(&str) -> String) -> Vec<String> {
+    search_result.indexer.as_ref()
+        .map(|indexer| escape(&search_result.other_indexers.iter()
+            .fold(indexer.clone(), |indexers, other| format!("{indexers}, {other}"))))
+        .into_iter()
+        .chain(search_result.category_names().map(&escape))
+        .chain(search_result.indexer_flags.iter()
+            .map(|flag| if is_freeleech_flag(flag) { highlight(&escape(flag)) } else { escape(flag) }))
+        .chain(warning(&check_result(search_result, Utc::now()), locale).map(|warning| highlight(&escape(&warning))))
+        .collect()
+}
+
+/// Seeders and leechers of a torrent joined with `separator`, Usenet releases have no peers.
+fn peers(search_result: &SearchResult, separator: &str) -> String {
+    match search_result.protocol {
+        Protocol::Usenet => "Usenet".to_string(),
+        Protocol::Torrent | Protocol::Unknown =>
+            format!("S {}{}L {}", search_result.seeders, separator, search_result.leechers),
+    }
+}
+
+/// Locale key of the get-link command, as Usenet releases are delivered as .nzb files.
+fn get_link_key(search_result: &SearchResult) -> &'static str {
+    match search_result.protocol {
+        Protocol::Usenet => "get_nzb",
+        Protocol::Torrent | Protocol::Unknown => "get_link",
+    }
+}
+
+/// Whether a result comes as a .torrent file, whose files can be listed before downloading it.
+/// Magnet links and .nzb files don't list files.
+fn has_torrent_file(search_result: &SearchResult) -> bool {
+    search_result.magnet_url.is_none() && search_result.download_url.is_some() && search_result.protocol != Protocol::Usenet
+}
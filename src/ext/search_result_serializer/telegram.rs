@@ -1,22 +1,55 @@
-use byte_unit::Byte;
-use byte_unit::UnitType::Decimal;
 use teloxide::utils::markdown::{bold, escape, link};
 
 use crate::core::prowlarr::SearchResult;
 use crate::core::traits::search_result_serializer::SearchResultSerializer;
+use crate::ext::search_result_serializer::{get_link_key, has_torrent_file, human_readable_size, labels, peers};
 
-pub struct TgSearchResultSerializer;
+pub struct TgSearchResultSerializer {
+    bot_username: Option<String>,
+}
+
+impl TgSearchResultSerializer {
+    /// `bot_username` is used to render `t.me` deep links sharing a result; they're omitted if it's unknown.
+    pub fn new(bot_username: Option<String>) -> TgSearchResultSerializer {
+        TgSearchResultSerializer { bot_username }
+    }
+
+    fn share_link(&self, bot_uuid: &str, locale: &str) -> String {
+        self.bot_username.as_ref()
+            .map(|bot_username| format!("{}\n", link(&format!("https://t.me/{}?start=r_{}", bot_username, bot_uuid),
+                                                     &t!("share", locale = &locale))))
+            .unwrap_or_default()
+    }
+}
 
 impl SearchResultSerializer for TgSearchResultSerializer {
     fn serialize(&self, search_result: &SearchResult, bot_uuid: &str, locale: &str) -> String {
-        format!("{}\n{}\nS {} \\| L {} \\| {} \\| {} {} \\| {} {}\n{}: /d\\_{}\n{}: /m\\_{}\n\n",
+        format!("{}\n{}{}\n{}{} \\| {} \\| {} {} \\| {} {}\n{}: /d\\_{}\n{}: /m\\_{}\n{}{}\n",
                 escape(&search_result.title),
-                link(&search_result.info_url, &t!("description", locale = &locale)),
-                search_result.seeders, search_result.leechers, downloads(search_result, locale), &t!("registered", locale = &locale),
+                link(&search_result.info_url, &t!("description", locale = &locale)), imdb_link(search_result),
+                labels_line(search_result, locale),
+                peers(search_result, " \\| "), downloads(search_result, locale), &t!("registered", locale = &locale),
                 escape(&search_result.publish_date.date_naive().to_string()),
                 &t!("size", locale = &locale), size(search_result),
                 bold(&t!("download", locale = &locale)), bot_uuid,
-                escape(&t!("get_link", locale = &locale)), bot_uuid)
+                escape(&t!(get_link_key(search_result), locale = &locale)), bot_uuid,
+                files_line(search_result, bot_uuid, locale),
+                self.share_link(bot_uuid, locale))
+    }
+}
+
+fn imdb_link(search_result: &SearchResult) -> String {
+    search_result.imdb_url()
+        .map(|url| format!(" \\| {}", link(&url, "IMDb")))
+        .unwrap_or_default()
+}
+
+fn labels_line(search_result: &SearchResult, locale: &str) -> String {
+    let labels = labels(search_result, locale, escape, bold);
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n", labels.join(" \\| "))
     }
 }
 
@@ -27,26 +60,53 @@ fn downloads(search_result: &SearchResult, locale: &str) -> String {
 }
 
 fn size(search_result: &SearchResult) -> String {
-    let size = Byte::from_u128(search_result.size)
-        .map(|b| b.get_appropriate_unit(Decimal))
-        .map(|b| format!("{b:#.2}"))
-        .unwrap_or_else(|| "???".to_string());
-    escape(&size)
+    escape(&human_readable_size(search_result.size))
+}
+
+fn files_line(search_result: &SearchResult, bot_uuid: &str, locale: &str) -> String {
+    if has_torrent_file(search_result) {
+        format!("{}: /files\\_{}\n", escape(&t!("files", locale = &locale)), bot_uuid)
+    } else {
+        String::new()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use chrono::DateTime;
 
-    use crate::core::prowlarr::SearchResult;
+    use crate::core::prowlarr::{Protocol, SearchResult};
     use crate::core::traits::search_result_serializer::SearchResultSerializer;
     use crate::ext::search_result_serializer::telegram::TgSearchResultSerializer;
 
-    #[test]
-    fn search_result_to_message() {
-        let search_result = SearchResult {
+    /// Drops MarkdownV2 markup: escapes, bold text markers and link urls.
+    fn markdown_to_plain(markdown: &str) -> String {
+        let mut plain = String::with_capacity(markdown.len());
+        let mut chars = markdown.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => plain.extend(chars.next()),
+                '*' | '[' => {}
+                ']' => {
+                    for c in chars.by_ref() {
+                        if c == ')' {
+                            break;
+                        }
+                    }
+                }
+                _ => plain.push(c),
+            }
+        }
+        plain
+    }
+
+    telegram_serializer_tests!(TgSearchResultSerializer::new, markdown_to_plain);
+
+    fn search_result() -> SearchResult {
+        SearchResult {
             guid: "ubuntu_22_04".to_string(),
             indexer_id: 2,
+            indexer: None,
             title: "Ubuntu 22.04".to_string(),
             size: 1234567,
             publish_date: DateTime::from_timestamp(1431648000, 0).unwrap(),
@@ -56,10 +116,18 @@ mod tests {
             seeders: 20,
             leechers: 10,
             grabs: Some(10000),
-        };
-        let bot_uuid = "uuid";
+            categories: vec![],
+            indexer_flags: vec![],
+            imdb_id: 0,
+            info_hash: None,
+            protocol: Protocol::Torrent,
+            other_indexers: vec![],
+        }
+    }
 
-        let result = TgSearchResultSerializer.serialize(&search_result, bot_uuid, "en");
+    #[test]
+    fn search_result_to_message() {
+        let result = TgSearchResultSerializer::new(None).serialize(&search_result(), "uuid", "en");
 
         assert_eq!(result, "Ubuntu 22\\.04\n\
             [Description](http://localhost/ubuntu)\n\
@@ -67,4 +135,29 @@ mod tests {
             *Download*: /d\\_uuid\n\
             Get link/torrent\\-file: /m\\_uuid\n\n")
     }
+
+    #[test]
+    fn search_result_with_share_link() {
+        let result = TgSearchResultSerializer::new(Some("torrents_bot".to_string()))
+            .serialize(&search_result(), "uuid", "en");
+
+        assert_eq!(result, "Ubuntu 22\\.04\n\
+            [Description](http://localhost/ubuntu)\n\
+            S 20 \\| L 10 \\| Downloaded 10000 \\| Reg 2015\\-05\\-15 \\| Size 1\\.23 MB\n\
+            *Download*: /d\\_uuid\n\
+            Get link/torrent\\-file: /m\\_uuid\n\
+            [Share](https://t.me/torrents_bot?start=r_uuid)\n\n")
+    }
+
+    #[test]
+    fn freeleech_is_bold() {
+        let search_result = SearchResult {
+            indexer_flags: vec!["freeleech75".to_string(), "scene".to_string()],
+            ..search_result()
+        };
+
+        let result = TgSearchResultSerializer::new(None).serialize(&search_result, "uuid", "en");
+
+        assert!(result.contains("\n*freeleech75* \\| scene\nS 20"));
+    }
 }
@@ -0,0 +1,170 @@
+use teloxide::utils::html::{bold, escape, expandable_blockquote, link};
+
+use crate::core::prowlarr::SearchResult;
+use crate::core::traits::search_result_serializer::SearchResultSerializer;
+use crate::ext::search_result_serializer::{get_link_key, has_torrent_file, human_readable_size, labels, peers};
+
+/// Titles longer than this are collapsed into an expandable blockquote.
+const LONG_TITLE_CHARS: usize = 100;
+
+/// Serializes search results into Telegram's HTML, which only needs `<`, `>` and `&` to be escaped.
+pub struct TgHtmlSearchResultSerializer {
+    bot_username: Option<String>,
+}
+
+impl TgHtmlSearchResultSerializer {
+    /// `bot_username` is used to render `t.me` deep links sharing a result; they're omitted if it's unknown.
+    pub fn new(bot_username: Option<String>) -> TgHtmlSearchResultSerializer {
+        TgHtmlSearchResultSerializer { bot_username }
+    }
+
+    fn share_link(&self, bot_uuid: &str, locale: &str) -> String {
+        self.bot_username.as_ref()
+            .map(|bot_username| format!("{}\n", link(&format!("https://t.me/{}?start=r_{}", bot_username, bot_uuid),
+                                                     &t!("share", locale = &locale))))
+            .unwrap_or_default()
+    }
+}
+
+impl SearchResultSerializer for TgHtmlSearchResultSerializer {
+    fn serialize(&self, search_result: &SearchResult, bot_uuid: &str, locale: &str) -> String {
+        format!("{}\n{}{}\n{}{} | {} | {} {} | {} {}\n{}: /d_{}\n{}: /m_{}\n{}{}\n",
+                title(search_result),
+                link(&search_result.info_url, &t!("description", locale = &locale)), imdb_link(search_result),
+                labels_line(search_result, locale),
+                peers(search_result, " | "), downloads(search_result, locale),
+                escape(&t!("registered", locale = &locale)), search_result.publish_date.date_naive(),
+                escape(&t!("size", locale = &locale)), human_readable_size(search_result.size),
+                bold(&escape(&t!("download", locale = &locale))), bot_uuid,
+                escape(&t!(get_link_key(search_result), locale = &locale)), bot_uuid,
+                files_line(search_result, bot_uuid, locale),
+                self.share_link(bot_uuid, locale))
+    }
+}
+
+fn title(search_result: &SearchResult) -> String {
+    if search_result.title.chars().count() > LONG_TITLE_CHARS {
+        expandable_blockquote(&escape(&search_result.title))
+    } else {
+        escape(&search_result.title)
+    }
+}
+
+fn imdb_link(search_result: &SearchResult) -> String {
+    search_result.imdb_url()
+        .map(|url| format!(" | {}", link(&url, "IMDb")))
+        .unwrap_or_default()
+}
+
+fn labels_line(search_result: &SearchResult, locale: &str) -> String {
+    let labels = labels(search_result, locale, escape, bold);
+    if labels.is_empty() {
+        String::new()
+    } else {
+        format!("{}\n", labels.join(" | "))
+    }
+}
+
+fn downloads(search_result: &SearchResult, locale: &str) -> String {
+    search_result.grabs
+        .map(|grabs| format!("{} {}", escape(&t!("downloaded", locale = &locale)), grabs))
+        .unwrap_or_default()
+}
+
+fn files_line(search_result: &SearchResult, bot_uuid: &str, locale: &str) -> String {
+    if has_torrent_file(search_result) {
+        format!("{}: /files_{}\n", escape(&t!("files", locale = &locale)), bot_uuid)
+    } else {
+        String::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use crate::core::prowlarr::{Protocol, SearchResult};
+    use crate::core::traits::search_result_serializer::SearchResultSerializer;
+    use crate::ext::search_result_serializer::telegram_html::TgHtmlSearchResultSerializer;
+
+    fn html_to_plain(html: &str) -> String {
+        let mut plain = String::with_capacity(html.len());
+        let mut inside_tag = false;
+        for c in html.chars() {
+            match c {
+                '<' => inside_tag = true,
+                '>' => inside_tag = false,
+                _ if !inside_tag => plain.push(c),
+                _ => {}
+            }
+        }
+        plain.replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&amp;", "&")
+    }
+
+    telegram_serializer_tests!(TgHtmlSearchResultSerializer::new, html_to_plain);
+
+    #[test]
+    fn search_result_to_message() {
+        let search_result = SearchResult {
+            guid: "ubuntu_22_04".to_string(),
+            indexer_id: 2,
+            indexer: None,
+            title: "Ubuntu <22.04> & more".to_string(),
+            size: 1234567,
+            publish_date: DateTime::from_timestamp(1431648000, 0).unwrap(),
+            download_url: None,
+            magnet_url: None,
+            info_url: "http://localhost/ubuntu?a=1&b=2".to_string(),
+            seeders: 20,
+            leechers: 10,
+            grabs: Some(10000),
+            categories: vec![],
+            indexer_flags: vec!["freeleech".to_string()],
+            imdb_id: 0,
+            info_hash: None,
+            protocol: Protocol::Torrent,
+            other_indexers: vec![],
+        };
+
+        let result = TgHtmlSearchResultSerializer::new(Some("torrents_bot".to_string()))
+            .serialize(&search_result, "uuid", "en");
+
+        assert_eq!(result, "Ubuntu &lt;22.04&gt; &amp; more\n\
+            <a href=\"http://localhost/ubuntu?a=1&amp;b=2\">Description</a>\n\
+            <b>freeleech</b>\n\
+            S 20 | L 10 | Downloaded 10000 | Reg 2015-05-15 | Size 1.23 MB\n\
+            <b>Download</b>: /d_uuid\n\
+            Get link/torrent-file: /m_uuid\n\
+            <a href=\"https://t.me/torrents_bot?start=r_uuid\">Share</a>\n\n")
+    }
+
+    #[test]
+    fn long_title_is_collapsed() {
+        let search_result = SearchResult {
+            guid: "ubuntu_22_04".to_string(),
+            indexer_id: 2,
+            indexer: None,
+            title: "Ubuntu ".repeat(20),
+            size: 1234567,
+            publish_date: DateTime::from_timestamp(1431648000, 0).unwrap(),
+            download_url: None,
+            magnet_url: None,
+            info_url: "http://localhost/ubuntu".to_string(),
+            seeders: 20,
+            leechers: 10,
+            grabs: None,
+            categories: vec![],
+            indexer_flags: vec![],
+            imdb_id: 0,
+            info_hash: None,
+            protocol: Protocol::Torrent,
+            other_indexers: vec![],
+        };
+
+        let result = TgHtmlSearchResultSerializer::new(None).serialize(&search_result, "uuid", "en");
+
+        assert!(result.starts_with(&format!("<blockquote expandable>{}</blockquote>\n", "Ubuntu ".repeat(20))));
+    }
+}
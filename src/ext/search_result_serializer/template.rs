@@ -0,0 +1,278 @@
+use std::fs;
+
+use thiserror::Error;
+
+use crate::core::prowlarr::SearchResult;
+use crate::core::traits::search_result_serializer::SearchResultSerializer;
+use crate::ext::search_result_serializer::human_readable_size;
+
+const RESULT_TEMPLATE_FILE_ENV: &str = "RESULT_TEMPLATE_FILE";
+
+#[derive(Error, Debug, PartialEq)]
+pub enum TemplateError {
+    #[error("unknown placeholder {{{0}}}, expected one of: {placeholders}", placeholders = placeholders())]
+    UnknownPlaceholder(String),
+    #[error("placeholder opened at character {0} isn't closed, use {{{{ and }}}} for literal braces")]
+    Unclosed(usize),
+    #[error("unexpected }} at character {0}, use }}}} for a literal brace")]
+    UnexpectedClosingBrace(usize),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Field {
+    Title,
+    Indexer,
+    Size,
+    Seeders,
+    Leechers,
+    Grabs,
+    Date,
+    InfoUrl,
+    Id,
+    Categories,
+    Flags,
+    ImdbUrl,
+    InfoHash,
+    Protocol,
+    Indexers,
+}
+
+const FIELDS: &[(&str, Field)] = &[
+    ("title", Field::Title),
+    ("indexer", Field::Indexer),
+    ("size", Field::Size),
+    ("seeders", Field::Seeders),
+    ("leechers", Field::Leechers),
+    ("grabs", Field::Grabs),
+    ("date", Field::Date),
+    ("info_url", Field::InfoUrl),
+    ("id", Field::Id),
+    ("categories", Field::Categories),
+    ("flags", Field::Flags),
+    ("imdb_url", Field::ImdbUrl),
+    ("info_hash", Field::InfoHash),
+    ("protocol", Field::Protocol),
+    ("indexers", Field::Indexers),
+];
+
+fn placeholders() -> String {
+    FIELDS.iter()
+        .map(|(name, _)| format!("{{{name}}}"))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Literal(String),
+    Field(Field),
+}
+
+/// Serializes search results with an operator-provided template, e.g.
+/// `{title} ({indexer}, {size})\n/d_{id}`. The template is written in the front-end's markup,
+/// while placeholder values are escaped for it with `escape`.
+pub struct TemplateSearchResultSerializer {
+    segments: Vec<Segment>,
+    escape: fn(&str) -> String,
+}
+
+impl TemplateSearchResultSerializer {
+    /// Loads the template from the file set by `RESULT_TEMPLATE_FILE`, if it's set.
+    pub fn from_env(escape: fn(&str) -> String) -> Option<TemplateSearchResultSerializer> {
+        let path = std::env::var(RESULT_TEMPLATE_FILE_ENV).ok()?;
+        let template = fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("Could not read {RESULT_TEMPLATE_FILE_ENV} \"{path}\": {err}"));
+        Some(TemplateSearchResultSerializer::new(&template, escape)
+            .unwrap_or_else(|err| panic!("Invalid result template \"{path}\": {err}")))
+    }
+
+    pub fn new(template: &str, escape: fn(&str) -> String) -> Result<TemplateSearchResultSerializer, TemplateError> {
+        Ok(TemplateSearchResultSerializer { segments: parse(template)?, escape })
+    }
+
+    fn value(&self, field: Field, search_result: &SearchResult, bot_uuid: &str) -> String {
+        match field {
+            Field::Title => (self.escape)(&search_result.title),
+            Field::Indexer => (self.escape)(&search_result.indexer.clone()
+                .unwrap_or_else(|| search_result.indexer_id.to_string())),
+            Field::Size => (self.escape)(&human_readable_size(search_result.size)),
+            Field::Seeders => search_result.seeders.to_string(),
+            Field::Leechers => search_result.leechers.to_string(),
+            Field::Grabs => search_result.grabs.map(|grabs| grabs.to_string()).unwrap_or_default(),
+            Field::Date => (self.escape)(&search_result.publish_date.date_naive().to_string()),
+            Field::InfoUrl => (self.escape)(&search_result.info_url),
+            Field::Id => (self.escape)(bot_uuid),
+            Field::Categories => (self.escape)(&search_result.category_names().collect::<Vec<_>>().join(", ")),
+            Field::Flags => (self.escape)(&search_result.indexer_flags.join(", ")),
+            Field::ImdbUrl => (self.escape)(&search_result.imdb_url().unwrap_or_default()),
+            Field::InfoHash => (self.escape)(search_result.info_hash.as_deref().unwrap_or_default()),
+            Field::Protocol => format!("{:?}", search_result.protocol).to_lowercase(),
+            Field::Indexers => (self.escape)(&search_result.indexer.iter()
+                .chain(&search_result.other_indexers)
+                .map(String::as_str)
+                .collect::<Vec<_>>()
+                .join(", ")),
+        }
+    }
+}
+
+impl SearchResultSerializer for TemplateSearchResultSerializer {
+    fn serialize(&self, search_result: &SearchResult, bot_uuid: &str, _locale: &str) -> String {
+        self.segments.iter()
+            .map(|segment| match segment {
+                Segment::Literal(literal) => literal.clone(),
+                Segment::Field(field) => self.value(*field, search_result, bot_uuid),
+            })
+            .collect()
+    }
+}
+
+fn parse(template: &str) -> Result<Vec<Segment>, TemplateError> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut chars = template.char_indices().peekable();
+    while let Some((position, c)) = chars.next() {
+        match c {
+            '{' if chars.next_if(|(_, c)| *c == '{').is_some() => literal.push('{'),
+            '}' if chars.next_if(|(_, c)| *c == '}').is_some() => literal.push('}'),
+            '{' => {
+                let name: String = chars.by_ref()
+                    .map(|(_, c)| c)
+                    .take_while(|c| *c != '}')
+                    .collect();
+                if !template[position..].contains('}') {
+                    return Err(TemplateError::Unclosed(position));
+                }
+                let field = FIELDS.iter()
+                    .find(|(field_name, _)| *field_name == name.trim())
+                    .map(|(_, field)| *field)
+                    .ok_or(TemplateError::UnknownPlaceholder(name))?;
+                if !literal.is_empty() {
+                    segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                }
+                segments.push(Segment::Field(field));
+            }
+            '}' => return Err(TemplateError::UnexpectedClosingBrace(position)),
+            _ => literal.push(c),
+        }
+    }
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal));
+    }
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+    use teloxide::utils::markdown;
+
+    use crate::core::prowlarr::{Category, Protocol, SearchResult};
+    use crate::core::traits::search_result_serializer::SearchResultSerializer;
+    use crate::ext::search_result_serializer::template::{TemplateError, TemplateSearchResultSerializer};
+
+    fn search_result() -> SearchResult {
+        SearchResult {
+            guid: "ubuntu_22_04".to_string(),
+            indexer_id: 2,
+            indexer: Some("Linux Tracker".to_string()),
+            title: "Ubuntu 22.04".to_string(),
+            size: 1234567,
+            publish_date: DateTime::from_timestamp(1431648000, 0).unwrap(),
+            download_url: None,
+            magnet_url: None,
+            info_url: "http://localhost/ubuntu".to_string(),
+            seeders: 20,
+            leechers: 10,
+            grabs: None,
+            categories: vec![],
+            indexer_flags: vec![],
+            imdb_id: 0,
+            info_hash: None,
+            protocol: Protocol::Torrent,
+            other_indexers: vec![],
+        }
+    }
+
+    #[test]
+    fn placeholders_are_replaced_with_escaped_values() {
+        let serializer = TemplateSearchResultSerializer::new(
+            "*{title}* \\({indexer}, {size}, {date}\\)\nS {seeders} \\| L {leechers}{grabs}\n/d\\_{id}\n\n",
+            markdown::escape).unwrap();
+
+        assert_eq!(serializer.serialize(&search_result(), "uuid", "en"),
+                   "*Ubuntu 22\\.04* \\(Linux Tracker, 1\\.23 MB, 2015\\-05\\-15\\)\nS 20 \\| L 10\n/d\\_uuid\n\n");
+    }
+
+    #[test]
+    fn details() {
+        let serializer = TemplateSearchResultSerializer::new("{categories}|{flags}|{imdb_url}|{info_hash}|{protocol}|{indexers}",
+                                                             |s| s.to_string()).unwrap();
+        let search_result = SearchResult {
+            categories: vec![Category { id: 2000, name: Some("Movies".to_string()) },
+                             Category { id: 2040, name: Some("Movies/HD".to_string()) }],
+            indexer_flags: vec!["freeleech".to_string(), "scene".to_string()],
+            imdb_id: 133093,
+            info_hash: Some("ABCDEF".to_string()),
+            other_indexers: vec!["Mirror".to_string()],
+            ..search_result()
+        };
+
+        assert_eq!(serializer.serialize(&search_result, "uuid", "en"),
+                   "Movies, Movies/HD|freeleech, scene|https://www.imdb.com/title/tt0133093/|ABCDEF|torrent|Linux Tracker, Mirror");
+    }
+
+    #[test]
+    fn literal_braces() {
+        let serializer = TemplateSearchResultSerializer::new("{{{ title }}}", |s| s.to_string()).unwrap();
+
+        assert_eq!(serializer.serialize(&search_result(), "uuid", "en"), "{Ubuntu 22.04}");
+    }
+
+    #[test]
+    fn indexer_id_if_name_is_unknown() {
+        let serializer = TemplateSearchResultSerializer::new("{indexer}", |s| s.to_string()).unwrap();
+        let search_result = SearchResult { indexer: None, ..search_result() };
+
+        assert_eq!(serializer.serialize(&search_result, "uuid", "en"), "2");
+    }
+
+    #[test]
+    fn unknown_placeholder() {
+        assert_eq!(TemplateSearchResultSerializer::new("{title} {magnet}", |s| s.to_string()).err(),
+                   Some(TemplateError::UnknownPlaceholder("magnet".to_string())));
+    }
+
+    #[test]
+    fn unclosed_placeholder() {
+        assert_eq!(TemplateSearchResultSerializer::new("{title} {size", |s| s.to_string()).err(),
+                   Some(TemplateError::Unclosed(8)));
+    }
+
+    #[test]
+    fn unexpected_closing_brace() {
+        assert_eq!(TemplateSearchResultSerializer::new("{title} }", |s| s.to_string()).err(),
+                   Some(TemplateError::UnexpectedClosingBrace(8)));
+    }
+
+    #[test]
+    fn loaded_from_file() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), "{title}").unwrap();
+
+        let serializer = temp_env::with_var("RESULT_TEMPLATE_FILE", Some(file.path()),
+                                            || TemplateSearchResultSerializer::from_env(|s| s.to_string()));
+
+        assert_eq!(serializer.unwrap().serialize(&search_result(), "uuid", "en"), "Ubuntu 22.04");
+    }
+
+    #[test]
+    #[should_panic(expected = "unknown placeholder {magnet}, expected one of: {title}, {indexer}")]
+    fn invalid_template_file() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), "{magnet}").unwrap();
+
+        temp_env::with_var("RESULT_TEMPLATE_FILE", Some(file.path()),
+                           || TemplateSearchResultSerializer::from_env(|s| s.to_string()));
+    }
+}
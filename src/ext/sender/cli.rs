@@ -0,0 +1,126 @@
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+
+use crate::core::response::Response;
+use crate::core::traits::input::{Destination, ReplyToMessage};
+use crate::core::traits::search_result_serializer::SearchResultSerializer;
+use crate::core::traits::sender::{MessageHandle, Sender};
+use crate::core::{HandlingError, HandlingResult};
+use crate::ext::input_handler::telegram::command_text;
+use crate::ext::sender::choices_text;
+
+/// Prints messages to a terminal. Nothing can be edited there, so edits are printed as new messages,
+/// and torrent files are saved to `download_dir`.
+pub struct CliSender<W: Write + Send> {
+    out: Mutex<W>,
+    download_dir: PathBuf,
+    message_sequence: AtomicU64,
+    search_result_serializer: Arc<dyn SearchResultSerializer>,
+}
+
+impl<W: Write + Send> CliSender<W> {
+    pub fn new(out: W, download_dir: PathBuf, search_result_serializer: Arc<dyn SearchResultSerializer>) -> CliSender<W> {
+        CliSender {
+            out: Mutex::new(out),
+            download_dir,
+            message_sequence: AtomicU64::new(1),
+            search_result_serializer,
+        }
+    }
+
+    fn text(&self, response: &Response) -> Result<String, HandlingError> {
+        match response {
+            Response::Results { results, locale } => Ok(self.search_result_serializer.serialize_all(results, locale)),
+            Response::Notice(message) | Response::Error(message) | Response::Magnet(message) => Ok(message.clone()),
+            Response::Choices { heading, choices } => Ok(choices_text(heading, choices, command_text)),
+            Response::File { .. } => Err(HandlingError::SendError("A file can't be printed".to_string())),
+        }
+    }
+
+    fn print(&self, message: &str) -> HandlingResult {
+        let mut out = self.out.lock().unwrap();
+        writeln!(out, "{}", message.trim_end())
+            .and_then(|_| out.flush())
+            .map_err(|err| HandlingError::SendError(err.to_string()))
+    }
+}
+
+#[async_trait]
+impl<W: Write + Send> Sender for CliSender<W> {
+    async fn send_progress_indication(&self, _destination: Destination) -> HandlingResult {
+        Ok(())
+    }
+
+    async fn send(&self, _destination: Destination, response: &Response) -> HandlingResult {
+        if let Response::File { filename, content } = response {
+            let path = self.download_dir.join(filename);
+            tokio::fs::write(&path, content)
+                .await
+                .map_err(|err| HandlingError::SendError(format!("Could not save {}: {}", path.display(), err)))?;
+            return self.print(&format!("{}", path.display()));
+        }
+        self.print(&self.text(response)?)
+    }
+
+    async fn send_editable_reply(&self, _destination: Destination, _reply_to_message: ReplyToMessage, response: &Response) -> Result<MessageHandle, HandlingError> {
+        self.print(&self.text(response)?)?;
+        Ok(self.message_sequence.fetch_add(1, Ordering::SeqCst).to_string().into())
+    }
+
+    async fn edit(&self, _destination: Destination, _message_handle: &MessageHandle, response: &Response) -> HandlingResult {
+        self.print(&self.text(response)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+
+    use crate::core::response::{Choice, Response};
+    use crate::core::traits::input::Command;
+    use crate::core::traits::sender::Sender;
+    use crate::ext::search_result_serializer::cli::CliSearchResultSerializer;
+    use crate::ext::sender::cli::CliSender;
+
+    #[tokio::test]
+    async fn choices_are_printed_with_commands() {
+        let sender = CliSender::new(Vec::new(), std::env::temp_dir(), Arc::new(CliSearchResultSerializer));
+        let choices = vec![
+            Choice { label: "The Office (2005)".to_string(), command: Some(Command::Add("uuid".into())) },
+            Choice { label: "The Office (2001), already in the library".to_string(), command: None },
+        ];
+
+        sender.send(0, &Response::Choices { heading: "Which one?".to_string(), choices }).await.unwrap();
+
+        assert_eq!(String::from_utf8(sender.out.into_inner().unwrap()).unwrap(),
+                   "Which one?\n\nThe Office (2005)\n/a_uuid\n\nThe Office (2001), already in the library\n");
+    }
+
+    #[tokio::test]
+    async fn edits_are_printed_as_new_messages() {
+        let sender = CliSender::new(Vec::new(), std::env::temp_dir(), Arc::new(CliSearchResultSerializer));
+
+        let handle = sender.send_editable_reply(0, 0, &Response::Notice("Searching...".to_string())).await.unwrap();
+        sender.edit(0, &handle, &Response::Notice("Nothing found\n".to_string())).await.unwrap();
+
+        assert_eq!(String::from_utf8(sender.out.into_inner().unwrap()).unwrap(), "Searching...\nNothing found\n");
+    }
+
+    #[tokio::test]
+    async fn torrent_file_is_saved() {
+        let download_dir = tempfile::tempdir().unwrap();
+        let sender = CliSender::new(Vec::new(), download_dir.path().to_path_buf(), Arc::new(CliSearchResultSerializer));
+
+        sender.send(0, &Response::File { filename: "uuid.torrent".to_string(), content: Bytes::from("contents") }).await.unwrap();
+
+        let path = download_dir.path().join("uuid.torrent");
+        assert_eq!(std::fs::read(&path).unwrap(), b"contents");
+        assert_eq!(String::from_utf8(sender.out.into_inner().unwrap()).unwrap(), format!("{}\n", path.display()));
+    }
+}
@@ -0,0 +1,277 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::core::response::Response;
+use crate::core::traits::input::{Destination, ReplyToMessage};
+use crate::core::traits::search_result_serializer::SearchResultSerializer;
+use crate::core::traits::sender::{MessageHandle, Sender};
+use crate::core::{HandlingError, HandlingResult};
+use crate::ext::discord::DiscordClient;
+use crate::ext::input_handler::discord::command_text;
+use crate::ext::sender::{choices_text, split_results};
+
+/// Discord rejects messages with longer content.
+const MAX_CONTENT_LENGTH: usize = 2000;
+/// Discord rejects embeds with longer descriptions.
+const MAX_EMBED_DESCRIPTION_LENGTH: usize = 4096;
+
+pub struct DiscordSender {
+    client: Arc<DiscordClient>,
+    search_result_serializer: Arc<dyn SearchResultSerializer>,
+}
+
+impl DiscordSender {
+    pub fn new(client: Arc<DiscordClient>, search_result_serializer: Arc<dyn SearchResultSerializer>) -> DiscordSender {
+        DiscordSender { client, search_result_serializer }
+    }
+
+    /// Renders a response into one or more messages, as texts that don't fit into one message are split between several.
+    fn contents(&self, response: &Response) -> Result<Vec<Value>, HandlingError> {
+        match response {
+            Response::Results { results, locale } =>
+                Ok(split_results(self.search_result_serializer.as_ref(), results, locale, MAX_EMBED_DESCRIPTION_LENGTH, length)
+                    .iter()
+                    .map(|message| formatted(message))
+                    .collect()),
+            Response::Notice(message) | Response::Error(message) => Ok(split_text(message, MAX_CONTENT_LENGTH).iter().map(|text| plain(text)).collect()),
+            Response::Magnet(link) => Ok(vec![json!({ "content": code_block(&fit_magnet(link, MAX_CONTENT_LENGTH - code_block("").len())) })]),
+            Response::Choices { heading, choices } =>
+                Ok(split_text(&choices_text(heading, choices, command_text), MAX_CONTENT_LENGTH).iter().map(|text| plain(text)).collect()),
+            Response::File { .. } => Err(HandlingError::SendError("A file can't be sent as a text message".to_string())),
+        }
+    }
+
+    async fn send_contents(&self, destination: Destination, contents: impl IntoIterator<Item = Value>) -> HandlingResult {
+        for content in contents {
+            self.send_content(destination, content).await?;
+        }
+        Ok(())
+    }
+
+    async fn send_content(&self, destination: Destination, message: Value) -> Result<MessageHandle, HandlingError> {
+        self.client.create_message(&destination.to_string(), &message)
+            .await
+            .map(|message_id| message_id.into())
+            .map_err(|err| HandlingError::SendError(err.to_string()))
+    }
+}
+
+#[async_trait]
+impl Sender for DiscordSender {
+    async fn send_progress_indication(&self, destination: Destination) -> HandlingResult {
+        self.client.trigger_typing(&destination.to_string())
+            .await
+            .map_err(|err| HandlingError::SendError(err.to_string()))
+    }
+
+    async fn send(&self, destination: Destination, response: &Response) -> HandlingResult {
+        if let Response::File { filename, content } = response {
+            return self.client.create_message_with_file(&destination.to_string(), filename, content.clone())
+                .await
+                .map(|_| {})
+                .map_err(|err| HandlingError::SendError(err.to_string()));
+        }
+        self.send_contents(destination, self.contents(response)?).await
+    }
+
+    /// Messages are requested through slash commands, which can't be replied to, so `reply_to_message` is unused.
+    /// If the response is split into several messages, only the first one can be edited.
+    async fn send_editable_reply(&self, destination: Destination, _reply_to_message: ReplyToMessage, response: &Response) -> Result<MessageHandle, HandlingError> {
+        let mut contents = self.contents(response)?.into_iter();
+        let first = contents.next()
+            .ok_or_else(|| HandlingError::SendError("Nothing to send".to_string()))?;
+        let message_handle = self.send_content(destination, first).await?;
+        self.send_contents(destination, contents).await?;
+        Ok(message_handle)
+    }
+
+    /// If the response is split into several messages, the first one replaces the message and the rest are sent after it.
+    async fn edit(&self, destination: Destination, message_handle: &MessageHandle, response: &Response) -> HandlingResult {
+        let mut contents = self.contents(response)?.into_iter();
+        let Some(first) = contents.next() else {
+            return Ok(());
+        };
+        self.client.edit_message(&destination.to_string(), message_handle, &first)
+            .await
+            .map_err(|err| HandlingError::SendError(err.to_string()))?;
+        self.send_contents(destination, contents).await
+    }
+}
+
+/// Formatted messages go to an embed, as its description fits twice as much text as a message content.
+fn formatted(message: &str) -> Value {
+    json!({ "content": "", "embeds": [{ "description": message }] })
+}
+
+fn plain(message: &str) -> Value {
+    json!({ "content": message, "embeds": [], "allowed_mentions": { "parse": [] } })
+}
+
+fn code_block(code: &str) -> String {
+    format!("```\n{}\n```", code)
+}
+
+/// Discord counts characters, not bytes.
+fn length(message: &str) -> usize {
+    message.chars().count()
+}
+
+/// Splits a text into parts of at most `max_length`, between lines where possible.
+fn split_text(text: &str, max_length: usize) -> Vec<String> {
+    let mut parts: Vec<String> = Vec::new();
+    for line in text.split('\n') {
+        // a line too long for a message on its own is cut into pieces
+        let chars: Vec<char> = line.chars().collect();
+        let pieces: Vec<String> = if chars.is_empty() {
+            vec![String::new()]
+        } else {
+            chars.chunks(max_length).map(|chunk| chunk.iter().collect()).collect()
+        };
+        for piece in pieces {
+            match parts.last_mut() {
+                Some(part) if length(part) + 1 + length(&piece) <= max_length => {
+                    part.push('\n');
+                    part.push_str(&piece);
+                }
+                _ => parts.push(piece),
+            }
+        }
+    }
+    parts
+}
+
+/// Drops trackers from the end of a magnet link until it fits into `max_length`,
+/// as clients can still find peers of a torrent through DHT.
+fn fit_magnet(link: &str, max_length: usize) -> String {
+    let mut link = link.to_string();
+    while length(&link) > max_length {
+        let Some(start) = link.rfind("&tr=") else {
+            break;
+        };
+        let end = link[start + 1..].find('&').map_or(link.len(), |end| start + 1 + end);
+        link.replace_range(start..end, "");
+    }
+    link
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use serde_json::{json, Value};
+    use url::Url;
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::core::prowlarr::SearchResult;
+    use crate::core::response::{FoundResult, Response};
+    use crate::core::traits::search_result_serializer::SearchResultSerializer;
+    use crate::core::traits::sender::Sender;
+    use crate::ext::discord::DiscordClient;
+    use crate::ext::sender::discord::{fit_magnet, length, split_text, DiscordSender, MAX_CONTENT_LENGTH, MAX_EMBED_DESCRIPTION_LENGTH};
+
+    struct TitleSerializer;
+
+    impl SearchResultSerializer for TitleSerializer {
+        fn serialize(&self, search_result: &SearchResult, _bot_uuid: &str, _locale: &str) -> String {
+            format!("**{}**", search_result.title)
+        }
+    }
+
+    #[tokio::test]
+    async fn progress_message_is_edited_into_embed() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/channels/100/messages"))
+            .and(body_json(json!({ "content": "Searching…", "embeds": [], "allowed_mentions": { "parse": [] } })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "200" })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/channels/100/messages/200"))
+            .and(body_json(json!({ "content": "", "embeds": [{ "description": "**Results**" }] })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let sender = DiscordSender::new(Arc::new(
+            DiscordClient::new(Url::parse(&mock_server.uri()).unwrap(), "token".to_string())),
+            Arc::new(TitleSerializer));
+        let results = vec![FoundResult {
+            uuid: "uuid".to_string(),
+            search_result: serde_json::from_value::<SearchResult>(json!({
+                "guid": "1", "indexerId": 1, "title": "Results", "size": 1, "publishDate": "2015-05-15T00:00:00Z",
+                "infoUrl": "info url", "seeders": 1, "leechers": 0
+            })).unwrap(),
+        }];
+
+        let handle = sender.send_editable_reply(100, 0, &Response::Notice("Searching…".to_string())).await.unwrap();
+        sender.edit(100, &handle, &Response::Results { results, locale: "en".into() }).await.unwrap();
+    }
+
+    fn found_result(uuid: &str, title: &str) -> FoundResult {
+        FoundResult {
+            uuid: uuid.to_string(),
+            search_result: serde_json::from_value::<SearchResult>(json!({
+                "guid": uuid, "indexerId": 1, "title": title, "size": 1, "publishDate": "2015-05-15T00:00:00Z",
+                "infoUrl": "info url", "seeders": 1, "leechers": 0
+            })).unwrap(),
+        }
+    }
+
+    #[tokio::test]
+    async fn long_results_are_split_between_embeds() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/channels/100/messages/200"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/channels/100/messages"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "id": "201" })))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        let sender = DiscordSender::new(Arc::new(
+            DiscordClient::new(Url::parse(&mock_server.uri()).unwrap(), "token".to_string())),
+            Arc::new(TitleSerializer));
+        let results: Vec<FoundResult> = (1..=10)
+            .map(|i| found_result(&i.to_string(), &format!("Ubuntu.{i}.{}", "x64.".repeat(250))))
+            .collect();
+
+        sender.edit(100, &"200".into(), &Response::Results { results, locale: "en".into() }).await.unwrap();
+
+        for request in mock_server.received_requests().await.unwrap() {
+            let body: Value = request.body_json().unwrap();
+            assert!(length(body["embeds"][0]["description"].as_str().unwrap()) <= MAX_EMBED_DESCRIPTION_LENGTH);
+        }
+    }
+
+    #[test]
+    fn long_text_is_split_between_lines() {
+        let text = format!("{}\n\n{}\n{}", "a".repeat(1500), "b".repeat(400), "c".repeat(4500));
+
+        let parts = split_text(&text, MAX_CONTENT_LENGTH);
+
+        assert_eq!(parts.iter().map(|part| length(part)).collect::<Vec<_>>(), vec![1902, 2000, 2000, 500]);
+        assert_eq!(parts[0], format!("{}\n\n{}", "a".repeat(1500), "b".repeat(400)));
+    }
+
+    #[test]
+    fn trackers_are_dropped_from_long_magnet() {
+        let magnet = format!("magnet:?xt=urn:btih:c811b41641a09d192b8ed81b14064fff55d85ce3&dn=Ubuntu{}",
+                             (0..100).map(|i| format!("&tr=udp://tracker{i}.example.org:1337/announce")).collect::<String>());
+
+        let fitted = fit_magnet(&magnet, MAX_CONTENT_LENGTH);
+
+        assert!(length(&fitted) <= MAX_CONTENT_LENGTH);
+        assert!(fitted.starts_with("magnet:?xt=urn:btih:c811b41641a09d192b8ed81b14064fff55d85ce3&dn=Ubuntu&tr=udp://tracker0."));
+        assert_eq!(fit_magnet("magnet:?xt=urn:btih:c811b41641a09d192b8ed81b14064fff55d85ce3", 10),
+                   "magnet:?xt=urn:btih:c811b41641a09d192b8ed81b14064fff55d85ce3");
+    }
+}
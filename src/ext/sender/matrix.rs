@@ -0,0 +1,261 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::core::response::Response;
+use crate::core::traits::input::{Destination, ReplyToMessage};
+use crate::core::traits::search_result_serializer::SearchResultSerializer;
+use crate::core::traits::sender::{MessageHandle, Sender};
+use crate::core::{HandlingError, HandlingResult};
+use crate::ext::input_handler::matrix::command_text;
+use crate::ext::matrix::MatrixClient;
+use crate::ext::search_result_serializer::matrix::escape_html;
+use crate::ext::sender::choices_text;
+
+pub struct MatrixSender {
+    client: Arc<MatrixClient>,
+    user_id: String,
+    search_result_serializer: Arc<dyn SearchResultSerializer>,
+}
+
+impl MatrixSender {
+    pub fn new(client: Arc<MatrixClient>, user_id: String, search_result_serializer: Arc<dyn SearchResultSerializer>) -> MatrixSender {
+        MatrixSender { client, user_id, search_result_serializer }
+    }
+
+    fn content(&self, response: &Response) -> Result<Value, HandlingError> {
+        match response {
+            Response::Results { results, locale } => Ok(html_content(&self.search_result_serializer.serialize_all(results, locale))),
+            Response::Notice(message) | Response::Error(message) => Ok(plain_content(message)),
+            Response::Magnet(link) => Ok(html_content(&format!("<pre><code>{}</code></pre>", escape_html(link)))),
+            Response::Choices { heading, choices } => Ok(plain_content(&choices_text(heading, choices, command_text))),
+            Response::File { .. } => Err(HandlingError::SendError("A file can't be sent as a text message".to_string())),
+        }
+    }
+
+    fn room(&self, destination: Destination) -> Result<String, HandlingError> {
+        self.client.rooms.name(destination)
+            .ok_or_else(|| HandlingError::SendError(format!("Unknown Matrix room {destination}")))
+    }
+
+    async fn send_content(&self, destination: Destination, content: Value) -> Result<MessageHandle, HandlingError> {
+        self.client.send_message(&self.room(destination)?, &content)
+            .await
+            .map(|event_id| event_id.into())
+            .map_err(|err| HandlingError::SendError(err.to_string()))
+    }
+
+    async fn edit_content(&self, destination: Destination, message_handle: &MessageHandle, new_content: Value) -> HandlingResult {
+        let mut content = json!({
+            "msgtype": "m.text",
+            "body": format!("* {}", new_content["body"].as_str().unwrap_or_default()),
+        });
+        if let Some(formatted_body) = new_content["formatted_body"].as_str() {
+            content["format"] = "org.matrix.custom.html".into();
+            content["formatted_body"] = format!("* {formatted_body}").into();
+        }
+        content["m.new_content"] = new_content;
+        content["m.relates_to"] = json!({ "rel_type": "m.replace", "event_id": message_handle.as_ref() });
+        self.send_content(destination, content).await.map(|_| {})
+    }
+}
+
+#[async_trait]
+impl Sender for MatrixSender {
+    async fn send_progress_indication(&self, destination: Destination) -> HandlingResult {
+        self.client.typing(&self.room(destination)?, &self.user_id)
+            .await
+            .map_err(|err| HandlingError::SendError(err.to_string()))
+    }
+
+    async fn send(&self, destination: Destination, response: &Response) -> HandlingResult {
+        if let Response::File { filename, content } = response {
+            let mime_type = mime_type(filename);
+            let content_uri = self.client.upload(filename, mime_type, content.clone())
+                .await
+                .map_err(|err| HandlingError::SendError(err.to_string()))?;
+            return self.send_content(destination, json!({
+                "msgtype": "m.file",
+                "body": filename,
+                "url": content_uri,
+                "info": { "mimetype": mime_type, "size": content.len() },
+            })).await.map(|_| {});
+        }
+        self.send_content(destination, self.content(response)?).await.map(|_| {})
+    }
+
+    async fn send_editable_reply(&self, destination: Destination, reply_to_message: ReplyToMessage, response: &Response) -> Result<MessageHandle, HandlingError> {
+        let mut content = self.content(response)?;
+        if let Some(event_id) = self.client.events.name(reply_to_message.into()) {
+            content["m.relates_to"] = json!({ "m.in_reply_to": { "event_id": event_id } });
+        }
+        self.send_content(destination, content).await
+    }
+
+    async fn edit(&self, destination: Destination, message_handle: &MessageHandle, response: &Response) -> HandlingResult {
+        self.edit_content(destination, message_handle, self.content(response)?).await
+    }
+}
+
+const TORRENT_MIME_TYPE: &str = "application/x-bittorrent";
+const NZB_MIME_TYPE: &str = "application/x-nzb";
+const DEFAULT_MIME_TYPE: &str = "application/octet-stream";
+
+/// Type of a sent file by its extension, so that clients open .torrent and .nzb files with the right apps.
+fn mime_type(filename: &str) -> &'static str {
+    match filename.rsplit_once('.').map(|(_, extension)| extension.to_lowercase()).as_deref() {
+        Some("torrent") => TORRENT_MIME_TYPE,
+        Some("nzb") => NZB_MIME_TYPE,
+        _ => DEFAULT_MIME_TYPE,
+    }
+}
+
+fn plain_content(message: &str) -> Value {
+    json!({ "msgtype": "m.text", "body": message })
+}
+
+fn html_content(message: &str) -> Value {
+    json!({
+        "msgtype": "m.text",
+        "body": html_to_plain(message),
+        "format": "org.matrix.custom.html",
+        "formatted_body": message,
+    })
+}
+
+/// Plain-text fallback for clients that can't render `formatted_body`.
+fn html_to_plain(html: &str) -> String {
+    let mut plain = String::with_capacity(html.len());
+    let mut inside_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => inside_tag = true,
+            '>' => inside_tag = false,
+            _ if !inside_tag => plain.push(c),
+            _ => {}
+        }
+    }
+    plain.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use bytes::Bytes;
+    use serde_json::json;
+    use url::Url;
+    use wiremock::matchers::{body_json, header, method, path, path_regex};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    use crate::core::prowlarr::SearchResult;
+    use crate::core::response::{FoundResult, Response};
+    use crate::core::traits::search_result_serializer::SearchResultSerializer;
+    use crate::core::traits::sender::Sender;
+    use crate::ext::matrix::MatrixClient;
+    use crate::ext::sender::matrix::{html_to_plain, MatrixSender};
+
+    const SEND_PATH: &str = r"^/_matrix/client/v3/rooms/!room:localhost/send/m\.room\.message/.+$";
+
+    fn sender(mock_server: &MockServer) -> (MatrixSender, i64) {
+        let client = Arc::new(MatrixClient::new(Url::parse(&mock_server.uri()).unwrap(), "token".to_string()));
+        let destination = client.rooms.id("!room:localhost");
+        (MatrixSender::new(client, "@bot:localhost".to_string(), Arc::new(TitleSerializer)), destination)
+    }
+
+    struct TitleSerializer;
+
+    impl SearchResultSerializer for TitleSerializer {
+        fn serialize(&self, search_result: &SearchResult, _bot_uuid: &str, _locale: &str) -> String {
+            format!("<b>{}</b>", search_result.title)
+        }
+    }
+
+    #[tokio::test]
+    async fn edit_message() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path_regex(SEND_PATH))
+            .and(body_json(json!({
+                "msgtype": "m.text",
+                "body": "* Title",
+                "format": "org.matrix.custom.html",
+                "formatted_body": "* <b>Title</b>",
+                "m.new_content": {
+                    "msgtype": "m.text",
+                    "body": "Title",
+                    "format": "org.matrix.custom.html",
+                    "formatted_body": "<b>Title</b>",
+                },
+                "m.relates_to": { "rel_type": "m.replace", "event_id": "$progress" },
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "event_id": "$edit" })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let (sender, destination) = sender(&mock_server);
+
+        let results = vec![FoundResult {
+            uuid: "uuid".to_string(),
+            search_result: serde_json::from_value::<SearchResult>(json!({
+                "guid": "1", "indexerId": 1, "title": "Title", "size": 1, "publishDate": "2015-05-15T00:00:00Z",
+                "infoUrl": "info url", "seeders": 1, "leechers": 0
+            })).unwrap(),
+        }];
+
+        sender.edit(destination, &"$progress".into(), &Response::Results { results, locale: "en".into() }).await.unwrap();
+    }
+
+    async fn assert_file_sent(filename: &str, mime_type: &str) {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/_matrix/media/v3/upload"))
+            .and(header("content-type", mime_type))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "content_uri": "mxc://localhost/abc" })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path_regex(SEND_PATH))
+            .and(body_json(json!({
+                "msgtype": "m.file",
+                "body": filename,
+                "url": "mxc://localhost/abc",
+                "info": { "mimetype": mime_type, "size": 8 },
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "event_id": "$file" })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        let (sender, destination) = sender(&mock_server);
+
+        sender.send(destination, &Response::File { filename: filename.to_string(), content: Bytes::from("contents") }).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn send_torrent_file() {
+        assert_file_sent("uuid.torrent", "application/x-bittorrent").await;
+    }
+
+    #[tokio::test]
+    async fn send_nzb_file() {
+        assert_file_sent("uuid.nzb", "application/x-nzb").await;
+    }
+
+    #[tokio::test]
+    async fn unknown_room() {
+        let mock_server = MockServer::start().await;
+        let (sender, destination) = sender(&mock_server);
+
+        assert!(sender.send(destination + 1, &Response::Notice("message".to_string())).await.is_err());
+    }
+
+    #[test]
+    fn plain_fallback() {
+        assert_eq!(html_to_plain("<b>Download</b>: !d_1<br>\nA &amp; B &lt;C&gt;"), "Download: !d_1\nA & B <C>");
+    }
+}
@@ -1 +1,49 @@
+use crate::core::response::{Choice, FoundResult};
+use crate::core::traits::input::{Command, Locale};
+use crate::core::traits::search_result_serializer::SearchResultSerializer;
+
 pub mod telegram;
+pub mod matrix;
+pub mod cli;
+#[cfg(feature = "discord")]
+pub mod discord;
+
+/// Plain text of choices, each followed by its command rendered with the front-end's `command_text`.
+fn choices_text(heading: &str, choices: &[Choice], command_text: fn(&Command) -> Option<String>) -> String {
+    choices.iter()
+        .fold(heading.to_string(), |text, choice| match choice.command.as_ref().and_then(command_text) {
+            Some(command) => format!("{text}\n\n{}\n{command}", choice.label),
+            None => format!("{text}\n\n{}", choice.label),
+        })
+}
+
+/// Packs serialized results into as few messages of at most `max_length` as possible, splitting only between results.
+/// A result that doesn't fit into a message even on its own gets its title shortened.
+/// `length` counts the length of a message the way the chat service does.
+fn split_results(serializer: &dyn SearchResultSerializer,
+                 results: &[FoundResult],
+                 locale: &Locale,
+                 max_length: usize,
+                 length: fn(&str) -> usize) -> Vec<String> {
+    let mut messages: Vec<String> = Vec::new();
+    for found in results {
+        let serialized = serialize_to_fit(serializer, found, locale, max_length, length);
+        match messages.last_mut() {
+            Some(message) if length(message) + length(&serialized) <= max_length => message.push_str(&serialized),
+            _ => messages.push(serialized),
+        }
+    }
+    messages
+}
+
+fn serialize_to_fit(serializer: &dyn SearchResultSerializer, found: &FoundResult, locale: &str, max_length: usize, length: fn(&str) -> usize) -> String {
+    let mut serialized = serializer.serialize(&found.search_result, &found.uuid, locale);
+    let mut search_result = found.search_result.clone();
+    let mut title_length = search_result.title.chars().count();
+    while length(&serialized) > max_length && title_length > 0 {
+        title_length = title_length.saturating_sub(length(&serialized) - max_length).saturating_sub(1);
+        search_result.title = found.search_result.title.chars().take(title_length).chain(Some('…')).collect();
+        serialized = serializer.serialize(&search_result, &found.uuid, locale);
+    }
+    serialized
+}
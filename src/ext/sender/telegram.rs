@@ -1,44 +1,80 @@
+use std::sync::Arc;
+
 use async_trait::async_trait;
-use bytes::Bytes;
-use teloxide::payloads::SendMessageSetters;
+use teloxide::payloads::{EditMessageTextSetters, SendMessageSetters};
 use teloxide::prelude::Requester;
 use teloxide::types::{ChatAction, ChatId, InputFile, LinkPreviewOptions, MessageId, ParseMode, ReplyParameters};
+use teloxide::utils::{html, markdown};
 use teloxide::Bot;
 
+use crate::core::response::Response;
 use crate::core::traits::input::{Destination, ReplyToMessage};
-use crate::core::traits::sender::Sender;
+use crate::core::traits::search_result_serializer::SearchResultSerializer;
+use crate::core::traits::sender::{MessageHandle, Sender};
 use crate::core::HandlingError;
 use crate::core::HandlingResult;
+use crate::ext::input_handler::telegram::command_text;
+use crate::ext::sender::{choices_text, split_results};
+
+/// Telegram rejects messages longer than 4096 characters, counted in UTF-16 code units.
+const MAX_MESSAGE_LENGTH: usize = 4096;
 
-#[derive(Clone)]
 pub struct TelegramSender {
-    bot: Bot
+    bot: Bot,
+    search_result_serializer: Arc<dyn SearchResultSerializer>,
+    parse_mode: ParseMode,
 }
 
+/// Text of a message and the parse mode it's formatted with, `None` for plain text.
+struct Text(String, Option<ParseMode>);
+
 impl TelegramSender {
-    pub fn from(bot: Bot) -> TelegramSender {
-        TelegramSender { bot }
+    /// `parse_mode` must be the one `search_result_serializer` formats results with.
+    pub fn new(bot: Bot, search_result_serializer: Arc<dyn SearchResultSerializer>, parse_mode: ParseMode) -> TelegramSender {
+        TelegramSender { bot, search_result_serializer, parse_mode }
     }
-}
 
-#[async_trait]
-impl Sender for TelegramSender {
-    async fn send_reply(&self, destination: Destination, reply_to_message: ReplyToMessage, message: &str) -> HandlingResult {
-        self.bot.send_message(ChatId(destination), message)
-            .reply_parameters(ReplyParameters::new(MessageId(reply_to_message)))
-            .parse_mode(ParseMode::MarkdownV2)
-            .link_preview_options(LinkPreviewOptions {
-                is_disabled: true,
-                url: None,
-                prefer_small_media: false,
-                prefer_large_media: false,
-                show_above_text: false,
-            })
+    /// Renders a response into one or more messages, as results that don't fit into one message are split between several.
+    fn texts(&self, response: &Response) -> Result<Vec<Text>, HandlingError> {
+        match response {
+            Response::Results { results, locale } =>
+                Ok(split_results(self.search_result_serializer.as_ref(), results, locale, MAX_MESSAGE_LENGTH, length)
+                    .into_iter()
+                    .map(|message| Text(message, Some(self.parse_mode)))
+                    .collect()),
+            Response::Notice(message) | Response::Error(message) => Ok(vec![Text(message.clone(), None)]),
+            Response::Magnet(link) => Ok(vec![Text(code_block(link, self.parse_mode), Some(self.parse_mode))]),
+            Response::Choices { heading, choices } => Ok(vec![Text(choices_text(heading, choices, command_text), None)]),
+            Response::File { .. } => Err(HandlingError::SendError("A file can't be sent as a text message".to_string())),
+        }
+    }
+
+    async fn send_text(&self, destination: Destination, reply_to_message: Option<ReplyToMessage>, text: Text) -> Result<MessageHandle, HandlingError> {
+        let Text(text, parse_mode) = text;
+        let mut request = self.bot.send_message(ChatId(destination), text)
+            .link_preview_options(disabled_link_preview());
+        if let Some(reply_to_message) = reply_to_message {
+            request = request.reply_parameters(ReplyParameters::new(MessageId(reply_to_message)));
+        }
+        if let Some(parse_mode) = parse_mode {
+            request = request.parse_mode(parse_mode);
+        }
+        request
             .await
-            .map(|_| {})
+            .map(|sent| sent.id.0.to_string().into())
             .map_err(|err| HandlingError::SendError(err.to_string()))
     }
 
+    async fn send_texts(&self, destination: Destination, texts: impl IntoIterator<Item = Text>) -> HandlingResult {
+        for text in texts {
+            self.send_text(destination, None, text).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Sender for TelegramSender {
     async fn send_progress_indication(&self, destination: Destination) -> HandlingResult {
         self.bot.send_chat_action(ChatId(destination), ChatAction::Typing)
             .await
@@ -46,35 +82,144 @@ impl Sender for TelegramSender {
             .map_err(|err| HandlingError::SendError(err.to_string()))
     }
 
-    async fn send_plain_message(&self, destination: Destination, message: &str) -> HandlingResult {
-        self.bot.send_message(ChatId(destination), message)
-            .await
-            .map(|_| {})
-            .map_err(|err| HandlingError::SendError(err.to_string()))
+    async fn send(&self, destination: Destination, response: &Response) -> HandlingResult {
+        if let Response::File { filename, content } = response {
+            let file = InputFile::memory(content.clone())
+                .file_name(filename.clone());
+            return self.bot.send_document(ChatId(destination), file)
+                .await
+                .map(|_| {})
+                .map_err(|err| HandlingError::SendError(err.to_string()));
+        }
+        self.send_texts(destination, self.texts(response)?).await
     }
 
-    async fn send_plain_reply(&self, destination: Destination, reply_to_message: ReplyToMessage, message: &str) -> HandlingResult {
-        self.bot.send_message(ChatId(destination), message)
-            .reply_parameters(ReplyParameters::new(MessageId(reply_to_message)))
-            .await
-            .map(|_| {})
-            .map_err(|err| HandlingError::SendError(err.to_string()))
+    /// If the response is split into several messages, only the first one is a reply and can be edited.
+    async fn send_editable_reply(&self, destination: Destination, reply_to_message: ReplyToMessage, response: &Response) -> Result<MessageHandle, HandlingError> {
+        let mut texts = self.texts(response)?.into_iter();
+        let first = texts.next()
+            .ok_or_else(|| HandlingError::SendError("Nothing to send".to_string()))?;
+        let message_handle = self.send_text(destination, Some(reply_to_message), first).await?;
+        self.send_texts(destination, texts).await?;
+        Ok(message_handle)
     }
 
-    async fn send_magnet(&self, destination: Destination, link: &str) -> HandlingResult {
-        self.bot.send_message(ChatId(destination), format!("```\n{}\n```", link))
-            .parse_mode(ParseMode::MarkdownV2)
+    /// If the response is split into several messages, the first one replaces the message and the rest are sent after it.
+    async fn edit(&self, destination: Destination, message_handle: &MessageHandle, response: &Response) -> HandlingResult {
+        let mut texts = self.texts(response)?.into_iter();
+        let Some(Text(text, parse_mode)) = texts.next() else {
+            return Ok(());
+        };
+        let mut request = self.bot.edit_message_text(ChatId(destination), to_message_id(message_handle)?, text)
+            .link_preview_options(disabled_link_preview());
+        if let Some(parse_mode) = parse_mode {
+            request = request.parse_mode(parse_mode);
+        }
+        request
             .await
-            .map(|_| {})
-            .map_err(|err| HandlingError::SendError(err.to_string()))
+            .map_err(|err| HandlingError::SendError(err.to_string()))?;
+        self.send_texts(destination, texts).await
     }
+}
 
-    async fn send_torrent_file(&self, destination: Destination, filename: &str, file: Bytes) -> HandlingResult {
-        let file = InputFile::memory(file)
-            .file_name(filename.to_string());
-        self.bot.send_document(ChatId(destination), file)
-            .await
-            .map(|_| {})
-            .map_err(|err| HandlingError::SendError(err.to_string()))
+fn length(message: &str) -> usize {
+    message.encode_utf16().count()
+}
+
+fn code_block(code: &str, parse_mode: ParseMode) -> String {
+    match parse_mode {
+        ParseMode::Html => html::code_block(code),
+        _ => markdown::code_block(code),
+    }
+}
+
+fn disabled_link_preview() -> LinkPreviewOptions {
+    LinkPreviewOptions {
+        is_disabled: true,
+        url: None,
+        prefer_small_media: false,
+        prefer_large_media: false,
+        show_above_text: false,
+    }
+}
+
+fn to_message_id(message_handle: &MessageHandle) -> Result<MessageId, HandlingError> {
+    message_handle.parse()
+        .map(MessageId)
+        .map_err(|_| HandlingError::SendError(format!("Unexpected message handle \"{message_handle}\"")))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use crate::core::prowlarr::{Protocol, SearchResult};
+    use crate::core::response::FoundResult;
+    use crate::ext::search_result_serializer::telegram::TgSearchResultSerializer;
+    use crate::ext::sender::split_results;
+    use crate::ext::sender::telegram::{length, MAX_MESSAGE_LENGTH};
+
+    fn found_result(uuid: &str, title: &str) -> FoundResult {
+        FoundResult {
+            uuid: uuid.to_string(),
+            search_result: SearchResult {
+                guid: uuid.to_string(),
+                indexer_id: 2,
+                indexer: None,
+                title: title.to_string(),
+                size: 1234567,
+                publish_date: DateTime::from_timestamp(1431648000, 0).unwrap(),
+                download_url: None,
+                magnet_url: None,
+                info_url: "http://localhost/ubuntu".to_string(),
+                seeders: 20,
+                leechers: 10,
+                grabs: Some(10000),
+                categories: vec![],
+                indexer_flags: vec![],
+                imdb_id: 0,
+                info_hash: None,
+                protocol: Protocol::Torrent,
+                other_indexers: vec![],
+            },
+        }
+    }
+
+    #[test]
+    fn short_results_fit_into_one_message() {
+        let results = vec![found_result("1", "Ubuntu"), found_result("2", "Debian")];
+
+        let messages = split_results(&TgSearchResultSerializer::new(None), &results, &"en".into(), MAX_MESSAGE_LENGTH, length);
+
+        assert_eq!(messages.len(), 1);
+        assert!(messages[0].contains("/d\\_1") && messages[0].contains("/d\\_2"));
+    }
+
+    #[test]
+    fn long_results_are_split_between_results() {
+        let results: Vec<FoundResult> = (1..=10)
+            .map(|i| found_result(&i.to_string(), &format!("Ubuntu.{i}.{}", "x64.".repeat(250))))
+            .collect();
+
+        let messages = split_results(&TgSearchResultSerializer::new(None), &results, &"en".into(), MAX_MESSAGE_LENGTH, length);
+
+        assert!(messages.len() > 1);
+        assert!(messages.iter().all(|message| length(message) <= MAX_MESSAGE_LENGTH));
+        assert!(messages.iter().all(|message| message.ends_with("\n\n")));
+        for i in 1..=10 {
+            assert_eq!(messages.iter().filter(|message| message.contains(&format!("/d\\_{i}\n"))).count(), 1);
+        }
+    }
+
+    #[test]
+    fn too_long_title_is_shortened() {
+        let results = vec![found_result("1", &"Ubuntu\u{1F427}.".repeat(1000))];
+
+        let messages = split_results(&TgSearchResultSerializer::new(None), &results, &"en".into(), MAX_MESSAGE_LENGTH, length);
+
+        assert_eq!(messages.len(), 1);
+        assert!(length(&messages[0]) <= MAX_MESSAGE_LENGTH);
+        assert!(messages[0].contains("…\n"));
+        assert!(messages[0].contains("/d\\_1"));
     }
 }
@@ -11,7 +11,7 @@ mod in_memory;
 mod redis;
 
 #[cfg(feature = "redis-storage")]
-const REDIS_URL_ENV: &str = "REDIS_URL";
+pub const REDIS_URL_ENV: &str = "REDIS_URL";
 
 pub fn create<
     #[cfg(feature = "redis-storage")] V: Clone + Sync + Send + Serialize + DeserializeOwned + 'static,
@@ -0,0 +1,18 @@
+use crate::core::traits::watch_storage::WatchStorage;
+#[cfg(feature = "redis-storage")]
+use crate::ext::uuid_mapper::REDIS_URL_ENV;
+#[cfg(feature = "redis-storage")]
+use crate::ext::watch_storage::redis::RedisWatchStorage;
+
+#[cfg(feature = "redis-storage")]
+mod redis;
+
+/// Keeps saved searches in Redis if it's configured. `None` otherwise, when they're only kept in memory.
+pub fn create() -> Option<Box<dyn WatchStorage>> {
+    #[cfg(feature = "redis-storage")]
+    if let Ok(redis_url) = std::env::var(REDIS_URL_ENV) {
+        return Some(Box::new(RedisWatchStorage::new(&redis_url)
+            .unwrap_or_else(|e| panic!("Cannot create Redis client from {REDIS_URL_ENV}=\"{redis_url}\": {e}"))));
+    }
+    None
+}
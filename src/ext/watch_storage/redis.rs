@@ -0,0 +1,49 @@
+use async_trait::async_trait;
+use redis::AsyncCommands;
+
+use crate::core::traits::watch_storage::WatchStorage;
+use crate::core::watches::{Watch, WatchId};
+
+/// Hash of saved searches as JSON by their ids.
+const WATCHES_KEY: &str = "watches";
+
+pub struct RedisWatchStorage {
+    client: redis::Client,
+}
+
+impl RedisWatchStorage {
+    pub fn new(url: &str) -> Result<RedisWatchStorage, String> {
+        Ok(RedisWatchStorage {
+            client: redis::Client::open(url)
+                .map_err(|e| e.to_string())?,
+        })
+    }
+}
+
+#[async_trait]
+impl WatchStorage for RedisWatchStorage {
+    async fn load(&self) -> Result<Vec<(WatchId, Watch)>, String> {
+        let mut con = self.client.get_multiplexed_async_connection().await.map_err(|e| e.to_string())?;
+        let values: Vec<(WatchId, String)> = con.hgetall(WATCHES_KEY).await.map_err(|e| e.to_string())?;
+        values.into_iter()
+            .map(|(id, value)| serde_json::from_str(&value)
+                .map(|watch| (id, watch))
+                .map_err(|e| format!("Could not read saved search {id}: {e}")))
+            .collect()
+    }
+
+    async fn save(&self, id: WatchId, watch: &Watch) -> Result<(), String> {
+        let mut con = self.client.get_multiplexed_async_connection().await.map_err(|e| e.to_string())?;
+        let value = serde_json::to_string(watch).map_err(|e| e.to_string())?;
+        con.hset::<_, _, _, ()>(WATCHES_KEY, id, value)
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    async fn remove(&self, id: WatchId) -> Result<(), String> {
+        let mut con = self.client.get_multiplexed_async_connection().await.map_err(|e| e.to_string())?;
+        con.hdel::<_, _, ()>(WATCHES_KEY, id)
+            .await
+            .map_err(|e| e.to_string())
+    }
+}
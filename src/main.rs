@@ -4,42 +4,227 @@ extern crate rust_i18n;
 use std::env;
 use std::sync::Arc;
 
+use teloxide::prelude::Requester;
+use teloxide::types::ParseMode;
+use teloxide::utils::{html, markdown};
 use teloxide::Bot;
 
+use crate::core::arr::{ArrClient, ArrKind};
 use crate::core::downloads_tracker::DownloadsTracker;
+use crate::core::health;
 use crate::core::input_handler::InputHandler;
+use crate::core::library::{Library, LibraryItem};
 use crate::core::prowlarr::ProwlarrClient;
+use crate::core::suspicious::SuspiciousPolicy;
 use crate::core::torrent_meta::TorrentMeta;
+use crate::core::traits::input::Locale;
+use crate::core::watches::Watches;
+use crate::core::traits::search_result_serializer::SearchResultSerializer;
+#[cfg(feature = "discord")]
+use crate::ext::discord::DiscordClient;
+use crate::ext::download_client::qbittorrent::QBittorrentClient;
+use crate::ext::matrix::MatrixClient;
+use crate::ext::search_cache;
+use crate::ext::search_result_serializer::cli::CliSearchResultSerializer;
+#[cfg(feature = "discord")]
+use crate::ext::search_result_serializer::discord::DiscordSearchResultSerializer;
+use crate::ext::search_result_serializer::matrix::MatrixSearchResultSerializer;
 use crate::ext::search_result_serializer::telegram::TgSearchResultSerializer;
+use crate::ext::search_result_serializer::telegram_html::TgHtmlSearchResultSerializer;
+use crate::ext::search_result_serializer::template::TemplateSearchResultSerializer;
+#[cfg(feature = "discord")]
+use crate::ext::sender::discord::DiscordSender;
+use crate::ext::sender::cli::CliSender;
+use crate::ext::sender::matrix::MatrixSender;
 use crate::ext::sender::telegram::TelegramSender;
 use crate::ext::uuid_mapper;
+use crate::ext::watch_storage;
 
 mod core;
 mod ext;
 
 i18n!("locales", fallback = "en");
 
+/// Locale of messages that aren't replies to a user, such as health alerts, unless the front-end has its own.
+const DEFAULT_LOCALE: &str = "en";
+const CLI_ARG: &str = "--cli";
+const CLI_LOCALE_ENV: &str = "CLI_LOCALE";
+const FRONTEND_ENV: &str = "FRONTEND";
+const MATRIX_ALLOWED_USERS_ENV: &str = "MATRIX_ALLOWED_USERS";
+const MATRIX_LOCALE_ENV: &str = "MATRIX_LOCALE";
+const TELEGRAM_PARSE_MODE_ENV: &str = "TELEGRAM_PARSE_MODE";
+
 #[tokio::main]
 async fn main() {
     if env::var_os("RUST_LOG").is_none() {
         env::set_var("RUST_LOG", "info");
     }
     env_logger::init();
-    let bot = Bot::from_env();
     let downloads_tracker = Arc::new(DownloadsTracker::new());
+    if env::args().any(|arg| arg == CLI_ARG) {
+        return run_cli(downloads_tracker).await;
+    }
+    match env::var(FRONTEND_ENV).as_deref() {
+        Err(_) | Ok("telegram") => run_telegram(downloads_tracker).await,
+        Ok("matrix") => run_matrix(downloads_tracker).await,
+        #[cfg(feature = "discord")]
+        Ok("discord") => run_discord(downloads_tracker).await,
+        Ok(frontend) => panic!("{FRONTEND_ENV} value \"{frontend}\" is unexpected or isn't supported by this build"),
+    }
+}
+
+async fn run_telegram(downloads_tracker: Arc<DownloadsTracker>) {
+    let bot = Bot::from_env();
+    let bot_username = get_bot_username(&bot).await;
+    let (search_result_serializer, parse_mode): (Arc<dyn SearchResultSerializer>, ParseMode) =
+        match env::var(TELEGRAM_PARSE_MODE_ENV).as_deref() {
+            Err(_) | Ok("markdown") => (templated_or(TgSearchResultSerializer::new(bot_username), markdown::escape),
+                                        ParseMode::MarkdownV2),
+            Ok("html") => (templated_or(TgHtmlSearchResultSerializer::new(bot_username), html::escape), ParseMode::Html),
+            Ok(parse_mode) => panic!("{TELEGRAM_PARSE_MODE_ENV} value \"{parse_mode}\" is unexpected, \
+                it must be either markdown or html"),
+        };
+    let sender = Arc::new(TelegramSender::new(bot.clone(), search_result_serializer, parse_mode));
 
-    let input_handler = InputHandler::new(
-        ProwlarrClient::from_env(),
+    let input_handler = Arc::new(with_integrations(InputHandler::new(
+        prowlarr(),
         uuid_mapper::create::<TorrentMeta>(),
         downloads_tracker.clone(),
         get_allowed_users(),
-        Box::new(TelegramSender::from(bot.clone())),
-        Box::new(TgSearchResultSerializer)
-    );
+        sender.clone()
+    )).await);
+
+    let notification_channels = ext::notifier::from_env(|user| user.parse().ok());
+    let health_alert_chats = health::alert_chats_from_env(|chat| chat.parse().ok());
+
+    tokio::join!(
+        ext::input_handler::telegram::run(bot, input_handler.clone()),
+        ext::api::web::run(input_handler.clone()),
+        health::run(input_handler.clone(), health_alert_chats, DEFAULT_LOCALE.into()),
+        core::watches::run(input_handler),
+        ext::completion::web::run(sender, downloads_tracker, notification_channels));
+}
+
+async fn run_matrix(downloads_tracker: Arc<DownloadsTracker>) {
+    let client = Arc::new(MatrixClient::from_env());
+    let bot_user_id = client.whoami()
+        .await
+        .unwrap_or_else(|err| panic!("Could not get the Matrix bot user: {err}"));
+    let allowed_users: Vec<u64> = env::var(MATRIX_ALLOWED_USERS_ENV)
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|user| !user.is_empty())
+        .map(|user| client.users.id(user) as u64)
+        .collect();
+    let sender = Arc::new(MatrixSender::new(client.clone(), bot_user_id.clone(), templated_or(MatrixSearchResultSerializer, ext::search_result_serializer::matrix::escape_html)));
+
+    let input_handler = Arc::new(with_integrations(InputHandler::new(
+        prowlarr(),
+        uuid_mapper::create::<TorrentMeta>(),
+        downloads_tracker.clone(),
+        allowed_users.clone(),
+        sender.clone()
+    )).await);
+
+    let notification_channels = ext::notifier::from_env(|user| Some(client.users.id(user) as u64));
+    let health_alert_chats = health::alert_chats_from_env(|room| Some(client.rooms.id(room)));
+    let locale: Locale = env::var(MATRIX_LOCALE_ENV).unwrap_or_else(|_| DEFAULT_LOCALE.to_string()).into();
 
     tokio::join!(
-        ext::input_handler::telegram::run(bot.clone(), input_handler),
-        ext::completion::web::run(Arc::new(TelegramSender::from(bot)), downloads_tracker));
+        ext::input_handler::matrix::run(client, bot_user_id, allowed_users, locale.clone(), input_handler.clone()),
+        ext::api::web::run(input_handler.clone()),
+        health::run(input_handler.clone(), health_alert_chats, locale),
+        core::watches::run(input_handler),
+        ext::completion::web::run(sender, downloads_tracker, notification_channels));
+}
+
+#[cfg(feature = "discord")]
+async fn run_discord(downloads_tracker: Arc<DownloadsTracker>) {
+    let client = Arc::new(DiscordClient::from_env());
+    let sender = Arc::new(DiscordSender::new(client.clone(), templated_or(DiscordSearchResultSerializer, ext::search_result_serializer::discord::escape_markdown)));
+
+    let input_handler = Arc::new(with_integrations(InputHandler::new(
+        prowlarr(),
+        uuid_mapper::create::<TorrentMeta>(),
+        downloads_tracker.clone(),
+        get_allowed_users(),
+        sender.clone()
+    )).await);
+
+    let notification_channels = ext::notifier::from_env(|user| user.parse().ok());
+    let health_alert_chats = health::alert_chats_from_env(|channel| channel.parse().ok());
+
+    tokio::join!(
+        ext::input_handler::discord::run(client, input_handler.clone()),
+        ext::api::web::run(input_handler.clone()),
+        health::run(input_handler.clone(), health_alert_chats, DEFAULT_LOCALE.into()),
+        core::watches::run(input_handler),
+        ext::completion::web::run(sender, downloads_tracker, notification_channels));
+}
+
+/// The serializer of `RESULT_TEMPLATE_FILE` if it's set, with values escaped by `escape`, otherwise `default`.
+fn templated_or(default: impl SearchResultSerializer + 'static, escape: fn(&str) -> String) -> Arc<dyn SearchResultSerializer> {
+    match TemplateSearchResultSerializer::from_env(escape) {
+        Some(template) => Arc::new(template),
+        None => Arc::new(default),
+    }
+}
+
+/// Enables the optional integrations configured by env variables and restores saved searches.
+async fn with_integrations(input_handler: InputHandler) -> InputHandler {
+    let input_handler = input_handler
+        .with_library(library())
+        .with_suspicious_policy(SuspiciousPolicy::from_env());
+    let input_handler = match watch_storage::create() {
+        Some(storage) => input_handler.with_watches(Watches::restore(storage).await),
+        None => input_handler,
+    };
+    match QBittorrentClient::from_env() {
+        Some(download_client) => input_handler.with_download_client(Box::new(download_client)),
+        None => input_handler,
+    }
+}
+
+fn prowlarr() -> ProwlarrClient {
+    let prowlarr = ProwlarrClient::from_env();
+    match search_cache::create() {
+        Some(cache) => prowlarr.with_cache(cache),
+        None => prowlarr,
+    }
+}
+
+fn library() -> Library {
+    Library::new(ArrClient::from_env(ArrKind::Sonarr),
+                 ArrClient::from_env(ArrKind::Radarr),
+                 uuid_mapper::create::<LibraryItem>())
+}
+
+/// Reads commands from stdin and prints responses to stdout, to try the bot out without a chat account.
+async fn run_cli(downloads_tracker: Arc<DownloadsTracker>) {
+    let input_handler = Arc::new(with_integrations(InputHandler::new(
+        prowlarr(),
+        uuid_mapper::create::<TorrentMeta>(),
+        downloads_tracker,
+        vec![],
+        Arc::new(CliSender::new(std::io::stdout(),
+                                env::current_dir().unwrap_or_default(),
+                                templated_or(CliSearchResultSerializer, str::to_string)))
+    )).await);
+
+    ext::input_handler::cli::run(tokio::io::BufReader::new(tokio::io::stdin()),
+                                 env::var(CLI_LOCALE_ENV).unwrap_or_else(|_| DEFAULT_LOCALE.to_string()).into(),
+                                 input_handler).await;
+}
+
+async fn get_bot_username(bot: &Bot) -> Option<String> {
+    match bot.get_me().await {
+        Ok(me) => me.user.username,
+        Err(err) => {
+            log::warn!("Could not get bot username, share links won't be available: {}", err);
+            None
+        }
+    }
 }
 
 fn get_allowed_users() -> Vec<u64> {